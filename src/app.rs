@@ -1,18 +1,24 @@
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 use anyhow::Result;
-use glob::glob;
+use glob::{glob, Pattern};
 use serde::{Deserialize, Serialize};
 
+use crate::config::{Config, RuleSet};
+use crate::filter::{parse_filter, FileFilter, PreparedFilter};
+use crate::hash::{HashCache, HashJob};
 use crate::operations::RenamePreview;
+use crate::template::DEFAULT_DATE_FORMAT;
 
 /// Result of handling a key event
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppResult {
     Continue,
     Quit,
+    /// Suspend the TUI and edit the names in $EDITOR
+    Edit,
 }
 
 /// Which panel is currently focused
@@ -21,6 +27,8 @@ pub enum FocusedPanel {
     Files,
     SearchField,
     ReplaceField,
+    /// Filter expression above the file list
+    FilterField,
 }
 
 /// Dialog state
@@ -84,6 +92,344 @@ impl DatePosition {
     }
 }
 
+/// Action for position based editing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PositionAction {
+    /// Insert text at a position
+    #[default]
+    Insert,
+    /// Delete a character range
+    Delete,
+    /// Keep only a character range
+    Keep,
+    /// Cut everything from the Nth occurrence of a delimiter
+    CutAfter,
+}
+
+impl PositionAction {
+    pub fn next(&self) -> Self {
+        match self {
+            PositionAction::Insert => PositionAction::Delete,
+            PositionAction::Delete => PositionAction::Keep,
+            PositionAction::Keep => PositionAction::CutAfter,
+            PositionAction::CutAfter => PositionAction::Insert,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            PositionAction::Insert => "Einfuegen",
+            PositionAction::Delete => "Loeschen",
+            PositionAction::Keep => "Behalten",
+            PositionAction::CutAfter => "Abschneiden ab",
+        }
+    }
+
+    /// Check if this action has a second input (position or occurrence)
+    pub fn uses_second_input(&self) -> bool {
+        matches!(self, PositionAction::Insert | PositionAction::CutAfter)
+    }
+}
+
+/// Target file system rules for sanitize mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SanitizeProfile {
+    /// POSIX portable filename character set (A-Z a-z 0-9 . _ -)
+    #[default]
+    Posix,
+    /// Windows/NTFS: no <>:"|?*, reserved names, no trailing dots
+    Windows,
+    /// FAT32/exFAT: Windows rules plus +,;=[] for old devices
+    Fat,
+    /// URL-safe: lowercase, words separated by '-'
+    Url,
+}
+
+impl SanitizeProfile {
+    pub fn next(&self) -> Self {
+        match self {
+            SanitizeProfile::Posix => SanitizeProfile::Windows,
+            SanitizeProfile::Windows => SanitizeProfile::Fat,
+            SanitizeProfile::Fat => SanitizeProfile::Url,
+            SanitizeProfile::Url => SanitizeProfile::Posix,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            SanitizeProfile::Posix => "POSIX",
+            SanitizeProfile::Windows => "Windows/NTFS",
+            SanitizeProfile::Fat => "FAT32/exFAT",
+            SanitizeProfile::Url => "URL",
+        }
+    }
+}
+
+/// Action for extension mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ExtensionAction {
+    /// Replace the extension
+    #[default]
+    Set,
+    /// Lowercase the extension
+    Lowercase,
+    /// Lowercase and map aliases (jpeg -> jpg)
+    Normalize,
+    /// Remove the extension
+    Remove,
+    /// Append an extension
+    Add,
+}
+
+impl ExtensionAction {
+    pub fn next(&self) -> Self {
+        match self {
+            ExtensionAction::Set => ExtensionAction::Lowercase,
+            ExtensionAction::Lowercase => ExtensionAction::Normalize,
+            ExtensionAction::Normalize => ExtensionAction::Remove,
+            ExtensionAction::Remove => ExtensionAction::Add,
+            ExtensionAction::Add => ExtensionAction::Set,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ExtensionAction::Set => "Setzen",
+            ExtensionAction::Lowercase => "Kleinschreiben",
+            ExtensionAction::Normalize => "Normalisieren",
+            ExtensionAction::Remove => "Entfernen",
+            ExtensionAction::Add => "Hinzufuegen",
+        }
+    }
+
+    /// Check if this action needs an extension as input
+    pub fn uses_value(&self) -> bool {
+        matches!(self, ExtensionAction::Set | ExtensionAction::Add)
+    }
+}
+
+/// Options for regex mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RegexFlags {
+    /// Match without regard to case (i)
+    #[serde(default)]
+    pub ignore_case: bool,
+    /// ^ and $ match at line breaks (m)
+    #[serde(default)]
+    pub multi_line: bool,
+    /// Replace only this match (1 = first), 0 replaces all matches
+    #[serde(default)]
+    pub occurrence: usize,
+}
+
+impl RegexFlags {
+    /// Highest occurrence reachable by cycling in the TUI
+    const MAX_CYCLE_OCCURRENCE: usize = 5;
+
+    /// Cycle the replaced match: all, 1st, 2nd, ... 5th
+    pub fn next_occurrence(&self) -> Self {
+        let occurrence = if self.occurrence >= Self::MAX_CYCLE_OCCURRENCE {
+            0
+        } else {
+            self.occurrence + 1
+        };
+        Self {
+            occurrence,
+            ..*self
+        }
+    }
+
+    pub fn occurrence_name(&self) -> String {
+        match self.occurrence {
+            0 => "Alle".to_string(),
+            1 => "Erster".to_string(),
+            n => format!("{}.", n),
+        }
+    }
+
+    /// Short flag notation, e.g. "i,m,2" or "-" without flags
+    pub fn display(&self) -> String {
+        let mut flags = Vec::new();
+        if self.ignore_case {
+            flags.push("i".to_string());
+        }
+        if self.multi_line {
+            flags.push("m".to_string());
+        }
+        if self.occurrence > 0 {
+            flags.push(self.occurrence.to_string());
+        }
+        if flags.is_empty() {
+            "-".to_string()
+        } else {
+            flags.join(",")
+        }
+    }
+}
+
+/// Counter format for numbering mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NumberFormat {
+    /// 1, 2, 3 (zero-padded to the number of #)
+    #[default]
+    Decimal,
+    /// a, b, ... z, aa, ab
+    Alpha,
+    /// I, II, III, IV
+    Roman,
+    /// Lowercase hexadecimal (zero-padded)
+    Hex,
+}
+
+impl NumberFormat {
+    pub fn next(&self) -> Self {
+        match self {
+            NumberFormat::Decimal => NumberFormat::Alpha,
+            NumberFormat::Alpha => NumberFormat::Roman,
+            NumberFormat::Roman => NumberFormat::Hex,
+            NumberFormat::Hex => NumberFormat::Decimal,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            NumberFormat::Decimal => "Dezimal",
+            NumberFormat::Alpha => "Buchstaben",
+            NumberFormat::Roman => "Roemisch",
+            NumberFormat::Hex => "Hex",
+        }
+    }
+}
+
+/// Which digit runs of a name are padded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NumberTarget {
+    /// First number in the name
+    First,
+    /// Last number in the name
+    #[default]
+    Last,
+    /// Every number in the name
+    All,
+}
+
+impl NumberTarget {
+    pub fn next(&self) -> Self {
+        match self {
+            NumberTarget::First => NumberTarget::Last,
+            NumberTarget::Last => NumberTarget::All,
+            NumberTarget::All => NumberTarget::First,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            NumberTarget::First => "Erste",
+            NumberTarget::Last => "Letzte",
+            NumberTarget::All => "Alle",
+        }
+    }
+}
+
+/// Files that share a counter in numbering and template mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CounterGroup {
+    /// One counter for all files
+    #[default]
+    All,
+    /// Own counter per extension
+    Extension,
+    /// Own counter per parent directory
+    Directory,
+    /// Own counter per day of the file date (see date source)
+    Day,
+}
+
+impl CounterGroup {
+    pub fn next(&self) -> Self {
+        match self {
+            CounterGroup::All => CounterGroup::Extension,
+            CounterGroup::Extension => CounterGroup::Directory,
+            CounterGroup::Directory => CounterGroup::Day,
+            CounterGroup::Day => CounterGroup::All,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            CounterGroup::All => "Alle",
+            CounterGroup::Extension => "Pro Endung",
+            CounterGroup::Directory => "Pro Ordner",
+            CounterGroup::Day => "Pro Tag",
+        }
+    }
+}
+
+/// Where the date for date insertion comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DateSource {
+    /// Modification time
+    #[default]
+    Modified,
+    /// Status change time (ctime)
+    Changed,
+    /// Birth time
+    Created,
+    /// EXIF DateTimeOriginal
+    Exif,
+    /// Current time
+    Now,
+}
+
+impl DateSource {
+    pub fn next(&self) -> Self {
+        match self {
+            DateSource::Modified => DateSource::Changed,
+            DateSource::Changed => DateSource::Created,
+            DateSource::Created => DateSource::Exif,
+            DateSource::Exif => DateSource::Now,
+            DateSource::Now => DateSource::Modified,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            DateSource::Modified => "Aenderungsdatum",
+            DateSource::Changed => "Statusaenderung",
+            DateSource::Created => "Erstellungsdatum",
+            DateSource::Exif => "EXIF Aufnahmedatum",
+            DateSource::Now => "Jetzt",
+        }
+    }
+}
+
+/// Checksum algorithm for hash mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+    Crc32,
+}
+
+impl HashAlgorithm {
+    pub fn next(&self) -> Self {
+        match self {
+            HashAlgorithm::Sha256 => HashAlgorithm::Blake3,
+            HashAlgorithm::Blake3 => HashAlgorithm::Crc32,
+            HashAlgorithm::Crc32 => HashAlgorithm::Sha256,
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "SHA-256",
+            HashAlgorithm::Blake3 => "BLAKE3",
+            HashAlgorithm::Crc32 => "CRC32",
+        }
+    }
+}
+
 /// Rename operation mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum RenameMode {
@@ -91,12 +437,31 @@ pub enum RenameMode {
     SearchReplace,
     Regex,
     Numbering,
+    PadNumbers,
     Prefix,
     Suffix,
+    Position,
     DateInsert,
+    Template,
+    AudioTags,
+    Script,
+    /// Template whose result may contain subfolders (files are moved)
+    Organize,
+    /// Normalizes TV episode and movie release names
+    Media,
+    Extension,
+    FixExtension,
+    Hash,
+    Sanitize,
     Uppercase,
     Lowercase,
     TitleCase,
+    SnakeCase,
+    KebabCase,
+    CamelCase,
+    PascalCase,
+    DotCase,
+    SentenceCase,
 }
 
 impl RenameMode {
@@ -105,13 +470,30 @@ impl RenameMode {
         match self {
             RenameMode::SearchReplace => RenameMode::Regex,
             RenameMode::Regex => RenameMode::Numbering,
-            RenameMode::Numbering => RenameMode::Prefix,
+            RenameMode::Numbering => RenameMode::PadNumbers,
+            RenameMode::PadNumbers => RenameMode::Prefix,
             RenameMode::Prefix => RenameMode::Suffix,
-            RenameMode::Suffix => RenameMode::DateInsert,
-            RenameMode::DateInsert => RenameMode::Uppercase,
+            RenameMode::Suffix => RenameMode::Position,
+            RenameMode::Position => RenameMode::DateInsert,
+            RenameMode::DateInsert => RenameMode::Template,
+            RenameMode::Template => RenameMode::AudioTags,
+            RenameMode::AudioTags => RenameMode::Script,
+            RenameMode::Script => RenameMode::Organize,
+            RenameMode::Organize => RenameMode::Media,
+            RenameMode::Media => RenameMode::Extension,
+            RenameMode::Extension => RenameMode::FixExtension,
+            RenameMode::FixExtension => RenameMode::Hash,
+            RenameMode::Hash => RenameMode::Sanitize,
+            RenameMode::Sanitize => RenameMode::Uppercase,
             RenameMode::Uppercase => RenameMode::Lowercase,
             RenameMode::Lowercase => RenameMode::TitleCase,
-            RenameMode::TitleCase => RenameMode::SearchReplace,
+            RenameMode::TitleCase => RenameMode::SnakeCase,
+            RenameMode::SnakeCase => RenameMode::KebabCase,
+            RenameMode::KebabCase => RenameMode::CamelCase,
+            RenameMode::CamelCase => RenameMode::PascalCase,
+            RenameMode::PascalCase => RenameMode::DotCase,
+            RenameMode::DotCase => RenameMode::SentenceCase,
+            RenameMode::SentenceCase => RenameMode::SearchReplace,
         }
     }
 
@@ -121,12 +503,29 @@ impl RenameMode {
             RenameMode::SearchReplace => "Suchen/Ersetzen",
             RenameMode::Regex => "Regex",
             RenameMode::Numbering => "Nummerierung",
+            RenameMode::PadNumbers => "Nummern auffuellen",
             RenameMode::Prefix => "Prefix",
             RenameMode::Suffix => "Suffix",
+            RenameMode::Position => "Position",
             RenameMode::DateInsert => "Datum einfuegen",
+            RenameMode::Template => "Template",
+            RenameMode::AudioTags => "Audio-Tags",
+            RenameMode::Script => "Skript",
+            RenameMode::Organize => "Ordnen",
+            RenameMode::Media => "Serien/Filme",
+            RenameMode::Extension => "Dateiendung",
+            RenameMode::FixExtension => "Typ-Erkennung",
+            RenameMode::Hash => "Pruefsumme",
+            RenameMode::Sanitize => "Bereinigen",
             RenameMode::Uppercase => "GROSSBUCHSTABEN",
             RenameMode::Lowercase => "kleinbuchstaben",
             RenameMode::TitleCase => "Titel Schreibweise",
+            RenameMode::SnakeCase => "snake_case",
+            RenameMode::KebabCase => "kebab-case",
+            RenameMode::CamelCase => "camelCase",
+            RenameMode::PascalCase => "PascalCase",
+            RenameMode::DotCase => "dot.case",
+            RenameMode::SentenceCase => "Satzschreibweise",
         }
     }
 
@@ -142,8 +541,31 @@ impl RenameMode {
             RenameMode::SearchReplace
                 | RenameMode::Regex
                 | RenameMode::Numbering
+                | RenameMode::PadNumbers
                 | RenameMode::Prefix
                 | RenameMode::Suffix
+                | RenameMode::Position
+                | RenameMode::DateInsert
+                | RenameMode::Template
+                | RenameMode::AudioTags
+                | RenameMode::Script
+                | RenameMode::Organize
+                | RenameMode::Media
+                | RenameMode::Extension
+                | RenameMode::Hash
+        )
+    }
+
+    /// Check if this mode uses the second input field
+    pub fn uses_second_input(&self) -> bool {
+        matches!(
+            self,
+            RenameMode::SearchReplace
+                | RenameMode::Regex
+                | RenameMode::Position
+                | RenameMode::DateInsert
+                | RenameMode::Media
+                | RenameMode::Hash
         )
     }
 
@@ -151,11 +573,142 @@ impl RenameMode {
     pub fn has_toggle(&self) -> bool {
         matches!(
             self,
-            RenameMode::Prefix | RenameMode::Suffix | RenameMode::DateInsert
+            RenameMode::Regex
+                | RenameMode::Numbering
+                | RenameMode::PadNumbers
+                | RenameMode::Prefix
+                | RenameMode::Suffix
+                | RenameMode::Position
+                | RenameMode::DateInsert
+                | RenameMode::Extension
+                | RenameMode::Hash
+                | RenameMode::Sanitize
         )
     }
 }
 
+/// A single step of a rename pipeline with its own mode and parameters
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenameStep {
+    /// Rename mode of this step
+    pub mode: RenameMode,
+    /// Search input (also used for pattern/prefix/suffix)
+    #[serde(default)]
+    pub search: String,
+    /// Replace input
+    #[serde(default)]
+    pub replace: String,
+    /// Action for prefix/suffix mode
+    #[serde(default)]
+    pub prefix_action: PrefixAction,
+    /// Flags for regex mode
+    #[serde(default)]
+    pub regex_flags: RegexFlags,
+    /// Action for position mode
+    #[serde(default)]
+    pub position_action: PositionAction,
+    /// Action for extension mode
+    #[serde(default)]
+    pub extension_action: ExtensionAction,
+    /// Target profile for sanitize mode
+    #[serde(default)]
+    pub sanitize_profile: SanitizeProfile,
+    /// Position for date insertion mode
+    #[serde(default)]
+    pub date_position: DatePosition,
+    /// Date source for date insertion mode
+    #[serde(default)]
+    pub date_source: DateSource,
+    /// strftime format for date insertion mode
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    /// Separator between date and name for date insertion mode
+    #[serde(default = "default_date_separator")]
+    pub date_separator: String,
+    /// Starting number for numbering mode
+    #[serde(default = "default_number_start")]
+    pub number_start: i64,
+    /// Step for numbering mode (negative counts down)
+    #[serde(default = "default_number_step")]
+    pub number_step: i64,
+    /// Counter format for numbering mode
+    #[serde(default)]
+    pub number_format: NumberFormat,
+    /// Files that share a counter
+    #[serde(default)]
+    pub number_group: CounterGroup,
+    /// Renumber the existing number in the name instead of replacing the name
+    #[serde(default)]
+    pub renumber: bool,
+    /// Numbers to pad in pad numbers mode
+    #[serde(default)]
+    pub pad_target: NumberTarget,
+    /// Checksum algorithm for hash mode
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    /// Position of the checksum for hash mode
+    #[serde(default = "default_hash_position")]
+    pub hash_position: DatePosition,
+}
+
+fn default_number_start() -> i64 {
+    1
+}
+
+fn default_number_step() -> i64 {
+    1
+}
+
+fn default_hash_position() -> DatePosition {
+    DatePosition::Replace
+}
+
+fn default_date_format() -> String {
+    DEFAULT_DATE_FORMAT.to_string()
+}
+
+fn default_date_separator() -> String {
+    "_".to_string()
+}
+
+impl Default for RenameStep {
+    fn default() -> Self {
+        Self {
+            mode: RenameMode::default(),
+            search: String::new(),
+            replace: String::new(),
+            prefix_action: PrefixAction::default(),
+            regex_flags: RegexFlags::default(),
+            position_action: PositionAction::default(),
+            extension_action: ExtensionAction::default(),
+            sanitize_profile: SanitizeProfile::default(),
+            date_position: DatePosition::default(),
+            date_source: DateSource::default(),
+            date_format: default_date_format(),
+            date_separator: default_date_separator(),
+            number_start: default_number_start(),
+            number_step: default_number_step(),
+            number_format: NumberFormat::default(),
+            number_group: CounterGroup::default(),
+            renumber: false,
+            pad_target: NumberTarget::default(),
+            hash_algorithm: HashAlgorithm::default(),
+            hash_position: default_hash_position(),
+        }
+    }
+}
+
+impl RenameStep {
+    pub fn new(mode: RenameMode, search: &str, replace: &str) -> Self {
+        Self {
+            mode,
+            search: search.to_string(),
+            replace: replace.to_string(),
+            ..Self::default()
+        }
+    }
+}
+
 /// Sort order for file list
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum SortOrder {
@@ -215,20 +768,47 @@ impl SortOrder {
 pub struct FileEntry {
     pub path: PathBuf,
     pub name: String,
+    /// Directory relative to the listed one (empty unless listed recursively)
+    pub subdir: PathBuf,
     pub is_dir: bool,
     pub size: u64,
     pub modified: Option<SystemTime>,
     pub extension: String,
 }
 
+impl FileEntry {
+    /// Name relative to the listed directory, e.g. "2024/photo.jpg"
+    pub fn relative_name(&self) -> String {
+        self.subdir.join(&self.name).to_string_lossy().to_string()
+    }
+}
+
 /// Main application state
 pub struct App {
     /// Current working directory
     pub directory: PathBuf,
 
-    /// List of files in the directory
+    /// Glob pattern the files were loaded with
+    pub pattern: Option<String>,
+
+    /// All files in the directory, before filtering
+    pub all_files: Vec<FileEntry>,
+
+    /// Files that pass the filter (the ones shown and renamed)
     pub files: Vec<FileEntry>,
 
+    /// Active file filter
+    pub filter: FileFilter,
+
+    /// Filter input field content
+    pub filter_input: String,
+
+    /// Cursor position in filter field (byte index at a char boundary)
+    pub filter_cursor: usize,
+
+    /// Filter error message (if the expression is invalid)
+    pub filter_error: Option<String>,
+
     /// Currently selected file index
     pub selected_index: usize,
 
@@ -253,6 +833,9 @@ pub struct App {
     /// Preview of rename operations
     pub previews: Vec<RenamePreview>,
 
+    /// Whether the previews may move files into subfolders (organize mode)
+    pub previews_move_files: bool,
+
     /// Current dialog state
     pub dialog_state: DialogState,
 
@@ -274,26 +857,100 @@ pub struct App {
     /// Action for prefix/suffix mode
     pub prefix_action: PrefixAction,
 
+    /// Flags for regex mode
+    pub regex_flags: RegexFlags,
+
+    /// Action for position mode
+    pub position_action: PositionAction,
+
+    /// Action for extension mode
+    pub extension_action: ExtensionAction,
+
+    /// Target profile for sanitize mode
+    pub sanitize_profile: SanitizeProfile,
+
     /// Position for date insertion mode
     pub date_position: DatePosition,
 
+    /// Date source for date insertion mode
+    pub date_source: DateSource,
+
     /// Starting number for numbering mode
-    pub number_start: usize,
+    pub number_start: i64,
 
     /// Step for numbering mode
-    pub number_step: usize,
+    pub number_step: i64,
+
+    /// Counter format for numbering mode
+    pub number_format: NumberFormat,
+
+    /// Files that share a counter in numbering mode
+    pub number_group: CounterGroup,
+
+    /// Renumber the existing number in the name (numbering mode)
+    pub renumber: bool,
+
+    /// Numbers to pad in pad numbers mode
+    pub pad_target: NumberTarget,
+
+    /// Checksum algorithm for hash mode
+    pub hash_algorithm: HashAlgorithm,
+
+    /// Position of the checksum for hash mode
+    pub hash_position: DatePosition,
+
+    /// Checksums computed so far
+    pub hashes: HashCache,
+
+    /// Running background hashing, if any
+    pub hash_job: Option<HashJob>,
 
     /// Regex error message (if pattern is invalid)
     pub regex_error: Option<String>,
+
+    /// Steps of the rename pipeline (the active one mirrors the input fields)
+    pub steps: Vec<RenameStep>,
+
+    /// Index of the step currently being edited
+    pub active_step: usize,
+
+    /// Rule set from the config that replaces the pipeline, if any
+    pub rule_set: Option<String>,
+
+    /// Loaded configuration (date fallback chain etc.)
+    pub config: Config,
 }
 
 impl App {
-    pub fn new(directory: PathBuf, pattern: Option<String>) -> Result<Self> {
-        let files = load_files(&directory, pattern.as_deref(), SortOrder::Name)?;
+    pub fn new(
+        directory: PathBuf,
+        pattern: Option<String>,
+        filter: FileFilter,
+        config: Config,
+    ) -> Result<Self> {
+        let all_files = load_files(
+            &directory,
+            pattern.as_deref(),
+            SortOrder::Name,
+            config.max_depth,
+        )?;
+        let prepared = PreparedFilter::new(&filter)?;
+        let files = all_files
+            .iter()
+            .filter(|file| prepared.matches(file))
+            .cloned()
+            .collect();
+        let filter_input = filter.to_string();
 
         Ok(Self {
             directory,
+            pattern,
+            all_files,
             files,
+            filter,
+            filter_cursor: filter_input.len(),
+            filter_input,
+            filter_error: None,
             selected_index: 0,
             selected_files: HashSet::new(),
             focused_panel: FocusedPanel::Files,
@@ -302,6 +959,7 @@ impl App {
             search_cursor: 0,
             replace_cursor: 0,
             previews: Vec::new(),
+            previews_move_files: false,
             dialog_state: DialogState::None,
             error_message: None,
             success_message: None,
@@ -309,10 +967,27 @@ impl App {
             rename_mode: RenameMode::default(),
             sort_order: SortOrder::default(),
             prefix_action: PrefixAction::default(),
+            regex_flags: RegexFlags::default(),
+            position_action: PositionAction::default(),
+            extension_action: ExtensionAction::default(),
+            sanitize_profile: SanitizeProfile::default(),
             date_position: DatePosition::default(),
+            date_source: DateSource::default(),
             number_start: 1,
             number_step: 1,
+            number_format: NumberFormat::default(),
+            number_group: CounterGroup::default(),
+            renumber: false,
+            pad_target: NumberTarget::default(),
+            hash_algorithm: HashAlgorithm::default(),
+            hash_position: default_hash_position(),
+            hashes: HashCache::default(),
+            hash_job: None,
             regex_error: None,
+            steps: vec![RenameStep::default()],
+            active_step: 0,
+            rule_set: None,
+            config,
         })
     }
 
@@ -354,6 +1029,15 @@ impl App {
         }
     }
 
+    /// Check if the current mode and action use the second input field
+    fn has_second_input(&self) -> bool {
+        match self.rename_mode {
+            RenameMode::Position => self.position_action.uses_second_input(),
+            RenameMode::Numbering => self.renumber,
+            mode => mode.uses_second_input(),
+        }
+    }
+
     /// Switch to next panel
     pub fn next_panel(&mut self) {
         self.focused_panel = match self.focused_panel {
@@ -365,13 +1049,13 @@ impl App {
                 }
             }
             FocusedPanel::SearchField => {
-                if self.rename_mode.uses_search_replace() {
+                if self.has_second_input() {
                     FocusedPanel::ReplaceField
                 } else {
                     FocusedPanel::Files
                 }
             }
-            FocusedPanel::ReplaceField => FocusedPanel::Files,
+            FocusedPanel::ReplaceField | FocusedPanel::FilterField => FocusedPanel::Files,
         };
     }
 
@@ -379,7 +1063,7 @@ impl App {
     pub fn previous_panel(&mut self) {
         self.focused_panel = match self.focused_panel {
             FocusedPanel::Files => {
-                if self.rename_mode.uses_search_replace() {
+                if self.has_second_input() {
                     FocusedPanel::ReplaceField
                 } else if self.rename_mode.uses_input() {
                     FocusedPanel::SearchField
@@ -387,7 +1071,7 @@ impl App {
                     FocusedPanel::Files
                 }
             }
-            FocusedPanel::SearchField => FocusedPanel::Files,
+            FocusedPanel::SearchField | FocusedPanel::FilterField => FocusedPanel::Files,
             FocusedPanel::ReplaceField => FocusedPanel::SearchField,
         };
     }
@@ -406,6 +1090,62 @@ impl App {
             self.search_input = "file_###".to_string();
             self.search_cursor = self.search_input.len();
         }
+        // Set default template for template mode
+        if self.rename_mode == RenameMode::Template && self.search_input.is_empty() {
+            self.search_input = "{stem}_{n:03}.{ext}".to_string();
+            self.search_cursor = self.search_input.len();
+        }
+        // Organize mode sorts by year and month by default
+        if self.rename_mode == RenameMode::Organize && self.search_input.is_empty() {
+            self.search_input = "{date:%Y}/{date:%m}/{name}".to_string();
+            self.search_cursor = self.search_input.len();
+        }
+        // Media mode starts with the naming schemes from the config
+        if self.rename_mode == RenameMode::Media {
+            if self.search_input.is_empty() {
+                self.search_input = self.config.media.episode_format.clone();
+                self.search_cursor = self.search_input.len();
+            }
+            if self.replace_input.is_empty() {
+                self.replace_input = self.config.media.movie_format.clone();
+                self.replace_cursor = self.replace_input.len();
+            }
+        }
+        // Set default pattern for audio tag mode
+        if self.rename_mode == RenameMode::AudioTags && self.search_input.is_empty() {
+            self.search_input = "{track:02} - {artist} - {title}.{ext}".to_string();
+            self.search_cursor = self.search_input.len();
+        }
+        // Script mode starts with the first script of the scripts directory
+        if self.rename_mode == RenameMode::Script && self.search_input.is_empty() {
+            self.search_input = crate::script::list_scripts()
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+            self.search_cursor = self.search_input.len();
+        }
+        // Position mode inserts at the start by default
+        if self.rename_mode == RenameMode::Position && self.replace_input.is_empty() {
+            self.replace_input = "0".to_string();
+            self.replace_cursor = self.replace_input.len();
+        }
+        // Date mode edits format and separator, start from the defaults
+        if self.rename_mode == RenameMode::DateInsert {
+            if self.search_input.is_empty() {
+                self.search_input = default_date_format();
+                self.search_cursor = self.search_input.len();
+            }
+            if self.replace_input.is_empty() {
+                self.replace_input = default_date_separator();
+                self.replace_cursor = self.replace_input.len();
+            }
+        }
+        // Hash mode puts '_' between checksum and name by default (an empty
+        // length keeps the full checksum)
+        if self.rename_mode == RenameMode::Hash && self.replace_input.is_empty() {
+            self.replace_input = "_".to_string();
+            self.replace_cursor = self.replace_input.len();
+        }
         self.update_preview();
     }
 
@@ -415,12 +1155,262 @@ impl App {
         self.update_preview();
     }
 
+    /// Cycle which regex match gets replaced (all/1st/2nd/...)
+    pub fn cycle_regex_occurrence(&mut self) {
+        self.regex_flags = self.regex_flags.next_occurrence();
+        self.update_preview();
+    }
+
+    /// Toggle case-insensitive regex matching
+    pub fn toggle_regex_ignore_case(&mut self) {
+        self.regex_flags.ignore_case = !self.regex_flags.ignore_case;
+        self.update_preview();
+    }
+
+    /// Toggle multi-line regex matching
+    pub fn toggle_regex_multi_line(&mut self) {
+        self.regex_flags.multi_line = !self.regex_flags.multi_line;
+        self.update_preview();
+    }
+
+    /// Cycle the position action (insert/delete/keep/cut)
+    pub fn cycle_position_action(&mut self) {
+        self.position_action = self.position_action.next();
+        if self.focused_panel == FocusedPanel::ReplaceField && !self.has_second_input() {
+            self.focused_panel = FocusedPanel::SearchField;
+        }
+        self.update_preview();
+    }
+
+    /// Cycle the extension action (set/lowercase/normalize/remove/add)
+    pub fn cycle_extension_action(&mut self) {
+        self.extension_action = self.extension_action.next();
+        self.update_preview();
+    }
+
+    /// Cycle the sanitize profile (POSIX/Windows/FAT/URL)
+    pub fn cycle_sanitize_profile(&mut self) {
+        self.sanitize_profile = self.sanitize_profile.next();
+        self.update_preview();
+    }
+
+    /// Cycle the counter format (decimal/letters/roman/hex)
+    pub fn cycle_number_format(&mut self) {
+        self.number_format = self.number_format.next();
+        self.update_preview();
+    }
+
+    /// Cycle which files share a counter (all/extension/directory/day)
+    pub fn cycle_number_group(&mut self) {
+        self.number_group = self.number_group.next();
+        self.update_preview();
+    }
+
+    /// Toggle renumbering of existing numbers (the second input holds the offset)
+    pub fn toggle_renumber(&mut self) {
+        self.renumber = !self.renumber;
+        if self.focused_panel == FocusedPanel::ReplaceField && !self.has_second_input() {
+            self.focused_panel = FocusedPanel::SearchField;
+        }
+        self.update_preview();
+    }
+
+    /// Cycle which numbers get padded (first/last/all)
+    pub fn cycle_pad_target(&mut self) {
+        self.pad_target = self.pad_target.next();
+        self.update_preview();
+    }
+
+    /// Cycle the checksum algorithm (SHA-256/BLAKE3/CRC32)
+    pub fn cycle_hash_algorithm(&mut self) {
+        self.hash_algorithm = self.hash_algorithm.next();
+        self.update_preview();
+    }
+
+    /// Cycle the checksum position (prefix/suffix/replace)
+    pub fn cycle_hash_position(&mut self) {
+        self.hash_position = self.hash_position.next();
+        self.update_preview();
+    }
+
+    /// Cycle through the rule sets of the config, then back to the pipeline
+    pub fn cycle_rule_set(&mut self) {
+        let names = self.config.list_rule_sets();
+        let next = match &self.rule_set {
+            None => names.first(),
+            Some(current) => names
+                .iter()
+                .position(|name| name == current)
+                .and_then(|i| names.get(i + 1)),
+        };
+        self.rule_set = next.map(|name| name.to_string());
+        self.update_preview();
+    }
+
+    /// The active rule set, if any
+    pub fn active_rule_set(&self) -> Option<&RuleSet> {
+        self.rule_set
+            .as_deref()
+            .and_then(|name| self.config.get_rule_set(name))
+    }
+
+    /// Toggle whether directories are renamed too
+    pub fn toggle_include_dirs(&mut self) {
+        self.config.include_dirs = !self.config.include_dirs;
+        self.update_preview();
+    }
+
     /// Toggle date position (prefix/suffix/replace)
     pub fn toggle_date_position(&mut self) {
         self.date_position = self.date_position.next();
         self.update_preview();
     }
 
+    /// Cycle the date source (mtime/ctime/birth/EXIF/now)
+    pub fn cycle_date_source(&mut self) {
+        self.date_source = self.date_source.next();
+        self.update_preview();
+    }
+
+    /// Build a rename step from the current input fields
+    pub fn current_step(&self) -> RenameStep {
+        let step = RenameStep {
+            mode: self.rename_mode,
+            prefix_action: self.prefix_action,
+            regex_flags: self.regex_flags,
+            position_action: self.position_action,
+            extension_action: self.extension_action,
+            sanitize_profile: self.sanitize_profile,
+            date_position: self.date_position,
+            date_source: self.date_source,
+            number_start: self.number_start,
+            number_step: self.number_step,
+            number_format: self.number_format,
+            number_group: self.number_group,
+            renumber: self.renumber,
+            pad_target: self.pad_target,
+            hash_algorithm: self.hash_algorithm,
+            hash_position: self.hash_position,
+            ..RenameStep::default()
+        };
+
+        // In date mode the input fields hold format and separator
+        if self.rename_mode == RenameMode::DateInsert {
+            RenameStep {
+                date_format: self.search_input.clone(),
+                date_separator: self.replace_input.clone(),
+                ..step
+            }
+        } else {
+            RenameStep {
+                search: self.search_input.clone(),
+                replace: self.replace_input.clone(),
+                ..step
+            }
+        }
+    }
+
+    /// Write the input fields back into the active pipeline step
+    fn store_active_step(&mut self) {
+        let step = self.current_step();
+        match self.steps.get_mut(self.active_step) {
+            Some(active) => *active = step,
+            None => {
+                self.steps.push(step);
+                self.active_step = self.steps.len() - 1;
+            }
+        }
+    }
+
+    /// Load a pipeline step into the input fields
+    fn load_step(&mut self, index: usize) {
+        let Some(step) = self.steps.get(index).cloned() else {
+            return;
+        };
+        self.active_step = index;
+        self.rename_mode = step.mode;
+        self.prefix_action = step.prefix_action;
+        self.regex_flags = step.regex_flags;
+        self.position_action = step.position_action;
+        self.extension_action = step.extension_action;
+        self.sanitize_profile = step.sanitize_profile;
+        self.date_position = step.date_position;
+        self.date_source = step.date_source;
+        self.number_start = step.number_start;
+        self.number_step = step.number_step;
+        self.number_format = step.number_format;
+        self.number_group = step.number_group;
+        self.renumber = step.renumber;
+        self.pad_target = step.pad_target;
+        self.hash_algorithm = step.hash_algorithm;
+        self.hash_position = step.hash_position;
+        if !self.rename_mode.uses_input() {
+            self.focused_panel = FocusedPanel::Files;
+        }
+        self.regex_error = None;
+        if step.mode == RenameMode::DateInsert {
+            self.set_search_replace(step.date_format, step.date_separator);
+        } else {
+            self.set_search_replace(step.search, step.replace);
+        }
+    }
+
+    /// Append a new step after the active one and start editing it
+    pub fn add_step(&mut self) {
+        self.store_active_step();
+        let index = self.active_step + 1;
+        self.steps.insert(index, RenameStep::default());
+        self.load_step(index);
+    }
+
+    /// Remove the active step (the last remaining step is reset instead)
+    pub fn remove_step(&mut self) {
+        if self.steps.len() <= 1 {
+            self.steps = vec![RenameStep::default()];
+            self.load_step(0);
+            return;
+        }
+        self.steps.remove(self.active_step);
+        let index = self.active_step.min(self.steps.len() - 1);
+        self.load_step(index);
+    }
+
+    /// Edit the next step of the pipeline
+    pub fn select_next_step(&mut self) {
+        if self.active_step + 1 < self.steps.len() {
+            self.store_active_step();
+            self.load_step(self.active_step + 1);
+        }
+    }
+
+    /// Edit the previous step of the pipeline
+    pub fn select_previous_step(&mut self) {
+        if self.active_step > 0 {
+            self.store_active_step();
+            self.load_step(self.active_step - 1);
+        }
+    }
+
+    /// Move the active step one position earlier in the pipeline
+    pub fn move_step_up(&mut self) {
+        if self.active_step > 0 {
+            self.store_active_step();
+            self.steps.swap(self.active_step, self.active_step - 1);
+            self.active_step -= 1;
+            self.update_preview();
+        }
+    }
+
+    /// Move the active step one position later in the pipeline
+    pub fn move_step_down(&mut self) {
+        if self.active_step + 1 < self.steps.len() {
+            self.store_active_step();
+            self.steps.swap(self.active_step, self.active_step + 1);
+            self.active_step += 1;
+            self.update_preview();
+        }
+    }
+
     /// Cycle to next sort order
     pub fn cycle_sort(&mut self) {
         self.sort_order = self.sort_order.next();
@@ -429,6 +1419,7 @@ impl App {
 
     /// Apply current sort order to files
     pub fn apply_sort(&mut self) {
+        sort_files(&mut self.all_files, self.sort_order);
         sort_files(&mut self.files, self.sort_order);
         // Reset selection after sort
         self.selected_files.clear();
@@ -447,6 +1438,11 @@ impl App {
                 self.replace_input.insert(self.replace_cursor, c);
                 self.replace_cursor += 1;
             }
+            FocusedPanel::FilterField => {
+                self.filter_input.insert(self.filter_cursor, c);
+                self.filter_cursor += c.len_utf8();
+                self.apply_filter();
+            }
             FocusedPanel::Files => {}
         }
         self.update_preview();
@@ -467,6 +1463,13 @@ impl App {
                     self.replace_input.remove(self.replace_cursor);
                 }
             }
+            FocusedPanel::FilterField => {
+                if let Some(c) = self.filter_input[..self.filter_cursor].chars().next_back() {
+                    self.filter_cursor -= c.len_utf8();
+                    self.filter_input.remove(self.filter_cursor);
+                    self.apply_filter();
+                }
+            }
             FocusedPanel::Files => {}
         }
         self.update_preview();
@@ -485,6 +1488,11 @@ impl App {
                     self.replace_cursor -= 1;
                 }
             }
+            FocusedPanel::FilterField => {
+                if let Some(c) = self.filter_input[..self.filter_cursor].chars().next_back() {
+                    self.filter_cursor -= c.len_utf8();
+                }
+            }
             FocusedPanel::Files => {}
         }
     }
@@ -502,23 +1510,87 @@ impl App {
                     self.replace_cursor += 1;
                 }
             }
+            FocusedPanel::FilterField => {
+                if let Some(c) = self.filter_input[self.filter_cursor..].chars().next() {
+                    self.filter_cursor += c.len_utf8();
+                }
+            }
             FocusedPanel::Files => {}
         }
     }
 
+    /// Focus the filter field
+    pub fn edit_filter(&mut self) {
+        self.focused_panel = FocusedPanel::FilterField;
+        self.filter_cursor = self.filter_input.len();
+    }
+
+    /// Apply the filter expression from the filter field. An invalid expression
+    /// keeps the previous filter and shows the error.
+    pub fn apply_filter(&mut self) {
+        let prepared = parse_filter(&self.filter_input)
+            .and_then(|filter| PreparedFilter::new(&filter).map(|prepared| (filter, prepared)));
+        match prepared {
+            Ok((filter, prepared)) => {
+                self.files = self
+                    .all_files
+                    .iter()
+                    .filter(|file| prepared.matches(file))
+                    .cloned()
+                    .collect();
+                self.filter = filter;
+                self.filter_error = None;
+                self.selected_files.clear();
+                self.selected_index = 0;
+            }
+            Err(e) => self.filter_error = Some(e.to_string()),
+        }
+    }
+
+    /// Load the files again (after renaming) and apply the filter
+    fn reload_files(&mut self) -> Result<()> {
+        self.all_files = load_files(
+            &self.directory,
+            self.pattern.as_deref(),
+            self.sort_order,
+            self.config.max_depth,
+        )?;
+        let prepared = PreparedFilter::new(&self.filter)?;
+        self.files = self
+            .all_files
+            .iter()
+            .filter(|file| prepared.matches(file))
+            .cloned()
+            .collect();
+        Ok(())
+    }
+
     /// Update preview based on current search/replace values
     pub fn update_preview(&mut self) {
-        let result = crate::operations::generate_previews(
-            &self.files,
-            &self.selected_files,
-            &self.search_input,
-            &self.replace_input,
-            self.rename_mode,
-            self.prefix_action,
-            self.number_start,
-            self.number_step,
-            self.date_position,
-        );
+        self.store_active_step();
+        self.start_hashing();
+        self.previews_move_files = match self.active_rule_set() {
+            Some(rule_set) => {
+                crate::operations::moves_to_subdirs(crate::rules::rule_set_steps(rule_set))
+            }
+            None => crate::operations::moves_to_subdirs(&self.steps),
+        };
+        let result = match self.active_rule_set() {
+            Some(rule_set) => crate::rules::rule_previews(
+                &self.files,
+                &self.selected_files,
+                rule_set,
+                &self.config,
+                &self.hashes,
+            ),
+            None => crate::operations::generate_previews(
+                &self.files,
+                &self.selected_files,
+                &self.steps,
+                &self.config,
+                &self.hashes,
+            ),
+        };
 
         match result {
             Ok(previews) => {
@@ -532,9 +1604,77 @@ impl App {
         }
     }
 
+    /// Start hashing the files that hash steps still need a checksum for
+    fn start_hashing(&mut self) {
+        let steps: Vec<&RenameStep> = match self.active_rule_set() {
+            Some(rule_set) => crate::rules::rule_set_steps(rule_set).collect(),
+            None => self.steps.iter().collect(),
+        };
+        // Every hash step may use its own algorithm
+        let mut algorithms: Vec<HashAlgorithm> = Vec::new();
+        for step in steps.iter().filter(|step| step.mode == RenameMode::Hash) {
+            if !algorithms.contains(&step.hash_algorithm) {
+                algorithms.push(step.hash_algorithm);
+            }
+        }
+        if algorithms.is_empty() {
+            return;
+        }
+        if self
+            .hash_job
+            .as_ref()
+            .is_some_and(|job| job.algorithms == algorithms && !job.is_finished())
+        {
+            return;
+        }
+
+        let files: Vec<&FileEntry> = self
+            .files
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.selected_files.is_empty() || self.selected_files.contains(i))
+            .map(|(_, file)| file)
+            .filter(|file| !file.is_dir)
+            .collect();
+        let hashes = &self.hashes;
+        let missing: Vec<(PathBuf, u64, HashAlgorithm)> = algorithms
+            .iter()
+            .flat_map(|&algorithm| {
+                files
+                    .iter()
+                    .filter(move |file| !hashes.contains(&file.path, algorithm))
+                    .map(move |file| (file.path.clone(), file.size, algorithm))
+            })
+            .collect();
+        self.hash_job = if missing.is_empty() {
+            None
+        } else {
+            Some(HashJob::start(missing))
+        };
+    }
+
+    /// Collect finished checksums, refreshes the preview when new ones arrived
+    pub fn poll_hash_job(&mut self) {
+        let Some(job) = self.hash_job.as_mut() else {
+            return;
+        };
+        let changed = job.poll(&mut self.hashes);
+        let finished = job.is_finished();
+        if finished {
+            self.hash_job = None;
+        }
+        if changed {
+            self.update_preview();
+        }
+    }
+
     /// Execute the rename operations
     pub fn execute_rename(&mut self) -> Result<usize> {
-        let result = crate::operations::execute_renames(&self.previews, &self.directory);
+        let result = crate::operations::execute_renames(
+            &self.previews,
+            &self.directory,
+            self.previews_move_files,
+        );
 
         match &result {
             Ok(count) => {
@@ -543,15 +1683,18 @@ impl App {
                 self.dialog_state = DialogState::Success;
 
                 // Reload files after rename
-                if let Ok(files) = load_files(&self.directory, None, self.sort_order) {
-                    self.files = files;
+                if self.reload_files().is_ok() {
                     self.selected_files.clear();
                     self.selected_index = 0;
                     self.search_input.clear();
                     self.replace_input.clear();
                     self.search_cursor = 0;
                     self.replace_cursor = 0;
+                    // The other steps of the pipeline stay for the next run
+                    self.store_active_step();
                     self.previews.clear();
+                    // Checksums are stored by path, which changed
+                    self.hashes = HashCache::default();
                 }
             }
             Err(e) => {
@@ -563,11 +1706,41 @@ impl App {
         result
     }
 
+    /// Files to edit in $EDITOR: the selected ones, or all if nothing is selected
+    pub fn edit_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.files.len())
+            .filter(|i| self.selected_files.is_empty() || self.selected_files.contains(i))
+            .filter(|&i| self.config.include_dirs || !self.files[i].is_dir)
+            .collect();
+        indices.sort();
+        indices
+    }
+
+    /// Show the names edited in $EDITOR as preview and ask for confirmation
+    pub fn apply_edited_names(&mut self, indices: &[usize], edited: Result<String>) {
+        let previews =
+            edited.and_then(|edited| crate::editor::edit_previews(&self.files, indices, &edited));
+        match previews {
+            Ok(previews) => {
+                self.previews = previews;
+                // Edited names never move files
+                self.previews_move_files = false;
+                if self.has_changes() {
+                    self.dialog_state = DialogState::Confirm;
+                }
+            }
+            Err(e) => {
+                self.error_message = Some(e.to_string());
+                self.dialog_state = DialogState::Error;
+            }
+        }
+    }
+
     /// Show confirmation dialog
     pub fn show_confirm_dialog(&mut self) {
         // Update preview before showing dialog
         self.update_preview();
-        if !self.previews.is_empty() && self.previews.iter().any(|p| p.will_change) {
+        if self.has_changes() {
             self.dialog_state = DialogState::Confirm;
         }
     }
@@ -589,20 +1762,14 @@ impl App {
         match crate::operations::undo_last_rename() {
             Ok((count, undo_directory)) => {
                 self.last_rename_count = count;
-                self.success_message = Some(format!(
-                    "{} Datei(en) rueckgaengig gemacht",
-                    count
-                ));
+                self.success_message = Some(format!("{} Datei(en) rueckgaengig gemacht", count));
                 self.dialog_state = DialogState::Success;
 
                 // Reload files if we're in the same directory
-                if undo_directory == self.directory {
-                    if let Ok(files) = load_files(&self.directory, None, self.sort_order) {
-                        self.files = files;
-                        self.selected_files.clear();
-                        self.selected_index = 0;
-                        self.previews.clear();
-                    }
+                if undo_directory == self.directory && self.reload_files().is_ok() {
+                    self.selected_files.clear();
+                    self.selected_index = 0;
+                    self.previews.clear();
                 }
             }
             Err(e) => {
@@ -612,22 +1779,6 @@ impl App {
         }
     }
 
-    /// Check if there's anything to undo
-    pub fn can_undo(&self) -> bool {
-        crate::operations::get_undo_preview()
-            .map(|opt| opt.is_some())
-            .unwrap_or(false)
-    }
-
-    /// Get files that will be affected by the operation
-    pub fn get_affected_files(&self) -> Vec<&FileEntry> {
-        self.previews
-            .iter()
-            .filter(|p| p.will_change)
-            .filter_map(|p| self.files.iter().find(|f| f.name == p.original_name))
-            .collect()
-    }
-
     /// Check if we have any changes to apply
     pub fn has_changes(&self) -> bool {
         self.previews.iter().any(|p| p.will_change)
@@ -652,75 +1803,41 @@ impl App {
     }
 }
 
-/// Load files from directory with optional glob pattern
+/// Load files from directory with optional glob pattern.
+/// `max_depth` is the number of directory levels to list (1 = only the directory
+/// itself, 0 = unlimited).
 pub fn load_files(
-    directory: &PathBuf,
+    directory: &Path,
     pattern: Option<&str>,
     sort_order: SortOrder,
+    max_depth: usize,
 ) -> Result<Vec<FileEntry>> {
     let mut files = Vec::new();
 
-    if let Some(pattern) = pattern {
-        // Use glob pattern
-        let full_pattern = directory.join(pattern);
-        let pattern_str = full_pattern.to_string_lossy();
-
-        for entry in glob(&pattern_str)? {
-            if let Ok(path) = entry {
-                if path.is_file() {
-                    if let Some(name) = path.file_name() {
-                        let metadata = std::fs::metadata(&path).ok();
-                        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-                        let modified = metadata.as_ref().and_then(|m| m.modified().ok());
-                        let extension = path
-                            .extension()
-                            .map(|e| e.to_string_lossy().to_string())
-                            .unwrap_or_default();
-
-                        files.push(FileEntry {
-                            path: path.clone(),
-                            name: name.to_string_lossy().to_string(),
-                            is_dir: false,
-                            size,
-                            modified,
-                            extension,
-                        });
-                    }
+    match pattern {
+        Some(pattern) if max_depth == 1 => {
+            // Use glob pattern
+            let full_pattern = directory.join(pattern);
+            let pattern_str = full_pattern.to_string_lossy();
+
+            for path in glob(&pattern_str)?.flatten() {
+                if path.is_file() || path.is_dir() {
+                    files.extend(file_entry(path, PathBuf::new()));
                 }
             }
         }
-    } else {
-        // List all files in directory
-        if directory.is_dir() {
-            for entry in std::fs::read_dir(directory)? {
-                let entry = entry?;
-                let path = entry.path();
-                let is_dir = path.is_dir();
-
-                // Skip hidden files
-                if let Some(name) = path.file_name() {
-                    let name_str = name.to_string_lossy().to_string();
-                    if !name_str.starts_with('.') {
-                        let metadata = std::fs::metadata(&path).ok();
-                        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-                        let modified = metadata.as_ref().and_then(|m| m.modified().ok());
-                        let extension = path
-                            .extension()
-                            .map(|e| e.to_string_lossy().to_string())
-                            .unwrap_or_default();
-
-                        files.push(FileEntry {
-                            path,
-                            name: name_str,
-                            is_dir,
-                            size,
-                            modified,
-                            extension,
-                        });
-                    }
-                }
-            }
+        Some(pattern) => {
+            // Match the glob against the names in every directory of the tree
+            let pattern = Pattern::new(pattern)?;
+            walk_directory(
+                directory,
+                Path::new(""),
+                max_depth,
+                Some(&pattern),
+                &mut files,
+            )?;
         }
+        None => walk_directory(directory, Path::new(""), max_depth, None, &mut files)?,
     }
 
     // Apply sorting
@@ -729,9 +1846,82 @@ pub fn load_files(
     Ok(files)
 }
 
+/// List the entries of a directory (without hidden ones), descending into
+/// subdirectories until `max_depth` levels are listed. Only the listed directory
+/// itself must be readable, subdirectories that cannot be read are skipped.
+fn walk_directory(
+    directory: &Path,
+    subdir: &Path,
+    max_depth: usize,
+    pattern: Option<&Pattern>,
+    files: &mut Vec<FileEntry>,
+) -> Result<()> {
+    if !directory.is_dir() {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(directory)? {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let path = entry.path();
+
+        // Skip hidden files
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+
+        // Symlinked directories are listed but not followed (no loops)
+        let descend = entry.file_type().is_ok_and(|kind| kind.is_dir()) && max_depth != 1;
+        if pattern.is_none_or(|pattern| pattern.matches(&name)) {
+            files.extend(file_entry(path.clone(), subdir.to_path_buf()));
+        }
+        // An unreadable subdirectory (e.g. lost+found) must not fail the whole listing
+        if descend {
+            let _ = walk_directory(
+                &path,
+                &subdir.join(&name),
+                max_depth.saturating_sub(1),
+                pattern,
+                files,
+            );
+        }
+    }
+    Ok(())
+}
+
+/// File entry for a path, `subdir` is its directory relative to the listed one
+fn file_entry(path: PathBuf, subdir: PathBuf) -> Option<FileEntry> {
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let metadata = std::fs::metadata(&path).ok();
+    let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+    let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    Some(FileEntry {
+        is_dir: path.is_dir(),
+        path,
+        name,
+        subdir,
+        size,
+        modified,
+        extension,
+    })
+}
+
 /// Sort files according to the given order
-fn sort_files(files: &mut Vec<FileEntry>, sort_order: SortOrder) {
+fn sort_files(files: &mut [FileEntry], sort_order: SortOrder) {
     files.sort_by(|a, b| {
+        // Files of a tree are grouped by their directory
+        let by_dir = a.subdir.cmp(&b.subdir);
+        if by_dir.is_ne() {
+            return by_dir;
+        }
+
         // Directories always come first
         match (a.is_dir, b.is_dir) {
             (true, false) => return std::cmp::Ordering::Less,
@@ -0,0 +1,255 @@
+use crate::config::{TitleCaseConfig, TitleLanguage};
+
+/// Naming convention for the case style modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseStyle {
+    Snake,
+    Kebab,
+    Camel,
+    Pascal,
+    Dot,
+    Sentence,
+}
+
+/// Split a name into words at separators, camelCase humps, acronyms and digits.
+/// "myHTTPServer2" -> ["my", "HTTP", "Server", "2"]
+pub fn split_words(value: &str) -> Vec<String> {
+    let chars: Vec<char> = value.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        // Apostrophes belong to the word ("don't" -> "dont")
+        if c == '\'' || c == '’' {
+            continue;
+        }
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        if let Some(prev) = current.chars().last() {
+            let next = chars.get(i + 1).copied();
+            let boundary =
+                // "myHttp": lower or digit followed by upper
+                (c.is_uppercase() && (prev.is_lowercase() || prev.is_numeric()))
+                // "HTTPServer": end of an acronym before a capitalized word
+                || (c.is_uppercase()
+                    && prev.is_uppercase()
+                    && next.is_some_and(|n| n.is_lowercase()))
+                // "Server2": letter followed by digit
+                || (c.is_numeric() && prev.is_alphabetic());
+            if boundary {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(c);
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// Uppercase the first character, lowercase the rest
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Convert a name to the given case style, None if it contains no words
+pub fn convert_case(value: &str, style: CaseStyle) -> Option<String> {
+    let words = split_words(value);
+    if words.is_empty() {
+        return None;
+    }
+    let lower = words.iter().map(|w| w.to_lowercase());
+
+    let converted = match style {
+        CaseStyle::Snake => lower.collect::<Vec<_>>().join("_"),
+        CaseStyle::Kebab => lower.collect::<Vec<_>>().join("-"),
+        CaseStyle::Dot => lower.collect::<Vec<_>>().join("."),
+        CaseStyle::Camel => words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| {
+                if i == 0 {
+                    w.to_lowercase()
+                } else {
+                    capitalize(w)
+                }
+            })
+            .collect(),
+        CaseStyle::Pascal => words.iter().map(|w| capitalize(w)).collect(),
+        CaseStyle::Sentence => capitalize(&lower.collect::<Vec<_>>().join(" ")),
+    };
+    Some(converted)
+}
+
+impl TitleLanguage {
+    /// Articles, conjunctions and short prepositions of the language
+    fn stop_words(&self) -> &'static [&'static str] {
+        match self {
+            TitleLanguage::English => &[
+                "a", "an", "the", "and", "but", "or", "nor", "for", "so", "yet", "as", "at", "by",
+                "in", "of", "off", "on", "per", "to", "up", "via", "vs", "from", "into", "with",
+            ],
+            TitleLanguage::German => &[
+                "der", "die", "das", "den", "dem", "des", "ein", "eine", "einer", "eines", "einem",
+                "einen", "und", "oder", "aber", "als", "an", "am", "auf", "aus", "bei", "bis",
+                "durch", "fuer", "für", "im", "in", "mit", "nach", "ueber", "über", "um", "von",
+                "vom", "vor", "zu", "zum", "zur",
+            ],
+            TitleLanguage::French => &[
+                "le", "la", "les", "l", "un", "une", "des", "de", "du", "d", "et", "ou", "mais",
+                "à", "a", "au", "aux", "en", "dans", "par", "pour", "sur", "sous", "avec", "sans",
+                "chez", "vers",
+            ],
+            TitleLanguage::None => &[],
+        }
+    }
+}
+
+/// Title case with stop words: every word is capitalized except articles and
+/// prepositions that are neither first nor last. Preserved words keep their spelling.
+pub fn title_case(value: &str, rules: &TitleCaseConfig) -> String {
+    let is_boundary = |c: char| c.is_whitespace() || c == '_' || c == '-';
+
+    // Alternating runs of words and separators, separators are kept as they are
+    let mut segments: Vec<(bool, String)> = Vec::new();
+    for c in value.chars() {
+        let is_word = !is_boundary(c);
+        match segments.last_mut() {
+            Some((word, text)) if *word == is_word => text.push(c),
+            _ => segments.push((is_word, c.to_string())),
+        }
+    }
+
+    let word_count = segments.iter().filter(|(is_word, _)| *is_word).count();
+    let stop_words = rules.language.stop_words();
+    let mut index = 0;
+    let mut result = String::with_capacity(value.len());
+
+    for (is_word, segment) in segments {
+        if !is_word {
+            result.push_str(&segment);
+            continue;
+        }
+        let lower = segment.to_lowercase();
+        let inner = index > 0 && index + 1 < word_count;
+        index += 1;
+
+        if let Some(preserved) = rules.preserve.iter().find(|w| w.to_lowercase() == lower) {
+            result.push_str(preserved);
+        } else if inner
+            && (stop_words.contains(&lower.as_str())
+                || rules.stop_words.iter().any(|w| w.to_lowercase() == lower))
+        {
+            result.push_str(&lower);
+        } else {
+            result.push_str(&capitalize(&segment));
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            split_words("myHTTPServer2"),
+            vec!["my", "HTTP", "Server", "2"]
+        );
+        assert_eq!(
+            split_words("hello_world-foo bar"),
+            vec!["hello", "world", "foo", "bar"]
+        );
+        assert_eq!(split_words("XMLParser"), vec!["XML", "Parser"]);
+        assert_eq!(split_words("version2Beta"), vec!["version", "2", "Beta"]);
+        assert_eq!(split_words("IMG_0042"), vec!["IMG", "0042"]);
+        assert_eq!(split_words("don't stop"), vec!["dont", "stop"]);
+        assert_eq!(split_words("Größe Über"), vec!["Größe", "Über"]);
+        assert!(split_words("__--").is_empty());
+    }
+
+    #[test]
+    fn test_convert_case() {
+        let name = "myHTTPServer2";
+        let convert = |style| convert_case(name, style).unwrap();
+        assert_eq!(convert(CaseStyle::Snake), "my_http_server_2");
+        assert_eq!(convert(CaseStyle::Kebab), "my-http-server-2");
+        assert_eq!(convert(CaseStyle::Camel), "myHttpServer2");
+        assert_eq!(convert(CaseStyle::Pascal), "MyHttpServer2");
+        assert_eq!(convert(CaseStyle::Dot), "my.http.server.2");
+        assert_eq!(convert(CaseStyle::Sentence), "My http server 2");
+        assert_eq!(convert_case("___", CaseStyle::Snake), None);
+    }
+
+    #[test]
+    fn test_title_case_stop_words() {
+        let rules = TitleCaseConfig::default();
+        assert_eq!(
+            title_case("the lord of the rings", &rules),
+            "The Lord of the Rings"
+        );
+        assert_eq!(
+            title_case("what we are made of", &rules),
+            "What We Are Made Of"
+        );
+        assert_eq!(title_case("hello_world", &rules), "Hello_World");
+
+        let german = TitleCaseConfig {
+            language: TitleLanguage::German,
+            ..TitleCaseConfig::default()
+        };
+        assert_eq!(
+            title_case("die reise zum mittelpunkt der erde", &german),
+            "Die Reise zum Mittelpunkt der Erde"
+        );
+
+        let french = TitleCaseConfig {
+            language: TitleLanguage::French,
+            ..TitleCaseConfig::default()
+        };
+        assert_eq!(
+            title_case("le tour du monde en quatre-vingts jours", &french),
+            "Le Tour du Monde en Quatre-Vingts Jours"
+        );
+    }
+
+    #[test]
+    fn test_title_case_preserved_words() {
+        let rules = TitleCaseConfig {
+            stop_words: vec!["feat".to_string()],
+            ..TitleCaseConfig::default()
+        };
+        assert_eq!(
+            title_case("my IPHONE photos from nasa", &rules),
+            "My iPhone Photos from NASA"
+        );
+        assert_eq!(
+            title_case("mcdonald feat the band", &rules),
+            "McDonald feat the Band"
+        );
+
+        let none = TitleCaseConfig {
+            language: TitleLanguage::None,
+            preserve: Vec::new(),
+            ..TitleCaseConfig::default()
+        };
+        assert_eq!(title_case("the end of it", &none), "The End Of It");
+    }
+}
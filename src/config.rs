@@ -6,14 +6,18 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::app::{DatePosition, RenameMode, SortOrder};
+use crate::app::{
+    CounterGroup, DatePosition, DateSource, ExtensionAction, HashAlgorithm, NumberFormat,
+    NumberTarget, PositionAction, RegexFlags, RenameMode, RenameStep, SanitizeProfile, SortOrder,
+};
+use crate::filter::FileFilter;
 
 /// A single rename entry in history
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RenameHistoryEntry {
-    /// Original filename before rename
+    /// Original filename before rename (relative to the directory of the operation)
     pub original_name: String,
-    /// New filename after rename
+    /// New filename after rename (relative to the directory of the operation)
     pub new_name: String,
 }
 
@@ -28,6 +32,10 @@ pub struct RenameOperation {
     pub entries: Vec<RenameHistoryEntry>,
     /// Description of the operation (mode used)
     pub description: String,
+    /// Folders created for the renamed files (relative, parents first),
+    /// removed again on undo if they are empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub created_dirs: Vec<String>,
 }
 
 impl RenameOperation {
@@ -41,6 +49,7 @@ impl RenameOperation {
             directory,
             entries,
             description,
+            created_dirs: Vec::new(),
         }
     }
 }
@@ -147,6 +156,12 @@ pub struct Preset {
     /// Replace pattern (for SearchReplace mode)
     #[serde(default)]
     pub replace: String,
+    /// Full rename pipeline (takes precedence over mode/search/replace)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<RenameStep>,
+    /// Files the preset applies to
+    #[serde(default, skip_serializing_if = "FileFilter::is_empty")]
+    pub filter: FileFilter,
 }
 
 impl Preset {
@@ -156,8 +171,68 @@ impl Preset {
             mode,
             search,
             replace,
+            steps: Vec::new(),
+            filter: FileFilter::default(),
         }
     }
+
+    /// Create a preset that stores a whole pipeline
+    pub fn with_steps(name: String, steps: Vec<RenameStep>) -> Self {
+        let first = steps.first().cloned().unwrap_or_default();
+        Self {
+            steps,
+            ..Self::new(name, first.mode, first.search, first.replace)
+        }
+    }
+
+    /// Get the rename pipeline of this preset
+    pub fn pipeline(&self) -> Vec<RenameStep> {
+        if self.steps.is_empty() {
+            vec![RenameStep::new(self.mode, &self.search, &self.replace)]
+        } else {
+            self.steps.clone()
+        }
+    }
+}
+
+/// A rule of a rule set: files matching the condition are renamed by the steps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    /// Name shown in the preview, the condition if empty
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    /// Condition as filter expression (e.g. "ext:jpg,jpeg" or "*.mp4 size:1M.."),
+    /// empty matches every file
+    #[serde(default)]
+    pub when: String,
+    /// Rename steps for matching files, none leaves them alone
+    #[serde(default)]
+    pub steps: Vec<RenameStep>,
+}
+
+impl Rule {
+    /// Name for the preview
+    pub fn label(&self) -> &str {
+        if !self.name.is_empty() {
+            &self.name
+        } else if !self.when.is_empty() {
+            &self.when
+        } else {
+            "alle"
+        }
+    }
+}
+
+/// Rules checked in order for every file, the first matching rule renames it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSet {
+    /// Name of the rule set
+    pub name: String,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Steps for files no rule matches, none leaves them alone
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback: Vec<RenameStep>,
 }
 
 /// Application configuration
@@ -171,9 +246,146 @@ pub struct Config {
     #[serde(default)]
     pub default_sort: SortOrder,
 
+    /// Date sources tried in order when the selected one has no date
+    #[serde(default = "default_date_fallback")]
+    pub date_fallback: Vec<DateSource>,
+
+    /// Extension aliases for normalizing extensions (e.g. jpeg = "jpg")
+    #[serde(default = "default_extension_aliases")]
+    pub extension_aliases: HashMap<String, String>,
+
+    /// Rules for title case mode
+    #[serde(default)]
+    pub title_case: TitleCaseConfig,
+
+    /// Naming schemes for media mode
+    #[serde(default)]
+    pub media: MediaConfig,
+
+    /// Rename directories too, not only files
+    #[serde(default)]
+    pub include_dirs: bool,
+
+    /// Directory levels to list (1 = only the directory itself, 0 = unlimited)
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize,
+
     /// Saved presets
     #[serde(default)]
     pub presets: HashMap<String, Preset>,
+
+    /// Saved rule sets
+    #[serde(default)]
+    pub rule_sets: HashMap<String, RuleSet>,
+}
+
+/// Language whose stop words stay lowercase in title case
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TitleLanguage {
+    #[default]
+    English,
+    German,
+    French,
+    /// Capitalize every word
+    None,
+}
+
+impl TitleLanguage {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TitleLanguage::English => "Englisch",
+            TitleLanguage::German => "Deutsch",
+            TitleLanguage::French => "Franzoesisch",
+            TitleLanguage::None => "Keine",
+        }
+    }
+}
+
+/// Title case rules: stop word language, extra stop words and preserved words
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TitleCaseConfig {
+    /// Built-in stop word list to use
+    #[serde(default)]
+    pub language: TitleLanguage,
+
+    /// Additional words that stay lowercase unless first or last
+    #[serde(default)]
+    pub stop_words: Vec<String>,
+
+    /// Words kept exactly as written (e.g. "iPhone", "NASA")
+    #[serde(default = "default_preserved_words")]
+    pub preserve: Vec<String>,
+}
+
+impl Default for TitleCaseConfig {
+    fn default() -> Self {
+        Self {
+            language: TitleLanguage::default(),
+            stop_words: Vec::new(),
+            preserve: default_preserved_words(),
+        }
+    }
+}
+
+/// Naming schemes for episodes and movies in media mode
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaConfig {
+    /// Scheme for episodes, e.g. "{show} - S{season:02}E{episode:02} - {title}.{ext}"
+    #[serde(default = "default_episode_format")]
+    pub episode_format: String,
+
+    /// Scheme for movies, e.g. "{title} ({year}).{ext}"
+    #[serde(default = "default_movie_format")]
+    pub movie_format: String,
+}
+
+impl Default for MediaConfig {
+    fn default() -> Self {
+        Self {
+            episode_format: default_episode_format(),
+            movie_format: default_movie_format(),
+        }
+    }
+}
+
+fn default_episode_format() -> String {
+    "{show} - S{season:02}E{episode:02} - {title}.{ext}".to_string()
+}
+
+fn default_movie_format() -> String {
+    "{title} ({year}).{ext}".to_string()
+}
+
+fn default_preserved_words() -> Vec<String> {
+    [
+        "iPhone", "iPad", "iOS", "macOS", "NASA", "McDonald", "YouTube", "DVD", "TV", "USA", "UK",
+    ]
+    .iter()
+    .map(|word| word.to_string())
+    .collect()
+}
+
+fn default_date_fallback() -> Vec<DateSource> {
+    vec![DateSource::Modified]
+}
+
+fn default_max_depth() -> usize {
+    1
+}
+
+fn default_extension_aliases() -> HashMap<String, String> {
+    [
+        ("jpeg", "jpg"),
+        ("jpe", "jpg"),
+        ("tif", "tiff"),
+        ("htm", "html"),
+        ("yml", "yaml"),
+        ("mpeg", "mpg"),
+        ("tgz", "tar.gz"),
+    ]
+    .into_iter()
+    .map(|(alias, ext)| (alias.to_string(), ext.to_string()))
+    .collect()
 }
 
 impl Default for Config {
@@ -181,7 +393,14 @@ impl Default for Config {
         Self {
             default_mode: RenameMode::SearchReplace,
             default_sort: SortOrder::Name,
+            date_fallback: default_date_fallback(),
+            extension_aliases: default_extension_aliases(),
+            title_case: TitleCaseConfig::default(),
+            media: MediaConfig::default(),
+            include_dirs: false,
+            max_depth: default_max_depth(),
             presets: HashMap::new(),
+            rule_sets: HashMap::new(),
         }
     }
 }
@@ -192,6 +411,11 @@ impl Config {
         dirs::config_dir().map(|p| p.join("rnm").join("config.toml"))
     }
 
+    /// Get the directory with rename scripts (*.rhai)
+    pub fn scripts_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|p| p.join("rnm").join("scripts"))
+    }
+
     /// Load config from file, or return default if file doesn't exist
     pub fn load() -> Result<Self> {
         let path = match Self::config_path() {
@@ -250,9 +474,28 @@ impl Config {
         self.presets.get(name)
     }
 
-    /// List all preset names
+    /// List all preset names (sorted)
     pub fn list_presets(&self) -> Vec<&str> {
-        self.presets.keys().map(|s| s.as_str()).collect()
+        let mut names: Vec<&str> = self.presets.keys().map(|s| s.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    /// Add or update a rule set
+    pub fn add_rule_set(&mut self, rule_set: RuleSet) {
+        self.rule_sets.insert(rule_set.name.clone(), rule_set);
+    }
+
+    /// Get a rule set by name
+    pub fn get_rule_set(&self, name: &str) -> Option<&RuleSet> {
+        self.rule_sets.get(name)
+    }
+
+    /// List all rule set names (sorted)
+    pub fn list_rule_sets(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.rule_sets.keys().map(|s| s.as_str()).collect();
+        names.sort();
+        names
     }
 }
 
@@ -262,16 +505,242 @@ pub fn parse_mode(mode_str: &str) -> Option<RenameMode> {
         "search" | "searchreplace" | "search-replace" | "s" => Some(RenameMode::SearchReplace),
         "regex" | "r" => Some(RenameMode::Regex),
         "numbering" | "number" | "num" | "n" => Some(RenameMode::Numbering),
+        "pad" | "pad-numbers" | "padnumbers" => Some(RenameMode::PadNumbers),
         "prefix" | "pre" => Some(RenameMode::Prefix),
         "suffix" | "suf" => Some(RenameMode::Suffix),
+        "position" | "pos" => Some(RenameMode::Position),
         "date" | "dateinsert" | "date-insert" | "d" => Some(RenameMode::DateInsert),
+        "template" | "tpl" => Some(RenameMode::Template),
+        "tags" | "audio" | "audiotags" => Some(RenameMode::AudioTags),
+        "script" | "rhai" => Some(RenameMode::Script),
+        "organize" | "ordnen" => Some(RenameMode::Organize),
+        "media" | "tv" | "movie" => Some(RenameMode::Media),
+        "ext" | "extension" | "e" => Some(RenameMode::Extension),
+        "detect" | "sniff" | "fix-ext" | "fixext" => Some(RenameMode::FixExtension),
+        "hash" | "checksum" => Some(RenameMode::Hash),
+        "sanitize" | "clean" => Some(RenameMode::Sanitize),
         "upper" | "uppercase" | "u" => Some(RenameMode::Uppercase),
         "lower" | "lowercase" | "l" => Some(RenameMode::Lowercase),
         "title" | "titlecase" | "t" => Some(RenameMode::TitleCase),
+        "snake" | "snake_case" | "snakecase" => Some(RenameMode::SnakeCase),
+        "kebab" | "kebab-case" | "kebabcase" => Some(RenameMode::KebabCase),
+        "camel" | "camelcase" => Some(RenameMode::CamelCase),
+        "pascal" | "pascalcase" => Some(RenameMode::PascalCase),
+        "dot" | "dot.case" | "dotcase" => Some(RenameMode::DotCase),
+        "sentence" | "sentencecase" => Some(RenameMode::SentenceCase),
         _ => None,
     }
 }
 
+/// Parse a pipeline step from CLI argument (format: mode[:search[:replace]])
+pub fn parse_step(step_str: &str) -> Option<RenameStep> {
+    let mut parts = step_str.splitn(3, ':');
+    let mode_str = parts.next()?;
+    if let Some(action) = parse_position_action(mode_str) {
+        // insert:TEXT[:POS], delete:RANGE, keep:RANGE, cut-after:DELIM[:N]
+        let mut step = RenameStep::new(
+            RenameMode::Position,
+            parts.next().unwrap_or(""),
+            parts.next().unwrap_or(""),
+        );
+        step.position_action = action;
+        return Some(step);
+    }
+    if mode_str.eq_ignore_ascii_case("renumber") {
+        // renumber[:pattern[:offset]], e.g. "renumber:####" or "renumber::10"
+        let mut step = RenameStep::new(
+            RenameMode::Numbering,
+            parts.next().unwrap_or(""),
+            parts.next().unwrap_or(""),
+        );
+        step.renumber = true;
+        return Some(step);
+    }
+    let mode = parse_mode(mode_str)?;
+    if matches!(
+        mode,
+        RenameMode::Template
+            | RenameMode::AudioTags
+            | RenameMode::Organize
+            | RenameMode::Media
+            | RenameMode::Script
+    ) {
+        // Templates may contain ':' themselves, e.g. "template:{n:03}_{stem}.{ext}"
+        let pattern = step_str.split_once(':').map_or("", |(_, pattern)| pattern);
+        return Some(RenameStep::new(mode, pattern, ""));
+    }
+    if mode == RenameMode::Extension {
+        // ext:action[:extension], e.g. "ext:set:png" or "ext:normalize"
+        let action = parts.next().unwrap_or("normalize");
+        let (action, value) = match parts.next() {
+            Some(value) => parse_extension_action(&format!("{}:{}", action, value))?,
+            None => parse_extension_action(action)?,
+        };
+        let mut step = RenameStep::new(mode, &value, "");
+        step.extension_action = action;
+        return Some(step);
+    }
+    if mode == RenameMode::PadNumbers {
+        // pad[:width[:first|last|all]], e.g. "pad" (auto width) or "pad:3:all"
+        let mut step = RenameStep::new(mode, parts.next().unwrap_or(""), "");
+        if let Some(target) = parts.next() {
+            step.pad_target = parse_number_target(target)?;
+        }
+        return Some(step);
+    }
+    if mode == RenameMode::Hash {
+        // hash[:algorithm[:length]], e.g. "hash:blake3:12"
+        let mut step = RenameStep::new(mode, "", "_");
+        if let Some(algorithm) = parts.next().filter(|a| !a.is_empty()) {
+            step.hash_algorithm = parse_hash_algorithm(algorithm)?;
+        }
+        step.search = parts.next().unwrap_or("").to_string();
+        return Some(step);
+    }
+    if mode == RenameMode::Sanitize {
+        // sanitize[:profile], e.g. "sanitize:windows"
+        let mut step = RenameStep::new(mode, "", "");
+        if let Some(profile) = parts.next() {
+            step.sanitize_profile = parse_sanitize_profile(profile)?;
+        }
+        return Some(step);
+    }
+    let search = parts.next().unwrap_or("");
+    let replace = parts.next().unwrap_or("");
+    Some(RenameStep::new(mode, search, replace))
+}
+
+/// Parse a rule from CLI argument (format: condition=>step), e.g. "ext:jpg=>date"
+/// or "*.tmp=>none" (leave matching files alone)
+pub fn parse_rule(rule_str: &str) -> Option<Rule> {
+    let (when, step_str) = rule_str.split_once("=>")?;
+    let step_str = step_str.trim();
+    let steps = if step_str.eq_ignore_ascii_case("none") {
+        Vec::new()
+    } else {
+        vec![parse_step(step_str)?]
+    };
+    Some(Rule {
+        name: String::new(),
+        when: when.trim().to_string(),
+        steps,
+    })
+}
+
+/// Parse checksum algorithm from CLI argument
+pub fn parse_hash_algorithm(algorithm_str: &str) -> Option<HashAlgorithm> {
+    match algorithm_str.to_lowercase().as_str() {
+        "sha256" | "sha-256" | "sha" => Some(HashAlgorithm::Sha256),
+        "blake3" | "b3" => Some(HashAlgorithm::Blake3),
+        "crc32" | "crc" => Some(HashAlgorithm::Crc32),
+        _ => None,
+    }
+}
+
+/// Parse counter format from CLI argument
+pub fn parse_number_format(format_str: &str) -> Option<NumberFormat> {
+    match format_str.to_lowercase().as_str() {
+        "decimal" | "dec" | "dezimal" => Some(NumberFormat::Decimal),
+        "alpha" | "letters" | "abc" | "buchstaben" => Some(NumberFormat::Alpha),
+        "roman" | "roemisch" => Some(NumberFormat::Roman),
+        "hex" | "hexadecimal" => Some(NumberFormat::Hex),
+        _ => None,
+    }
+}
+
+/// Parse regex flags from CLI argument, e.g. "i", "i,first", "nth=2,m" or "all"
+pub fn parse_regex_flags(flags_str: &str) -> Option<RegexFlags> {
+    let mut flags = RegexFlags::default();
+    for flag in flags_str
+        .split([',', ' '])
+        .map(|f| f.trim().to_lowercase())
+        .filter(|f| !f.is_empty())
+    {
+        match flag.as_str() {
+            "i" | "ignore-case" | "nocase" => flags.ignore_case = true,
+            "m" | "multi" | "multiline" | "multi-line" => flags.multi_line = true,
+            "g" | "all" | "alle" => flags.occurrence = 0,
+            "first" | "erster" => flags.occurrence = 1,
+            other => {
+                let n = other.strip_prefix("nth=").unwrap_or(other);
+                flags.occurrence = n.parse().ok().filter(|&n| n > 0)?;
+            }
+        }
+    }
+    Some(flags)
+}
+
+/// Parse which numbers to pad from CLI argument
+pub fn parse_number_target(target_str: &str) -> Option<NumberTarget> {
+    match target_str.to_lowercase().as_str() {
+        "first" | "erste" => Some(NumberTarget::First),
+        "last" | "letzte" => Some(NumberTarget::Last),
+        "all" | "alle" => Some(NumberTarget::All),
+        _ => None,
+    }
+}
+
+/// Parse counter group from CLI argument
+pub fn parse_counter_group(group_str: &str) -> Option<CounterGroup> {
+    match group_str.to_lowercase().as_str() {
+        "all" | "none" | "alle" => Some(CounterGroup::All),
+        "ext" | "extension" | "endung" => Some(CounterGroup::Extension),
+        "dir" | "directory" | "parent" | "ordner" => Some(CounterGroup::Directory),
+        "day" | "date" | "tag" => Some(CounterGroup::Day),
+        _ => None,
+    }
+}
+
+/// Parse title case language from CLI argument
+pub fn parse_title_language(language_str: &str) -> Option<TitleLanguage> {
+    match language_str.to_lowercase().as_str() {
+        "en" | "english" | "englisch" => Some(TitleLanguage::English),
+        "de" | "german" | "deutsch" => Some(TitleLanguage::German),
+        "fr" | "french" | "franzoesisch" => Some(TitleLanguage::French),
+        "none" | "keine" => Some(TitleLanguage::None),
+        _ => None,
+    }
+}
+
+/// Parse sanitize profile string from CLI argument
+pub fn parse_sanitize_profile(profile_str: &str) -> Option<SanitizeProfile> {
+    match profile_str.to_lowercase().as_str() {
+        "posix" | "portable" | "linux" => Some(SanitizeProfile::Posix),
+        "windows" | "win" | "ntfs" => Some(SanitizeProfile::Windows),
+        "fat" | "fat32" | "exfat" | "usb" => Some(SanitizeProfile::Fat),
+        "url" | "web" => Some(SanitizeProfile::Url),
+        _ => None,
+    }
+}
+
+/// Parse a position action from CLI argument
+pub fn parse_position_action(action_str: &str) -> Option<PositionAction> {
+    match action_str.to_lowercase().as_str() {
+        "insert" | "ins" => Some(PositionAction::Insert),
+        "delete" | "del" => Some(PositionAction::Delete),
+        "keep" | "crop" => Some(PositionAction::Keep),
+        "cut-after" | "cutafter" | "cut" => Some(PositionAction::CutAfter),
+        _ => None,
+    }
+}
+
+/// Parse an extension action from CLI argument (format: action[:extension])
+pub fn parse_extension_action(action_str: &str) -> Option<(ExtensionAction, String)> {
+    let (action, value) = match action_str.split_once(':') {
+        Some((action, value)) => (action, value.trim_start_matches('.')),
+        None => (action_str, ""),
+    };
+    let action = match action.to_lowercase().as_str() {
+        "set" | "s" => ExtensionAction::Set,
+        "lower" | "lowercase" | "l" => ExtensionAction::Lowercase,
+        "normalize" | "norm" | "n" => ExtensionAction::Normalize,
+        "remove" | "rm" | "r" => ExtensionAction::Remove,
+        "add" | "a" => ExtensionAction::Add,
+        _ => return None,
+    };
+    Some((action, value.to_string()))
+}
+
 /// Parse date position string from CLI argument
 pub fn parse_date_position(position_str: &str) -> Option<DatePosition> {
     match position_str.to_lowercase().as_str() {
@@ -282,6 +751,26 @@ pub fn parse_date_position(position_str: &str) -> Option<DatePosition> {
     }
 }
 
+/// Parse date source string from CLI argument
+pub fn parse_date_source(source_str: &str) -> Option<DateSource> {
+    match source_str.trim().to_lowercase().as_str() {
+        "mtime" | "modified" | "m" => Some(DateSource::Modified),
+        "ctime" | "changed" | "c" => Some(DateSource::Changed),
+        "birth" | "btime" | "created" | "b" => Some(DateSource::Created),
+        "exif" | "e" => Some(DateSource::Exif),
+        "now" | "n" => Some(DateSource::Now),
+        _ => None,
+    }
+}
+
+/// Parse a comma separated list of date sources (e.g. "exif,birth,mtime")
+pub fn parse_date_sources(list: &str) -> Option<Vec<DateSource>> {
+    list.split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(parse_date_source)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -322,9 +811,216 @@ mod tests {
         assert_eq!(parse_mode("title"), Some(RenameMode::TitleCase));
         assert_eq!(parse_mode("search"), Some(RenameMode::SearchReplace));
         assert_eq!(parse_mode("date"), Some(RenameMode::DateInsert));
+        assert_eq!(parse_mode("tags"), Some(RenameMode::AudioTags));
+        assert_eq!(parse_mode("rhai"), Some(RenameMode::Script));
+        assert_eq!(parse_mode("organize"), Some(RenameMode::Organize));
+        assert_eq!(parse_mode("tv"), Some(RenameMode::Media));
+        assert_eq!(parse_mode("fix-ext"), Some(RenameMode::FixExtension));
+        assert_eq!(parse_mode("sanitize"), Some(RenameMode::Sanitize));
+        assert_eq!(parse_mode("snake"), Some(RenameMode::SnakeCase));
+        assert_eq!(parse_mode("camelCase"), Some(RenameMode::CamelCase));
+        assert_eq!(parse_mode("template"), Some(RenameMode::Template));
         assert_eq!(parse_mode("invalid"), None);
     }
 
+    #[test]
+    fn test_parse_extension_action() {
+        assert_eq!(
+            parse_extension_action("set:.png"),
+            Some((ExtensionAction::Set, "png".to_string()))
+        );
+        assert_eq!(
+            parse_extension_action("add:tar.gz"),
+            Some((ExtensionAction::Add, "tar.gz".to_string()))
+        );
+        assert_eq!(
+            parse_extension_action("normalize"),
+            Some((ExtensionAction::Normalize, String::new()))
+        );
+        assert_eq!(parse_extension_action("invalid"), None);
+
+        let step = parse_step("ext:set:png").unwrap();
+        assert_eq!(step.mode, RenameMode::Extension);
+        assert_eq!(step.extension_action, ExtensionAction::Set);
+        assert_eq!(step.search, "png");
+        assert_eq!(
+            parse_step("ext:remove").unwrap().extension_action,
+            ExtensionAction::Remove
+        );
+    }
+
+    #[test]
+    fn test_parse_position_steps() {
+        let step = parse_step("insert:_v2:-0").unwrap();
+        assert_eq!(step.mode, RenameMode::Position);
+        assert_eq!(step.position_action, PositionAction::Insert);
+        assert_eq!((step.search.as_str(), step.replace.as_str()), ("_v2", "-0"));
+
+        let step = parse_step("cut-after: - :2").unwrap();
+        assert_eq!(step.position_action, PositionAction::CutAfter);
+        assert_eq!((step.search.as_str(), step.replace.as_str()), (" - ", "2"));
+
+        assert_eq!(
+            parse_step("keep:..8").unwrap().position_action,
+            PositionAction::Keep
+        );
+        assert_eq!(parse_mode("pos"), Some(RenameMode::Position));
+    }
+
+    #[test]
+    fn test_parse_sanitize_profile() {
+        assert_eq!(
+            parse_sanitize_profile("NTFS"),
+            Some(SanitizeProfile::Windows)
+        );
+        assert_eq!(parse_sanitize_profile("exfat"), Some(SanitizeProfile::Fat));
+        assert_eq!(parse_sanitize_profile("ext4"), None);
+
+        let step = parse_step("sanitize:url").unwrap();
+        assert_eq!(step.sanitize_profile, SanitizeProfile::Url);
+        assert_eq!(
+            parse_step("sanitize").unwrap().sanitize_profile,
+            SanitizeProfile::Posix
+        );
+        assert!(parse_step("sanitize:ext4").is_none());
+    }
+
+    #[test]
+    fn test_parse_numbering_options() {
+        assert_eq!(parse_number_format("Roman"), Some(NumberFormat::Roman));
+        assert_eq!(parse_number_format("abc"), Some(NumberFormat::Alpha));
+        assert_eq!(parse_number_format("octal"), None);
+        assert_eq!(parse_counter_group("ext"), Some(CounterGroup::Extension));
+        assert_eq!(parse_counter_group("day"), Some(CounterGroup::Day));
+        assert_eq!(parse_counter_group("week"), None);
+
+        let step = parse_step("renumber:####:-5").unwrap();
+        assert_eq!(step.mode, RenameMode::Numbering);
+        assert!(step.renumber);
+        assert_eq!(step.search, "####");
+        assert_eq!(step.replace, "-5");
+        assert!(!parse_step("num:img_##").unwrap().renumber);
+    }
+
+    #[test]
+    fn test_parse_regex_flags() {
+        let flags = parse_regex_flags("i,first").unwrap();
+        assert!(flags.ignore_case);
+        assert!(!flags.multi_line);
+        assert_eq!(flags.occurrence, 1);
+
+        let flags = parse_regex_flags("nth=3, m").unwrap();
+        assert!(flags.multi_line);
+        assert_eq!(flags.occurrence, 3);
+
+        assert_eq!(parse_regex_flags("2").unwrap().occurrence, 2);
+        assert_eq!(parse_regex_flags("").unwrap(), RegexFlags::default());
+        assert!(parse_regex_flags("nth=0").is_none());
+        assert!(parse_regex_flags("x").is_none());
+    }
+
+    #[test]
+    fn test_parse_hash_step() {
+        assert_eq!(parse_hash_algorithm("SHA-256"), Some(HashAlgorithm::Sha256));
+        assert_eq!(parse_hash_algorithm("md5"), None);
+
+        let step = parse_step("hash").unwrap();
+        assert_eq!(step.mode, RenameMode::Hash);
+        assert_eq!(step.hash_algorithm, HashAlgorithm::Sha256);
+        assert_eq!(step.hash_position, DatePosition::Replace);
+        assert_eq!(step.replace, "_");
+
+        let step = parse_step("hash:crc32:6").unwrap();
+        assert_eq!(step.hash_algorithm, HashAlgorithm::Crc32);
+        assert_eq!(step.search, "6");
+        assert!(parse_step("hash:md5").is_none());
+    }
+
+    #[test]
+    fn test_parse_pad_step() {
+        assert_eq!(parse_number_target("ALL"), Some(NumberTarget::All));
+        assert_eq!(parse_number_target("middle"), None);
+
+        let step = parse_step("pad").unwrap();
+        assert_eq!(step.mode, RenameMode::PadNumbers);
+        assert_eq!(step.search, "");
+        assert_eq!(step.pad_target, NumberTarget::Last);
+
+        let step = parse_step("pad:3:first").unwrap();
+        assert_eq!(step.search, "3");
+        assert_eq!(step.pad_target, NumberTarget::First);
+        assert!(parse_step("pad:3:middle").is_none());
+    }
+
+    #[test]
+    fn test_title_case_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.title_case.language, TitleLanguage::English);
+        assert!(config.title_case.preserve.contains(&"iPhone".to_string()));
+
+        let config: Config = toml::from_str(
+            "[title_case]\nlanguage = \"German\"\nstop_words = [\"vs\"]\npreserve = [\"GmbH\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.title_case.language, TitleLanguage::German);
+        assert_eq!(config.title_case.stop_words, vec!["vs"]);
+        assert_eq!(config.title_case.preserve, vec!["GmbH"]);
+
+        assert_eq!(parse_title_language("DE"), Some(TitleLanguage::German));
+        assert_eq!(parse_title_language("klingon"), None);
+    }
+
+    #[test]
+    fn test_media_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.media, MediaConfig::default());
+
+        let config: Config =
+            toml::from_str("[media]\nmovie_format = \"{title} [{year}].{ext}\"\n").unwrap();
+        assert_eq!(config.media.movie_format, "{title} [{year}].{ext}");
+        assert_eq!(config.media.episode_format, default_episode_format());
+    }
+
+    #[test]
+    fn test_extension_aliases_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.extension_aliases.get("jpeg").unwrap(), "jpg");
+
+        let config: Config = toml::from_str("[extension_aliases]\nmpeg4 = \"mp4\"\n").unwrap();
+        assert_eq!(config.extension_aliases.len(), 1);
+        assert_eq!(config.extension_aliases.get("mpeg4").unwrap(), "mp4");
+    }
+
+    #[test]
+    fn test_parse_date_source() {
+        assert_eq!(parse_date_source("exif"), Some(DateSource::Exif));
+        assert_eq!(parse_date_source("MTIME"), Some(DateSource::Modified));
+        assert_eq!(parse_date_source("birth"), Some(DateSource::Created));
+        assert_eq!(parse_date_source("invalid"), None);
+
+        assert_eq!(
+            parse_date_sources("exif, birth,mtime"),
+            Some(vec![
+                DateSource::Exif,
+                DateSource::Created,
+                DateSource::Modified
+            ])
+        );
+        assert_eq!(parse_date_sources("exif,nope"), None);
+    }
+
+    #[test]
+    fn test_date_fallback_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.date_fallback, vec![DateSource::Modified]);
+
+        let config: Config = toml::from_str("date_fallback = [\"Created\", \"Modified\"]").unwrap();
+        assert_eq!(
+            config.date_fallback,
+            vec![DateSource::Created, DateSource::Modified]
+        );
+    }
+
     #[test]
     fn test_parse_date_position() {
         assert_eq!(parse_date_position("prefix"), Some(DatePosition::Prefix));
@@ -336,8 +1032,10 @@ mod tests {
 
     #[test]
     fn test_config_serialization() {
-        let mut config = Config::default();
-        config.default_mode = RenameMode::Uppercase;
+        let mut config = Config {
+            default_mode: RenameMode::Uppercase,
+            ..Config::default()
+        };
         config.add_preset(Preset::new(
             "my-preset".to_string(),
             RenameMode::SearchReplace,
@@ -351,4 +1049,160 @@ mod tests {
         assert_eq!(loaded.default_mode, RenameMode::Uppercase);
         assert!(loaded.get_preset("my-preset").is_some());
     }
+
+    #[test]
+    fn test_parse_step() {
+        let step = parse_step("search: :_").unwrap();
+        assert_eq!(step.mode, RenameMode::SearchReplace);
+        assert_eq!(step.search, " ");
+        assert_eq!(step.replace, "_");
+
+        let step = parse_step("regex:(\\d+):n$1:x").unwrap();
+        assert_eq!(step.search, "(\\d+)");
+        assert_eq!(step.replace, "n$1:x");
+
+        assert_eq!(parse_step("lower").unwrap().mode, RenameMode::Lowercase);
+        assert!(parse_step("invalid:foo").is_none());
+
+        // Everything after the mode is the template, ':' included
+        let step = parse_step("template:{n:03}_{stem}.{ext}").unwrap();
+        assert_eq!(step.mode, RenameMode::Template);
+        assert_eq!(step.search, "{n:03}_{stem}.{ext}");
+        assert_eq!(step.replace, "");
+
+        let rule = parse_rule("ext:jpg=>organize:{date:%Y}/{name}").unwrap();
+        assert_eq!(rule.when, "ext:jpg");
+        assert_eq!(rule.steps[0].mode, RenameMode::Organize);
+        assert_eq!(rule.steps[0].search, "{date:%Y}/{name}");
+    }
+
+    #[test]
+    fn test_preset_pipeline_serialization() {
+        let mut config = Config::default();
+        config.add_preset(Preset::with_steps(
+            "cleanup".to_string(),
+            vec![
+                RenameStep::new(RenameMode::Lowercase, "", ""),
+                RenameStep::new(RenameMode::SearchReplace, " ", "_"),
+                RenameStep::new(RenameMode::DateInsert, "", ""),
+            ],
+        ));
+
+        let toml_str = toml::to_string_pretty(&config).unwrap();
+        let loaded: Config = toml::from_str(&toml_str).unwrap();
+
+        let preset = loaded.get_preset("cleanup").unwrap();
+        assert_eq!(preset.mode, RenameMode::Lowercase);
+        let steps = preset.pipeline();
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[1].search, " ");
+        assert_eq!(steps[2].mode, RenameMode::DateInsert);
+    }
+
+    #[test]
+    fn test_preset_filter_serialization() {
+        let mut preset = Preset::with_steps(
+            "fotos".to_string(),
+            vec![RenameStep::new(RenameMode::Lowercase, "", "")],
+        );
+        preset.filter = crate::filter::parse_filter("IMG_* ext:jpg size:1M..").unwrap();
+        let mut config = Config::default();
+        config.add_preset(preset);
+
+        let toml_str = toml::to_string_pretty(&config).unwrap();
+        let loaded: Config = toml::from_str(&toml_str).unwrap();
+        let filter = &loaded.get_preset("fotos").unwrap().filter;
+        assert_eq!(filter.glob.as_deref(), Some("IMG_*"));
+        assert_eq!(filter.extensions, ["jpg"]);
+        assert_eq!(filter.min_size, Some(1 << 20));
+
+        // Presets without filter apply to all files
+        let preset: Preset = toml::from_str("name = \"alt\"\nmode = \"Lowercase\"").unwrap();
+        assert!(preset.filter.is_empty());
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let rule = parse_rule("ext:jpg,jpeg => date").unwrap();
+        assert_eq!(rule.when, "ext:jpg,jpeg");
+        assert_eq!(rule.label(), "ext:jpg,jpeg");
+        assert_eq!(rule.steps[0].mode, RenameMode::DateInsert);
+
+        let rule = parse_rule("*.mp4=>numbering:clip_###").unwrap();
+        assert_eq!(rule.steps[0].search, "clip_###");
+
+        assert!(parse_rule("*.tmp=>none").unwrap().steps.is_empty());
+        assert!(parse_rule("*.jpg").is_none());
+        assert!(parse_rule("*.jpg=>invalid").is_none());
+    }
+
+    #[test]
+    fn test_rule_set_config() {
+        let toml_str = r#"
+            [rule_sets.medien]
+            name = "medien"
+            fallback = [{ mode = "Lowercase" }]
+
+            [[rule_sets.medien.rules]]
+            name = "Fotos"
+            when = "ext:jpg,jpeg"
+            steps = [{ mode = "DateInsert", date_format = "%Y-%m-%d" }]
+
+            [[rule_sets.medien.rules]]
+            when = "*.mp4"
+            steps = [{ mode = "Numbering", search = "clip_###" }]
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let rule_set = config.get_rule_set("medien").unwrap();
+        assert_eq!(rule_set.rules.len(), 2);
+        assert_eq!(rule_set.rules[0].label(), "Fotos");
+        assert_eq!(rule_set.rules[0].steps[0].date_format, "%Y-%m-%d");
+        assert_eq!(rule_set.rules[1].label(), "*.mp4");
+        assert_eq!(rule_set.fallback[0].mode, RenameMode::Lowercase);
+        assert_eq!(config.list_rule_sets(), ["medien"]);
+
+        let saved = toml::to_string_pretty(&config).unwrap();
+        let loaded: Config = toml::from_str(&saved).unwrap();
+        assert_eq!(
+            loaded.get_rule_set("medien").unwrap().rules[1].steps[0].search,
+            "clip_###"
+        );
+    }
+
+    #[test]
+    fn test_legacy_preset_pipeline() {
+        let preset = Preset::new(
+            "legacy".to_string(),
+            RenameMode::SearchReplace,
+            "IMG_".to_string(),
+            "photo_".to_string(),
+        );
+
+        let steps = preset.pipeline();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].search, "IMG_");
+        assert_eq!(steps[0].replace, "photo_");
+    }
+
+    #[test]
+    fn test_date_step_defaults() {
+        let toml_str = r#"
+            name = "dated"
+            mode = "DateInsert"
+
+            [[steps]]
+            mode = "DateInsert"
+
+            [[steps]]
+            mode = "DateInsert"
+            date_format = "%Y-%m-%d_%H%M"
+            date_separator = " "
+        "#;
+        let preset: Preset = toml::from_str(toml_str).unwrap();
+        let steps = preset.pipeline();
+        assert_eq!(steps[0].date_format, "%Y%m%d");
+        assert_eq!(steps[0].date_separator, "_");
+        assert_eq!(steps[1].date_format, "%Y-%m-%d_%H%M");
+        assert_eq!(steps[1].date_separator, " ");
+    }
 }
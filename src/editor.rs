@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::app::FileEntry;
+use crate::operations::{target_error, RenamePreview};
+
+/// Editor command from $VISUAL or $EDITOR, "vi" if neither is set
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Let the user edit the names in $EDITOR, one per line. Returns the edited text.
+pub fn edit_names(names: &[String]) -> Result<String> {
+    if let Some(name) = names.iter().find(|name| name.contains(['\n', '\r'])) {
+        return Err(anyhow!(
+            "Dateiname mit Zeilenumbruch kann nicht bearbeitet werden: {:?}",
+            name
+        ));
+    }
+
+    let mut content = names.join("\n");
+    content.push('\n');
+    // Removed again when it goes out of scope, also on errors
+    let file = EditFile::create(&content)?;
+
+    // Commands like "code --wait" come with arguments
+    let command = editor_command();
+    let mut parts = command.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program).args(parts).arg(&file.path).status();
+
+    let edited = fs::read_to_string(&file.path);
+
+    let status = status.with_context(|| format!("Editor nicht startbar: {}", command))?;
+    if !status.success() {
+        return Err(anyhow!("Editor ohne Erfolg beendet ({})", status));
+    }
+    Ok(edited?)
+}
+
+/// Temporary file for the editor inside a new directory only the user can
+/// access, so nobody else can plant a symlink in its place. Both are removed on drop.
+struct EditFile {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl EditFile {
+    fn create(content: &str) -> Result<Self> {
+        let base = std::env::temp_dir();
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+
+        for attempt in 0..100 {
+            let dir = base.join(format!(
+                "rnm-edit-{}-{}-{}",
+                std::process::id(),
+                nanos,
+                attempt
+            ));
+            match create_private_dir(&dir) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!("Temporaerer Ordner nicht anlegbar: {}", dir.display())
+                    })
+                }
+            }
+
+            let file = Self {
+                path: dir.join("rnm-edit.txt"),
+                dir,
+            };
+            OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&file.path)
+                .and_then(|mut handle| handle.write_all(content.as_bytes()))
+                .with_context(|| {
+                    format!("Temporaere Datei nicht schreibbar: {}", file.path.display())
+                })?;
+            return Ok(file);
+        }
+        Err(anyhow!(
+            "Kein freier temporaerer Ordner in {}",
+            base.display()
+        ))
+    }
+}
+
+impl Drop for EditFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        let _ = fs::remove_dir(&self.dir);
+    }
+}
+
+/// Create a directory that only the owner can read and write (fails if it exists)
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    fs::DirBuilder::new().mode(0o700).create(dir)
+}
+
+/// Create a directory (fails if it exists)
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir(dir)
+}
+
+/// Build previews from the edited names, line N is the new name of the Nth file
+/// of `indices`. Fails if lines were added or removed, are empty, or give the
+/// same name twice.
+pub fn edit_previews(
+    files: &[FileEntry],
+    indices: &[usize],
+    edited: &str,
+) -> Result<Vec<RenamePreview>> {
+    let lines: Vec<&str> = edited
+        .trim_end_matches(['\n', '\r'])
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .collect();
+    if lines.len() != indices.len() {
+        return Err(anyhow!(
+            "Zeilenanzahl geaendert: {} statt {} (Zeilen nicht loeschen oder hinzufuegen)",
+            lines.len(),
+            indices.len()
+        ));
+    }
+
+    let mut errors = Vec::new();
+    let mut targets: HashMap<&str, usize> = HashMap::new();
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            errors.push(format!("Zeile {} ist leer", i + 1));
+        } else if let Some(first) = targets.insert(line, i) {
+            errors.push(format!(
+                "Zeile {}: '{}' ist schon in Zeile {} vergeben",
+                i + 1,
+                line,
+                first + 1
+            ));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow!("Ungueltige Namen:\n{}", errors.join("\n")));
+    }
+
+    Ok(indices
+        .iter()
+        .zip(lines)
+        .map(|(&index, line)| {
+            let original_name = files[index].relative_name();
+            // Edited names stay in their folder, invalid ones are skipped
+            let error = (original_name != line)
+                .then(|| target_error(&original_name, line, false))
+                .flatten();
+            let new_name = if error.is_some() {
+                original_name.clone()
+            } else {
+                line.to_string()
+            };
+            RenamePreview {
+                will_change: original_name != new_name,
+                original_name,
+                new_name,
+                error,
+                type_mismatch: None,
+                rule: None,
+                file_index: index,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_file(name: &str) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(name),
+            name: name.to_string(),
+            subdir: PathBuf::new(),
+            is_dir: false,
+            size: 0,
+            modified: None,
+            extension: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_edit_previews() {
+        let files = [make_file("a.txt"), make_file("b.txt"), make_file("c.txt")];
+
+        let previews = edit_previews(&files, &[0, 1, 2], "eins.txt\r\nb.txt\nc d.txt\n\n").unwrap();
+        let names: Vec<_> = previews.iter().map(|p| p.new_name.as_str()).collect();
+        assert_eq!(names, ["eins.txt", "b.txt", "c d.txt"]);
+        assert!(previews[0].will_change);
+        assert!(!previews[1].will_change);
+
+        let previews = edit_previews(&files, &[2], "c.md\n").unwrap();
+        assert_eq!(previews[0].original_name, "c.txt");
+        assert_eq!(previews[0].file_index, 2);
+    }
+
+    #[test]
+    fn test_edit_previews_errors() {
+        let files = [make_file("a.txt"), make_file("b.txt")];
+
+        let err = edit_previews(&files, &[0, 1], "a.txt\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Zeilenanzahl geaendert: 1 statt 2"));
+
+        let err = edit_previews(&files, &[0, 1], "x.txt\nx.txt\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Zeile 2: 'x.txt' ist schon in Zeile 1 vergeben"));
+
+        let err = edit_previews(&files, &[0, 1], " \nb.txt\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Zeile 1 ist leer"));
+    }
+
+    #[test]
+    fn test_edit_file_is_private_and_removed() {
+        let file = EditFile::create("a.txt\n").unwrap();
+        let other = EditFile::create("b.txt\n").unwrap();
+        assert_ne!(file.dir, other.dir);
+        assert_eq!(fs::read_to_string(&file.path).unwrap(), "a.txt\n");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&file.dir).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        let dir = file.dir.clone();
+        drop(file);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_target_in_subfolder_is_rejected() {
+        let files = [make_file("a.txt"), make_file("b.txt")];
+        let previews = edit_previews(&files, &[0, 1], "neu/a.txt\nc.txt\n").unwrap();
+
+        assert!(!previews[0].will_change);
+        assert_eq!(previews[0].new_name, "a.txt");
+        assert_eq!(
+            previews[0].error.as_deref(),
+            Some("Ungueltiger Dateiname: neu/a.txt (Unterordner nur im Ordnen-Modus)")
+        );
+        assert!(previews[1].will_change);
+        assert_eq!(previews[1].new_name, "c.txt");
+    }
+}
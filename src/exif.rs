@@ -0,0 +1,674 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::SystemTime;
+
+use chrono::{FixedOffset, Local, NaiveDateTime, TimeZone};
+
+/// Maximum number of bytes read from a TIFF file when looking for the date
+const TIFF_READ_LIMIT: u64 = 1024 * 1024;
+
+/// Maximum size of an EXIF block embedded in a HEIF container
+const HEIF_EXIF_LIMIT: u64 = 1024 * 1024;
+
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_DATE_TIME_DIGITIZED: u16 = 0x9004;
+const TAG_OFFSET_TIME_ORIGINAL: u16 = 0x9011;
+
+/// Read the capture date (DateTimeOriginal) from a JPEG, TIFF or HEIC/HEIF file.
+/// Without a recorded UTC offset the camera time is taken as local time.
+pub fn read_capture_date(path: &Path) -> Option<SystemTime> {
+    let mut file = File::open(path).ok()?;
+    capture_date(&mut file)
+}
+
+/// Read the capture date from the content of an image file
+fn capture_date<R: Read + Seek>(file: &mut R) -> Option<SystemTime> {
+    let mut header = [0u8; 12];
+    let read = read_up_to(file, &mut header).ok()?;
+    let header = &header[..read];
+
+    let tiff = if header.starts_with(&[0xFF, 0xD8]) {
+        jpeg_exif_block(file)?
+    } else if header.starts_with(b"II*\0") || header.starts_with(b"MM\0*") {
+        file.seek(SeekFrom::Start(0)).ok()?;
+        let mut data = Vec::new();
+        file.take(TIFF_READ_LIMIT).read_to_end(&mut data).ok()?;
+        data
+    } else if header.len() >= 8 && &header[4..8] == b"ftyp" {
+        heif_exif_block(file)?
+    } else {
+        return None;
+    };
+
+    parse_tiff_date(&tiff)
+}
+
+/// Read into the buffer until it is full or the end of the file is reached
+fn read_up_to<R: Read>(file: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        let n = file.read(&mut buf[total..])?;
+        if n == 0 {
+            break;
+        }
+        total += n;
+    }
+    Ok(total)
+}
+
+/// Find the APP1 "Exif" segment of a JPEG file and return its TIFF data
+fn jpeg_exif_block<R: Read + Seek>(file: &mut R) -> Option<Vec<u8>> {
+    file.seek(SeekFrom::Start(2)).ok()?;
+
+    loop {
+        let mut byte = [0u8; 1];
+        file.read_exact(&mut byte).ok()?;
+        if byte[0] != 0xFF {
+            return None;
+        }
+        // Any number of 0xFF fill bytes may come before the marker
+        while byte[0] == 0xFF {
+            file.read_exact(&mut byte).ok()?;
+        }
+        let marker = byte[0];
+        // Start of scan or end of image: no more metadata segments
+        if marker == 0xDA || marker == 0xD9 {
+            return None;
+        }
+        // Markers without a length field
+        if (0xD0..=0xD7).contains(&marker) || marker == 0x01 {
+            continue;
+        }
+
+        let mut len = [0u8; 2];
+        file.read_exact(&mut len).ok()?;
+        let len = u16::from_be_bytes(len) as usize;
+        if len < 2 {
+            return None;
+        }
+
+        if marker == 0xE1 {
+            let mut data = vec![0u8; len - 2];
+            file.read_exact(&mut data).ok()?;
+            if let Some(tiff) = data.strip_prefix(b"Exif\0\0") {
+                return Some(tiff.to_vec());
+            }
+        } else {
+            file.seek(SeekFrom::Current(len as i64 - 2)).ok()?;
+        }
+    }
+}
+
+/// A box header of an ISO base media file (HEIF/HEIC, also used for MP4 tags)
+pub(crate) struct BoxHeader {
+    pub kind: [u8; 4],
+    /// Offset of the box content
+    pub content_start: u64,
+    /// Offset right after the box
+    pub end: u64,
+}
+
+pub(crate) fn read_box_header<R: Read + Seek>(
+    file: &mut R,
+    start: u64,
+    limit: u64,
+) -> Option<BoxHeader> {
+    if start.checked_add(8)? > limit {
+        return None;
+    }
+    file.seek(SeekFrom::Start(start)).ok()?;
+    let mut buf = [0u8; 8];
+    file.read_exact(&mut buf).ok()?;
+    let size = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) as u64;
+    let kind = [buf[4], buf[5], buf[6], buf[7]];
+
+    let (size, header_len) = match size {
+        0 => (limit - start, 8),
+        1 => {
+            let mut large = [0u8; 8];
+            file.read_exact(&mut large).ok()?;
+            (u64::from_be_bytes(large), 16)
+        }
+        _ => (size, 8),
+    };
+    if size < header_len {
+        return None;
+    }
+
+    // A size beyond u64 is a broken file
+    Some(BoxHeader {
+        kind,
+        content_start: start + header_len,
+        end: start.checked_add(size)?.min(limit),
+    })
+}
+
+/// Find a child box of the given type between `start` and `end`
+pub(crate) fn find_box<R: Read + Seek>(
+    file: &mut R,
+    mut start: u64,
+    end: u64,
+    kind: &[u8; 4],
+) -> Option<BoxHeader> {
+    while let Some(header) = read_box_header(file, start, end) {
+        if &header.kind == kind {
+            return Some(header);
+        }
+        if header.end <= start {
+            return None;
+        }
+        start = header.end;
+    }
+    None
+}
+
+/// Locate the Exif item of a HEIF container via the iinf and iloc boxes
+fn heif_exif_block<R: Read + Seek>(file: &mut R) -> Option<Vec<u8>> {
+    let file_len = file.seek(SeekFrom::End(0)).ok()?;
+    let meta = find_box(file, 0, file_len, b"meta")?;
+    // meta is a full box: skip version and flags
+    let children = meta.content_start + 4;
+
+    let iinf = find_box(file, children, meta.end, b"iinf")?;
+    let exif_id = heif_exif_item_id(file, &iinf)?;
+
+    let iloc = find_box(file, children, meta.end, b"iloc")?;
+    let (offset, length) = heif_item_location(file, &iloc, exif_id)?;
+    if !(4..=HEIF_EXIF_LIMIT).contains(&length) {
+        return None;
+    }
+
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut data = vec![0u8; length as usize];
+    file.read_exact(&mut data).ok()?;
+
+    // The payload starts with the offset to the TIFF header
+    let tiff_offset = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let tiff = data.get(4 + tiff_offset..)?;
+    Some(tiff.strip_prefix(b"Exif\0\0").unwrap_or(tiff).to_vec())
+}
+
+/// Read the content of a (small) box into memory
+fn read_box_content<R: Read + Seek>(file: &mut R, header: &BoxHeader) -> Option<Vec<u8>> {
+    let len = header.end.checked_sub(header.content_start)?;
+    if len > HEIF_EXIF_LIMIT {
+        return None;
+    }
+    file.seek(SeekFrom::Start(header.content_start)).ok()?;
+    let mut data = vec![0u8; len as usize];
+    file.read_exact(&mut data).ok()?;
+    Some(data)
+}
+
+/// Find the item id of the "Exif" item in the iinf box
+fn heif_exif_item_id<R: Read + Seek>(file: &mut R, iinf: &BoxHeader) -> Option<u32> {
+    let data = read_box_content(file, iinf)?;
+    let mut reader = ByteReader::new(&data, false);
+    let version = reader.u8()?;
+    reader.skip(3)?;
+    let count = if version == 0 {
+        reader.u16()? as u32
+    } else {
+        reader.u32()?
+    };
+
+    for _ in 0..count {
+        let box_start = reader.pos;
+        let size = reader.u32()? as usize;
+        let kind = reader.bytes(4)?;
+        if size < 8 {
+            return None;
+        }
+        if kind == b"infe" {
+            let infe_version = reader.u8()?;
+            reader.skip(3)?;
+            if infe_version >= 2 {
+                let id = if infe_version == 2 {
+                    reader.u16()? as u32
+                } else {
+                    reader.u32()?
+                };
+                reader.skip(2)?; // item_protection_index
+                if reader.bytes(4)? == b"Exif" {
+                    return Some(id);
+                }
+            }
+        }
+        reader.pos = box_start.checked_add(size)?;
+    }
+    None
+}
+
+/// Get the absolute file offset and length of an item from the iloc box
+fn heif_item_location<R: Read + Seek>(
+    file: &mut R,
+    iloc: &BoxHeader,
+    item_id: u32,
+) -> Option<(u64, u64)> {
+    let data = read_box_content(file, iloc)?;
+    let mut reader = ByteReader::new(&data, false);
+    let version = reader.u8()?;
+    reader.skip(3)?;
+
+    let sizes = reader.u8()?;
+    let offset_size = (sizes >> 4) as usize;
+    let length_size = (sizes & 0x0F) as usize;
+    let sizes = reader.u8()?;
+    let base_offset_size = (sizes >> 4) as usize;
+    let index_size = if version == 1 || version == 2 {
+        (sizes & 0x0F) as usize
+    } else {
+        0
+    };
+
+    let count = if version < 2 {
+        reader.u16()? as u32
+    } else {
+        reader.u32()?
+    };
+
+    for _ in 0..count {
+        let id = if version < 2 {
+            reader.u16()? as u32
+        } else {
+            reader.u32()?
+        };
+        if version == 1 || version == 2 {
+            let construction_method = reader.u16()? & 0x0F;
+            // Only data stored directly in the file is supported
+            if id == item_id && construction_method != 0 {
+                return None;
+            }
+        }
+        reader.skip(2)?; // data_reference_index
+        let base_offset = reader.uint(base_offset_size)?;
+        let extent_count = reader.u16()?;
+
+        let mut first_extent = None;
+        for _ in 0..extent_count {
+            reader.skip(index_size)?;
+            let offset = reader.uint(offset_size)?;
+            let length = reader.uint(length_size)?;
+            if first_extent.is_none() {
+                first_extent = Some((base_offset.checked_add(offset)?, length));
+            }
+        }
+
+        if id == item_id {
+            return first_extent;
+        }
+    }
+    None
+}
+
+/// Parse TIFF data and return DateTimeOriginal (or a fallback date tag)
+fn parse_tiff_date(tiff: &[u8]) -> Option<SystemTime> {
+    let little_endian = match tiff.get(..4)? {
+        b"II*\0" => true,
+        b"MM\0*" => false,
+        _ => return None,
+    };
+    let reader = ByteReader::new(tiff, little_endian);
+    let ifd0 = reader.u32_at(4)? as usize;
+
+    let mut fallback = None;
+    let mut exif_ifd = None;
+    for (tag, entry) in ifd_entries(&reader, ifd0)? {
+        match tag {
+            TAG_EXIF_IFD => exif_ifd = reader.u32_at(entry + 8).map(|o| o as usize),
+            TAG_DATE_TIME => fallback = ascii_value(&reader, entry),
+            _ => {}
+        }
+    }
+
+    let mut original = None;
+    let mut digitized = None;
+    let mut offset = None;
+    if let Some(exif_ifd) = exif_ifd {
+        for (tag, entry) in ifd_entries(&reader, exif_ifd).unwrap_or_default() {
+            match tag {
+                TAG_DATE_TIME_ORIGINAL => original = ascii_value(&reader, entry),
+                TAG_DATE_TIME_DIGITIZED => digitized = ascii_value(&reader, entry),
+                TAG_OFFSET_TIME_ORIGINAL => offset = ascii_value(&reader, entry),
+                _ => {}
+            }
+        }
+    }
+
+    // The offset only belongs to DateTimeOriginal
+    if let Some(naive) = original.as_deref().and_then(parse_exif_datetime) {
+        if let Some(offset) = offset.as_deref().and_then(parse_exif_offset) {
+            return offset
+                .from_local_datetime(&naive)
+                .single()
+                .map(SystemTime::from);
+        }
+        return local_time(naive);
+    }
+
+    [digitized, fallback]
+        .into_iter()
+        .flatten()
+        .find_map(|value| parse_exif_datetime(&value))
+        .and_then(local_time)
+}
+
+/// Interpret a wall clock time in the local time zone
+fn local_time(naive: NaiveDateTime) -> Option<SystemTime> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(SystemTime::from)
+}
+
+/// List (tag, entry offset) pairs of an IFD
+fn ifd_entries(reader: &ByteReader, offset: usize) -> Option<Vec<(u16, usize)>> {
+    let count = reader.u16_at(offset)? as usize;
+    let mut entries = Vec::with_capacity(count);
+    for i in 0..count {
+        let entry = offset + 2 + i * 12;
+        entries.push((reader.u16_at(entry)?, entry));
+    }
+    Some(entries)
+}
+
+/// Read an ASCII value of an IFD entry
+fn ascii_value(reader: &ByteReader, entry: usize) -> Option<String> {
+    const TYPE_ASCII: u16 = 2;
+    if reader.u16_at(entry + 2)? != TYPE_ASCII {
+        return None;
+    }
+    let count = reader.u32_at(entry + 4)? as usize;
+    let start = if count <= 4 {
+        entry + 8
+    } else {
+        reader.u32_at(entry + 8)? as usize
+    };
+    let bytes = reader.data.get(start..start.checked_add(count)?)?;
+    let text: String = bytes
+        .iter()
+        .take_while(|&&b| b != 0)
+        .map(|&b| b as char)
+        .collect();
+    Some(text.trim().to_string())
+}
+
+/// Parse "YYYY:MM:DD HH:MM:SS" (cameras without a clock write zeros)
+fn parse_exif_datetime(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.get(..19)?, "%Y:%m:%d %H:%M:%S").ok()
+}
+
+/// Parse an EXIF offset like "+02:00"
+fn parse_exif_offset(value: &str) -> Option<FixedOffset> {
+    let sign = match value.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let hours: i32 = value.get(1..3)?.parse().ok()?;
+    let minutes: i32 = value.get(4..6)?.parse().ok()?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Bounds-checked reader for binary metadata
+struct ByteReader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8], little_endian: bool) -> Self {
+        Self {
+            data,
+            little_endian,
+            pos: 0,
+        }
+    }
+
+    fn u16_at(&self, offset: usize) -> Option<u16> {
+        let b = self.data.get(offset..offset.checked_add(2)?)?;
+        Some(if self.little_endian {
+            u16::from_le_bytes([b[0], b[1]])
+        } else {
+            u16::from_be_bytes([b[0], b[1]])
+        })
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        let b = self.data.get(offset..offset.checked_add(4)?)?;
+        Some(if self.little_endian {
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+        } else {
+            u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+        })
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let slice = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(slice)
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let value = self.u16_at(self.pos)?;
+        self.pos += 2;
+        Some(value)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let value = self.u32_at(self.pos)?;
+        self.pos += 4;
+        Some(value)
+    }
+
+    /// Read a big-endian unsigned integer of 0, 4 or 8 bytes
+    fn uint(&mut self, size: usize) -> Option<u64> {
+        match size {
+            0 => Some(0),
+            4 => self.u32().map(u64::from),
+            8 => {
+                let b = self.bytes(8)?;
+                Some(u64::from_be_bytes([
+                    b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7],
+                ]))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a minimal big-endian TIFF block with an Exif IFD
+    fn make_tiff(original: &str, offset: Option<&str>) -> Vec<u8> {
+        let mut tiff = b"MM\0*".to_vec();
+        tiff.extend_from_slice(&8u32.to_be_bytes());
+
+        // IFD0 at 8 with one entry pointing to the Exif IFD at 26
+        tiff.extend_from_slice(&1u16.to_be_bytes());
+        tiff.extend_from_slice(&TAG_EXIF_IFD.to_be_bytes());
+        tiff.extend_from_slice(&4u16.to_be_bytes());
+        tiff.extend_from_slice(&1u32.to_be_bytes());
+        tiff.extend_from_slice(&26u32.to_be_bytes());
+        tiff.extend_from_slice(&0u32.to_be_bytes());
+
+        let entries: u16 = if offset.is_some() { 2 } else { 1 };
+        let data_start = 26 + 2 + entries as u32 * 12 + 4;
+        tiff.extend_from_slice(&entries.to_be_bytes());
+        tiff.extend_from_slice(&TAG_DATE_TIME_ORIGINAL.to_be_bytes());
+        tiff.extend_from_slice(&2u16.to_be_bytes());
+        tiff.extend_from_slice(&20u32.to_be_bytes());
+        tiff.extend_from_slice(&data_start.to_be_bytes());
+        if offset.is_some() {
+            tiff.extend_from_slice(&TAG_OFFSET_TIME_ORIGINAL.to_be_bytes());
+            tiff.extend_from_slice(&2u16.to_be_bytes());
+            tiff.extend_from_slice(&7u32.to_be_bytes());
+            tiff.extend_from_slice(&(data_start + 20).to_be_bytes());
+        }
+        tiff.extend_from_slice(&0u32.to_be_bytes());
+
+        tiff.extend_from_slice(original.as_bytes());
+        tiff.push(0);
+        if let Some(offset) = offset {
+            tiff.extend_from_slice(offset.as_bytes());
+            tiff.push(0);
+        }
+        tiff
+    }
+
+    /// Expected result for a capture date without UTC offset
+    fn local(value: &str) -> Option<SystemTime> {
+        local_time(parse_exif_datetime(value).unwrap())
+    }
+
+    fn read_date(data: &[u8]) -> Option<SystemTime> {
+        capture_date(&mut std::io::Cursor::new(data))
+    }
+
+    #[test]
+    fn test_parse_tiff_date() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        assert_eq!(
+            parse_tiff_date(&make_tiff("2024:03:12 14:30:00", None)),
+            local("2024:03:12 14:30:00")
+        );
+
+        let expected = UNIX_EPOCH + Duration::from_secs(1710253800 - 7200);
+        assert_eq!(
+            parse_tiff_date(&make_tiff("2024:03:12 14:30:00", Some("+02:00"))),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_invalid_dates_are_ignored() {
+        assert!(parse_tiff_date(&make_tiff("0000:00:00 00:00:00", None)).is_none());
+        assert!(parse_tiff_date(b"not a tiff").is_none());
+    }
+
+    #[test]
+    fn test_read_jpeg() {
+        let tiff = make_tiff("2023:07:14 15:32:10", None);
+        let mut jpeg = vec![0xFF, 0xD8];
+        // An unrelated APP0 segment first
+        jpeg.extend_from_slice(&[0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00]);
+        jpeg.extend_from_slice(&[0xFF, 0xE1]);
+        jpeg.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(&tiff);
+        jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02, 0xFF, 0xD9]);
+
+        assert_eq!(read_date(&jpeg), local("2023:07:14 15:32:10"));
+    }
+
+    #[test]
+    fn test_read_jpeg_with_fill_bytes() {
+        let tiff = make_tiff("2023:07:14 15:32:10", None);
+        let mut jpeg = vec![0xFF, 0xD8];
+        // Fill bytes before the markers, an odd and an even number
+        jpeg.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00]);
+        jpeg.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0xE1]);
+        jpeg.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(&tiff);
+        jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02, 0xFF, 0xD9]);
+
+        assert_eq!(read_date(&jpeg), local("2023:07:14 15:32:10"));
+    }
+
+    #[test]
+    fn test_read_heif() {
+        let tiff = make_tiff("2022:01:02 03:04:05", None);
+
+        let mut infe = Vec::new();
+        infe.extend_from_slice(&[2, 0, 0, 0]);
+        infe.extend_from_slice(&7u16.to_be_bytes());
+        infe.extend_from_slice(&0u16.to_be_bytes());
+        infe.extend_from_slice(b"Exif");
+        let infe = make_box(b"infe", &infe);
+
+        let mut iinf = vec![0, 0, 0, 0];
+        iinf.extend_from_slice(&1u16.to_be_bytes());
+        iinf.extend_from_slice(&infe);
+        let iinf = make_box(b"iinf", &iinf);
+
+        let ftyp = make_box(b"ftyp", b"heic\0\0\0\0mif1heic");
+        // iloc: version 0, offset/length size 4, base offset size 0, one item
+        let iloc_len = 8 + 4 + 2 + 2 + 2 + 2 + 2 + 8;
+        let meta_len = 8 + 4 + iinf.len() + iloc_len;
+        let exif_offset = (ftyp.len() + meta_len + 8) as u32;
+        let payload_len = (4 + 6 + tiff.len()) as u32;
+
+        let mut iloc = vec![0, 0, 0, 0, 0x44, 0x00];
+        iloc.extend_from_slice(&1u16.to_be_bytes());
+        iloc.extend_from_slice(&7u16.to_be_bytes());
+        iloc.extend_from_slice(&0u16.to_be_bytes());
+        iloc.extend_from_slice(&1u16.to_be_bytes());
+        iloc.extend_from_slice(&exif_offset.to_be_bytes());
+        iloc.extend_from_slice(&payload_len.to_be_bytes());
+        let iloc = make_box(b"iloc", &iloc);
+
+        let mut meta = vec![0, 0, 0, 0];
+        meta.extend_from_slice(&iinf);
+        meta.extend_from_slice(&iloc);
+        let meta = make_box(b"meta", &meta);
+
+        let mut payload = 6u32.to_be_bytes().to_vec();
+        payload.extend_from_slice(b"Exif\0\0");
+        payload.extend_from_slice(&tiff);
+        let mdat = make_box(b"mdat", &payload);
+
+        let mut file = ftyp;
+        file.extend_from_slice(&meta);
+        file.extend_from_slice(&mdat);
+
+        assert_eq!(read_date(&file), local("2022:01:02 03:04:05"));
+    }
+
+    #[test]
+    fn test_heif_with_huge_sizes() {
+        // A 64-bit box size that overflows when added to its start
+        let mut file = make_box(b"ftyp", b"heic\0\0\0\0mif1heic");
+        file.extend_from_slice(&1u32.to_be_bytes());
+        file.extend_from_slice(b"meta");
+        file.extend_from_slice(&u64::MAX.to_be_bytes());
+        file.extend_from_slice(&[0; 16]);
+        assert_eq!(read_date(&file), None);
+
+        // iloc: version 0, offset/length size 4, base offset size 8, one item
+        let mut iloc = vec![0, 0, 0, 0, 0x44, 0x80];
+        iloc.extend_from_slice(&1u16.to_be_bytes());
+        iloc.extend_from_slice(&7u16.to_be_bytes());
+        iloc.extend_from_slice(&0u16.to_be_bytes());
+        iloc.extend_from_slice(&u64::MAX.to_be_bytes());
+        iloc.extend_from_slice(&1u16.to_be_bytes());
+        iloc.extend_from_slice(&16u32.to_be_bytes());
+        iloc.extend_from_slice(&4u32.to_be_bytes());
+        let iloc = make_box(b"iloc", &iloc);
+        let mut cursor = std::io::Cursor::new(&iloc);
+        let header = read_box_header(&mut cursor, 0, iloc.len() as u64).unwrap();
+        assert_eq!(heif_item_location(&mut cursor, &header, 7), None);
+    }
+
+    fn make_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut data = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(kind);
+        data.extend_from_slice(content);
+        data
+    }
+}
@@ -0,0 +1,427 @@
+use std::fmt;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+use chrono::{Days, Local, NaiveDate};
+use glob::Pattern;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::app::FileEntry;
+
+/// Date format of the date range
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Kind of directory entry a filter lets through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryType {
+    File,
+    Directory,
+}
+
+impl EntryType {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            EntryType::File => "file",
+            EntryType::Directory => "dir",
+        }
+    }
+}
+
+/// Which files take part in a rename, independent of the rename steps.
+/// All conditions that are set must hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFilter {
+    /// Glob on the name, e.g. "IMG_*"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glob: Option<String>,
+    /// Regex on the name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    /// Allowed extensions without dot (case-insensitive)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+    /// Minimum size in bytes (directories never match a size bound)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u64>,
+    /// First day of the modification date range (YYYY-MM-DD, local time)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_since: Option<String>,
+    /// Last day of the modification date range (inclusive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_until: Option<String>,
+    /// Only files or only directories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_type: Option<EntryType>,
+}
+
+impl FileFilter {
+    /// Check if the filter lets everything through
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Written in the syntax of `parse_filter`
+impl fmt::Display for FileFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(glob) = &self.glob {
+            parts.push(glob.clone());
+        }
+        if let Some(regex) = &self.regex {
+            parts.push(format!("re:{}", regex));
+        }
+        if !self.extensions.is_empty() {
+            parts.push(format!("ext:{}", self.extensions.join(",")));
+        }
+        if self.min_size.is_some() || self.max_size.is_some() {
+            parts.push(format!(
+                "size:{}..{}",
+                self.min_size.map(format_size).unwrap_or_default(),
+                self.max_size.map(format_size).unwrap_or_default()
+            ));
+        }
+        if self.modified_since.is_some() || self.modified_until.is_some() {
+            parts.push(format!(
+                "date:{}..{}",
+                self.modified_since.as_deref().unwrap_or_default(),
+                self.modified_until.as_deref().unwrap_or_default()
+            ));
+        }
+        if let Some(entry_type) = self.entry_type {
+            parts.push(format!("type:{}", entry_type.display_name()));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Parse a filter expression of space separated conditions:
+/// "IMG_* re:^\d+ ext:jpg,png size:1M..10M date:2024-01-01..2024-06-30 type:file".
+/// A condition without a key is a glob on the name.
+pub fn parse_filter(input: &str) -> Result<FileFilter> {
+    let mut filter = FileFilter::default();
+
+    for part in input.split_whitespace() {
+        let Some((key, value)) = part.split_once(':') else {
+            if filter.glob.is_some() {
+                return Err(anyhow!("Nur ein Namensmuster erlaubt: {}", part));
+            }
+            filter.glob = Some(part.to_string());
+            continue;
+        };
+        match key {
+            "re" | "regex" => filter.regex = Some(value.to_string()),
+            "ext" => filter.extensions = parse_extensions(value),
+            "size" => {
+                let (min, max) = parse_range(value, parse_size)?;
+                filter.min_size = min;
+                filter.max_size = max;
+            }
+            "date" => {
+                // A single day is a range of one day
+                let (since, until) = match value.split_once("..") {
+                    Some((since, until)) => (since, until),
+                    None => (value, value),
+                };
+                filter.modified_since = parse_optional_date(since)?;
+                filter.modified_until = parse_optional_date(until)?;
+            }
+            "type" => {
+                filter.entry_type =
+                    Some(parse_entry_type(value).ok_or_else(|| {
+                        anyhow!("Unbekannter Typ: {} (erlaubt: file, dir)", value)
+                    })?)
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Unbekannte Bedingung: {} (erlaubt: re, ext, size, date, type)",
+                    key
+                ))
+            }
+        }
+    }
+
+    Ok(filter)
+}
+
+/// Parse "from..to", either side may be empty
+fn parse_range<T>(value: &str, parse: fn(&str) -> Result<T>) -> Result<(Option<T>, Option<T>)> {
+    let (from, to) = value
+        .split_once("..")
+        .ok_or_else(|| anyhow!("Bereich erwartet (von..bis): {}", value))?;
+    let from = (!from.is_empty()).then(|| parse(from)).transpose()?;
+    let to = (!to.is_empty()).then(|| parse(to)).transpose()?;
+    Ok((from, to))
+}
+
+fn parse_optional_date(value: &str) -> Result<Option<String>> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    parse_date(value)?;
+    Ok(Some(value.to_string()))
+}
+
+/// Parse a comma separated extension list, dots are optional: "jpg,.PNG"
+pub fn parse_extensions(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect()
+}
+
+/// Parse a size with optional binary unit: "512", "10K", "1.5M", "2GB"
+pub fn parse_size(value: &str) -> Result<u64> {
+    let upper = value.trim().to_uppercase();
+    let number = upper.trim_end_matches("IB").trim_end_matches('B');
+    let (number, factor) = match number.chars().last() {
+        Some('K') => (&number[..number.len() - 1], 1u64 << 10),
+        Some('M') => (&number[..number.len() - 1], 1 << 20),
+        Some('G') => (&number[..number.len() - 1], 1 << 30),
+        Some('T') => (&number[..number.len() - 1], 1 << 40),
+        _ => (number, 1),
+    };
+    let number: f64 = number
+        .parse()
+        .ok()
+        .filter(|n: &f64| n.is_finite() && *n >= 0.0)
+        .ok_or_else(|| anyhow!("Ungueltige Groesse: {} (z.B. 500K, 1.5M, 2G)", value))?;
+    Ok((number * factor as f64).round() as u64)
+}
+
+/// Size in the largest unit that divides it: 1048576 -> "1M"
+fn format_size(bytes: u64) -> String {
+    for (unit, shift) in [("T", 40), ("G", 30), ("M", 20), ("K", 10)] {
+        if bytes > 0 && bytes.is_multiple_of(1 << shift) {
+            return format!("{}{}", bytes >> shift, unit);
+        }
+    }
+    bytes.to_string()
+}
+
+/// Parse a date in YYYY-MM-DD format
+pub fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, DATE_FORMAT)
+        .map_err(|_| anyhow!("Ungueltiges Datum: {} (Format: YYYY-MM-DD)", value))
+}
+
+pub fn parse_entry_type(value: &str) -> Option<EntryType> {
+    match value.to_lowercase().as_str() {
+        "file" | "files" | "f" | "datei" => Some(EntryType::File),
+        "dir" | "dirs" | "directory" | "d" | "ordner" => Some(EntryType::Directory),
+        _ => None,
+    }
+}
+
+/// Start of a day in local time
+fn start_of_day(date: NaiveDate) -> Option<SystemTime> {
+    date.and_hms_opt(0, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()
+        .map(SystemTime::from)
+}
+
+/// A filter with compiled patterns and resolved date bounds, ready to match files
+pub struct PreparedFilter {
+    glob: Option<Pattern>,
+    regex: Option<Regex>,
+    extensions: Vec<String>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    since: Option<SystemTime>,
+    /// Exclusive end: start of the day after the last day
+    before: Option<SystemTime>,
+    entry_type: Option<EntryType>,
+}
+
+impl PreparedFilter {
+    pub fn new(filter: &FileFilter) -> Result<Self> {
+        let glob = filter
+            .glob
+            .as_deref()
+            .map(|glob| Pattern::new(glob).map_err(|e| anyhow!("Ungueltiges Namensmuster: {}", e)))
+            .transpose()?;
+        let regex = filter
+            .regex
+            .as_deref()
+            .map(|regex| Regex::new(regex).map_err(|e| anyhow!("Ungueltiger Regex: {}", e)))
+            .transpose()?;
+        let since = match &filter.modified_since {
+            Some(date) => start_of_day(parse_date(date)?),
+            None => None,
+        };
+        let before = match &filter.modified_until {
+            Some(date) => parse_date(date)?
+                .checked_add_days(Days::new(1))
+                .and_then(start_of_day),
+            None => None,
+        };
+
+        Ok(Self {
+            glob,
+            regex,
+            extensions: filter.extensions.clone(),
+            min_size: filter.min_size,
+            max_size: filter.max_size,
+            since,
+            before,
+            entry_type: filter.entry_type,
+        })
+    }
+
+    /// Check if a file passes all conditions
+    pub fn matches(&self, file: &FileEntry) -> bool {
+        match self.entry_type {
+            Some(EntryType::File) if file.is_dir => return false,
+            Some(EntryType::Directory) if !file.is_dir => return false,
+            _ => {}
+        }
+        if self
+            .glob
+            .as_ref()
+            .is_some_and(|glob| !glob.matches(&file.name))
+        {
+            return false;
+        }
+        if self
+            .regex
+            .as_ref()
+            .is_some_and(|regex| !regex.is_match(&file.name))
+        {
+            return false;
+        }
+        if !self.extensions.is_empty() && !self.extensions.contains(&file.extension.to_lowercase())
+        {
+            return false;
+        }
+
+        let has_size_bound = self.min_size.is_some() || self.max_size.is_some();
+        if has_size_bound
+            && (file.is_dir
+                || self.min_size.is_some_and(|min| file.size < min)
+                || self.max_size.is_some_and(|max| file.size > max))
+        {
+            return false;
+        }
+
+        if self.since.is_some() || self.before.is_some() {
+            let Some(modified) = file.modified else {
+                return false;
+            };
+            if self.since.is_some_and(|since| modified < since)
+                || self.before.is_some_and(|before| modified >= before)
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn make_file(name: &str, size: u64, modified: Option<SystemTime>) -> FileEntry {
+        let path = PathBuf::from(name);
+        FileEntry {
+            extension: path
+                .extension()
+                .map(|e| e.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path,
+            name: name.to_string(),
+            subdir: PathBuf::new(),
+            is_dir: false,
+            size,
+            modified,
+        }
+    }
+
+    fn noon(date: &str) -> Option<SystemTime> {
+        parse_date(date)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)?
+            .and_local_timezone(Local)
+            .earliest()
+            .map(SystemTime::from)
+    }
+
+    fn matches(filter: &str, file: &FileEntry) -> bool {
+        PreparedFilter::new(&parse_filter(filter).unwrap())
+            .unwrap()
+            .matches(file)
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let filter =
+            parse_filter("IMG_* re:^\\w+ ext:jpg,.PNG size:1M.. date:..2024-06-30 type:file")
+                .unwrap();
+        assert_eq!(filter.glob.as_deref(), Some("IMG_*"));
+        assert_eq!(filter.regex.as_deref(), Some("^\\w+"));
+        assert_eq!(filter.extensions, ["jpg", "png"]);
+        assert_eq!(filter.min_size, Some(1 << 20));
+        assert_eq!(filter.max_size, None);
+        assert_eq!(filter.modified_since, None);
+        assert_eq!(filter.modified_until.as_deref(), Some("2024-06-30"));
+        assert_eq!(filter.entry_type, Some(EntryType::File));
+
+        // Display gives an expression that parses to the same filter
+        assert_eq!(parse_filter(&filter.to_string()).unwrap(), filter);
+        assert!(parse_filter("").unwrap().is_empty());
+
+        assert!(parse_filter("a* b*").is_err());
+        assert!(parse_filter("size:10").is_err());
+        assert!(parse_filter("date:2024-13-01").is_err());
+        assert!(parse_filter("type:link").is_err());
+        assert!(parse_filter("owner:root").is_err());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("10k").unwrap(), 10 * 1024);
+        assert_eq!(parse_size("1.5M").unwrap(), 1536 * 1024);
+        assert_eq!(parse_size("2GB").unwrap(), 2 << 30);
+        assert_eq!(parse_size("1KiB").unwrap(), 1024);
+        assert!(parse_size("viel").is_err());
+        assert!(parse_size("-1K").is_err());
+        assert_eq!(format_size(3 << 20), "3M");
+        assert_eq!(format_size(1500), "1500");
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let photo = make_file("IMG_01.JPG", 2 << 20, noon("2024-03-10"));
+        let note = make_file("notiz.txt", 100, noon("2024-07-01"));
+        let mut dir = make_file("Ordner", 4096, None);
+        dir.is_dir = true;
+
+        assert!(matches("", &photo) && matches("", &dir));
+        assert!(matches("IMG_*", &photo) && !matches("IMG_*", &note));
+        assert!(matches("re:_\\d+\\.", &photo) && !matches("re:^\\d", &photo));
+        assert!(matches("ext:jpg,png", &photo) && !matches("ext:jpg", &note));
+        assert!(matches("size:1M..", &photo) && !matches("size:1M..", &note));
+        assert!(matches("size:..1K", &note) && !matches("size:..1K", &dir));
+        assert!(matches("type:dir", &dir) && !matches("type:dir", &photo));
+        assert!(matches("type:file ext:txt", &note));
+    }
+
+    #[test]
+    fn test_filter_date_range() {
+        let file = make_file("a.txt", 0, noon("2024-03-10"));
+
+        assert!(matches("date:2024-03-10", &file));
+        assert!(matches("date:2024-03-01..2024-03-10", &file));
+        assert!(matches("date:2024-03-10..", &file));
+        assert!(!matches("date:2024-03-11..", &file));
+        assert!(!matches("date:..2024-03-09", &file));
+        assert!(!matches("date:2024-01-01..", &make_file("b.txt", 0, None)));
+    }
+}
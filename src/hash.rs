@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use sha2::{Digest, Sha256};
+
+use crate::app::HashAlgorithm;
+
+/// Read buffer size for streaming hashing
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Hash the contents of a file chunk by chunk, `progress` gets the number of bytes read
+pub fn hash_file(
+    path: &Path,
+    algorithm: HashAlgorithm,
+    mut progress: impl FnMut(u64),
+) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0; CHUNK_SIZE];
+
+    let mut sha256 = Sha256::new();
+    let mut blake3 = blake3::Hasher::new();
+    let mut crc32 = crc32fast::Hasher::new();
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        let chunk = &buffer[..read];
+        match algorithm {
+            HashAlgorithm::Sha256 => sha256.update(chunk),
+            HashAlgorithm::Blake3 => {
+                blake3.update(chunk);
+            }
+            HashAlgorithm::Crc32 => crc32.update(chunk),
+        }
+        progress(read as u64);
+    }
+
+    Ok(match algorithm {
+        HashAlgorithm::Sha256 => to_hex(&sha256.finalize()),
+        HashAlgorithm::Blake3 => blake3.finalize().to_hex().to_string(),
+        HashAlgorithm::Crc32 => format!("{:08x}", crc32.finalize()),
+    })
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hashes that were already computed, by file and algorithm
+#[derive(Debug, Clone, Default)]
+pub struct HashCache {
+    hashes: HashMap<(PathBuf, HashAlgorithm), Result<String, String>>,
+}
+
+impl HashCache {
+    /// Hash of a file, or the error that occurred while reading it
+    pub fn get(&self, path: &Path, algorithm: HashAlgorithm) -> Option<&Result<String, String>> {
+        self.hashes.get(&(path.to_path_buf(), algorithm))
+    }
+
+    pub fn insert(
+        &mut self,
+        path: PathBuf,
+        algorithm: HashAlgorithm,
+        hash: Result<String, String>,
+    ) {
+        self.hashes.insert((path, algorithm), hash);
+    }
+
+    /// Check if the hash of a file was computed
+    pub fn contains(&self, path: &Path, algorithm: HashAlgorithm) -> bool {
+        self.get(path, algorithm).is_some()
+    }
+}
+
+/// Message from the hashing thread
+enum HashEvent {
+    /// Bytes read since the last message
+    Progress(u64),
+    /// A file is done for one algorithm
+    Done(PathBuf, HashAlgorithm, Result<String, String>),
+}
+
+/// Hashing of files in a background thread, so the TUI stays responsive
+pub struct HashJob {
+    /// Algorithms of all hash steps the job computes
+    pub algorithms: Vec<HashAlgorithm>,
+    receiver: Receiver<HashEvent>,
+    /// Total size of all files in bytes
+    pub total_bytes: u64,
+    pub done_bytes: u64,
+    pub total_files: usize,
+    pub done_files: usize,
+}
+
+impl HashJob {
+    /// Start hashing the given files (path, size and algorithm), a file may
+    /// appear once per algorithm
+    pub fn start(files: Vec<(PathBuf, u64, HashAlgorithm)>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let total_bytes = files.iter().map(|(_, size, _)| size).sum();
+        let total_files = files.len();
+        let mut algorithms: Vec<HashAlgorithm> = Vec::new();
+        for (_, _, algorithm) in &files {
+            if !algorithms.contains(algorithm) {
+                algorithms.push(*algorithm);
+            }
+        }
+
+        thread::spawn(move || {
+            for (path, _, algorithm) in files {
+                let mut pending = 0;
+                let result = hash_file(&path, algorithm, |read| {
+                    // Report about every megabyte, not every chunk
+                    pending += read;
+                    if pending >= 16 * CHUNK_SIZE as u64 {
+                        let _ = sender.send(HashEvent::Progress(pending));
+                        pending = 0;
+                    }
+                });
+                let _ = sender.send(HashEvent::Progress(pending));
+                let result = result.map_err(|e| format!("Datei nicht lesbar: {}", e));
+                // The receiver is gone if the job was replaced, stop then
+                if sender
+                    .send(HashEvent::Done(path, algorithm, result))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        });
+
+        Self {
+            algorithms,
+            receiver,
+            total_bytes,
+            done_bytes: 0,
+            total_files,
+            done_files: 0,
+        }
+    }
+
+    /// Move finished hashes into the cache. Returns whether new hashes arrived.
+    pub fn poll(&mut self, cache: &mut HashCache) -> bool {
+        let mut changed = false;
+        loop {
+            match self.receiver.try_recv() {
+                Ok(HashEvent::Progress(bytes)) => self.done_bytes += bytes,
+                Ok(HashEvent::Done(path, algorithm, hash)) => {
+                    cache.insert(path, algorithm, hash);
+                    self.done_files += 1;
+                    changed = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.done_files = self.total_files;
+                    break;
+                }
+            }
+        }
+        changed
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.done_files >= self.total_files
+    }
+
+    /// Progress in percent (by bytes)
+    pub fn percent(&self) -> u64 {
+        (self.done_bytes * 100)
+            .checked_div(self.total_bytes)
+            .map_or(100, |percent| percent.min(100))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, content: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rnm_hash_{}_{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_hash_file() {
+        let path = temp_file("abc", b"abc");
+        let hash = |algorithm| hash_file(&path, algorithm, |_| {}).unwrap();
+
+        assert_eq!(
+            hash(HashAlgorithm::Sha256),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hash(HashAlgorithm::Blake3),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+        assert_eq!(hash(HashAlgorithm::Crc32), "352441c2");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_hash_job() {
+        let content = vec![7u8; 3 * CHUNK_SIZE + 5];
+        let path = temp_file("job", &content);
+        let size = content.len() as u64;
+        let mut job = HashJob::start(vec![
+            (path.clone(), size, HashAlgorithm::Crc32),
+            (path.clone(), size, HashAlgorithm::Sha256),
+        ]);
+        assert_eq!(
+            job.algorithms,
+            [HashAlgorithm::Crc32, HashAlgorithm::Sha256]
+        );
+        let mut cache = HashCache::default();
+
+        while !job.is_finished() {
+            job.poll(&mut cache);
+            thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        assert_eq!(job.percent(), 100);
+        assert!(
+            matches!(cache.get(&path, HashAlgorithm::Crc32), Some(Ok(hash)) if hash.len() == 8)
+        );
+        assert!(
+            matches!(cache.get(&path, HashAlgorithm::Sha256), Some(Ok(hash)) if hash.len() == 64)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
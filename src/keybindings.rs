@@ -19,7 +19,9 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) -> AppResult {
 
     match app.focused_panel {
         FocusedPanel::Files => handle_files_panel(app, key),
-        FocusedPanel::SearchField | FocusedPanel::ReplaceField => handle_input_field(app, key),
+        FocusedPanel::SearchField | FocusedPanel::ReplaceField | FocusedPanel::FilterField => {
+            handle_input_field(app, key)
+        }
     }
 }
 
@@ -49,6 +51,15 @@ fn handle_files_panel(app: &mut App, key: KeyEvent) -> AppResult {
             AppResult::Continue
         }
 
+        // Edit the names in $EDITOR
+        KeyCode::Char('e') => AppResult::Edit,
+
+        // File filter
+        KeyCode::Char('f') => {
+            app.edit_filter();
+            AppResult::Continue
+        }
+
         // Mode cycling
         KeyCode::Char('m') => {
             app.cycle_mode();
@@ -61,16 +72,106 @@ fn handle_files_panel(app: &mut App, key: KeyEvent) -> AppResult {
             AppResult::Continue
         }
 
-        // Toggle action (prefix/suffix action or date position)
+        // Toggle action (prefix/suffix/position/extension action, sanitize profile,
+        // regex match, counter format, date or checksum position)
         KeyCode::Char('t') => {
             match app.rename_mode {
+                RenameMode::Regex => app.cycle_regex_occurrence(),
+                RenameMode::Numbering => app.cycle_number_format(),
+                RenameMode::PadNumbers => app.cycle_pad_target(),
                 RenameMode::Prefix | RenameMode::Suffix => app.toggle_prefix_action(),
+                RenameMode::Position => app.cycle_position_action(),
                 RenameMode::DateInsert => app.toggle_date_position(),
+                RenameMode::Extension => app.cycle_extension_action(),
+                RenameMode::Hash => app.cycle_hash_position(),
+                RenameMode::Sanitize => app.cycle_sanitize_profile(),
                 _ => {}
             }
             AppResult::Continue
         }
 
+        // Date source for date insertion
+        KeyCode::Char('d') => {
+            if app.rename_mode == RenameMode::DateInsert {
+                app.cycle_date_source();
+            }
+            AppResult::Continue
+        }
+
+        // Checksum algorithm for hash mode
+        KeyCode::Char('h') => {
+            if app.rename_mode == RenameMode::Hash {
+                app.cycle_hash_algorithm();
+            }
+            AppResult::Continue
+        }
+
+        // Regex flags: case-insensitive and multi-line
+        KeyCode::Char('i') => {
+            if app.rename_mode == RenameMode::Regex {
+                app.toggle_regex_ignore_case();
+            }
+            AppResult::Continue
+        }
+        KeyCode::Char('l') => {
+            if app.rename_mode == RenameMode::Regex {
+                app.toggle_regex_multi_line();
+            }
+            AppResult::Continue
+        }
+
+        // Counter group and renumbering for numbering mode
+        KeyCode::Char('g') => {
+            if app.rename_mode == RenameMode::Numbering {
+                app.cycle_number_group();
+            }
+            AppResult::Continue
+        }
+        KeyCode::Char('r') => {
+            if app.rename_mode == RenameMode::Numbering {
+                app.toggle_renumber();
+            }
+            AppResult::Continue
+        }
+
+        // Pipeline steps
+        KeyCode::Char('+') => {
+            app.add_step();
+            AppResult::Continue
+        }
+        KeyCode::Char('-') => {
+            app.remove_step();
+            AppResult::Continue
+        }
+        KeyCode::Char(']') => {
+            app.select_next_step();
+            AppResult::Continue
+        }
+        KeyCode::Char('[') => {
+            app.select_previous_step();
+            AppResult::Continue
+        }
+        KeyCode::Char('>') => {
+            app.move_step_down();
+            AppResult::Continue
+        }
+        KeyCode::Char('<') => {
+            app.move_step_up();
+            AppResult::Continue
+        }
+
+        // Rule sets from the config instead of the pipeline
+        KeyCode::Char('R') => {
+            app.cycle_rule_set();
+            AppResult::Continue
+        }
+
+        // Rename directories too
+        KeyCode::Char('D') => {
+            app.toggle_include_dirs();
+            AppResult::Continue
+        }
+
         // Undo last rename operation
         KeyCode::Char('u') => {
             app.undo_last();
@@ -144,9 +245,13 @@ fn handle_input_field(app: &mut App, key: KeyEvent) -> AppResult {
             AppResult::Continue
         }
 
-        // Execute rename from input field
+        // Execute rename from input field, the filter field just closes
         KeyCode::Enter => {
-            app.show_confirm_dialog();
+            if app.focused_panel == FocusedPanel::FilterField {
+                app.focused_panel = FocusedPanel::Files;
+            } else {
+                app.show_confirm_dialog();
+            }
             AppResult::Continue
         }
 
@@ -172,6 +277,8 @@ fn handle_confirm_dialog(app: &mut App, key: KeyEvent) -> AppResult {
         // Cancel with Escape, 'n', or 'q'
         KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Char('q') => {
             app.close_dialog();
+            // Names edited in $EDITOR are dropped, back to the preview of the steps
+            app.update_preview();
             AppResult::Continue
         }
 
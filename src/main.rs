@@ -1,12 +1,28 @@
 mod app;
+mod case;
 mod config;
+mod editor;
+mod exif;
+mod filter;
+mod hash;
 mod keybindings;
+mod mapping;
+mod media;
+mod numbering;
 mod operations;
+mod position;
+mod replace;
+mod rules;
+mod sanitize;
+mod script;
+mod sniff;
+mod tags;
+mod template;
 mod ui;
 
 use std::collections::HashSet;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 use clap::Parser;
@@ -17,10 +33,31 @@ use crossterm::{
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 
-use app::{App, AppResult, DatePosition, PrefixAction, RenameMode};
-use config::{parse_date_position, parse_mode, Config, Preset};
+use app::{
+    App, AppResult, CounterGroup, DatePosition, DateSource, FileEntry, HashAlgorithm, NumberFormat,
+    PositionAction, PrefixAction, RegexFlags, RenameMode, RenameStep,
+};
+use config::{
+    parse_counter_group, parse_date_position, parse_date_source, parse_date_sources,
+    parse_extension_action, parse_hash_algorithm, parse_mode, parse_number_format,
+    parse_number_target, parse_regex_flags, parse_rule, parse_sanitize_profile, parse_step,
+    parse_title_language, Config, Preset, RuleSet,
+};
+use editor::{edit_names, edit_previews};
+use filter::{
+    parse_date, parse_entry_type, parse_extensions, parse_filter, parse_size, FileFilter,
+    PreparedFilter,
+};
+use hash::{hash_file, HashCache};
 use keybindings::handle_key_event;
-use operations::{execute_renames, generate_previews, print_previews, undo_last_rename, get_undo_preview};
+use mapping::{load_mapping, mapping_previews};
+use operations::{
+    execute_renames_with_history, generate_previews, get_undo_preview, moves_to_subdirs,
+    parse_hash_length, parse_pad_width, print_previews, undo_last_rename, RenamePreview,
+};
+use position::PositionEdit;
+use rules::{rule_previews, rule_set_steps};
+use template::validate_date_format;
 use ui::draw_ui;
 
 /// rnm - A modern TUI tool for batch renaming files
@@ -44,7 +81,12 @@ struct Args {
     #[arg(short, long)]
     replace: Option<String>,
 
-    /// Rename mode: search, regex, numbering, prefix, suffix, upper, lower, title
+    /// Regex flags: i (ignore case), m (multi-line), first, nth=N or all, e.g. "i,first"
+    #[arg(long, value_name = "FLAGS")]
+    regex_flags: Option<String>,
+
+    /// Rename mode: search, regex, numbering, pad, prefix, suffix, date, template, tags, organize, media, ext, detect, sanitize, position, upper, lower, title,
+    /// snake, kebab, camel, pascal, dot, sentence
     #[arg(long, short = 'm')]
     mode: Option<String>,
 
@@ -53,8 +95,41 @@ struct Args {
     pattern: Option<String>,
 
     /// Starting number for numbering mode
-    #[arg(long, default_value = "1")]
-    start: usize,
+    #[arg(long, default_value = "1", allow_hyphen_values = true)]
+    start: i64,
+
+    /// Increment of the counter for numbering mode (negative counts down)
+    #[arg(
+        long,
+        default_value = "1",
+        value_name = "N",
+        allow_hyphen_values = true
+    )]
+    increment: i64,
+
+    /// Counter format for numbering mode: decimal, alpha, roman, or hex
+    #[arg(long, value_name = "FORMAT")]
+    number_format: Option<String>,
+
+    /// Separate counters per group: ext, dir, or day (file date, see --date-source)
+    #[arg(long, value_name = "GROUP")]
+    group_by: Option<String>,
+
+    /// Renumber the existing number in each name (width from --pattern, e.g. "####")
+    #[arg(long)]
+    renumber: bool,
+
+    /// Value added to existing numbers with --renumber, e.g. 10 or -5
+    #[arg(long, value_name = "N", allow_hyphen_values = true)]
+    offset: Option<String>,
+
+    /// Zero-pad numbers in the names to a common width ("auto" = largest number)
+    #[arg(long, value_name = "WIDTH")]
+    pad_numbers: Option<String>,
+
+    /// Numbers to pad with --pad-numbers: first, last, or all
+    #[arg(long, value_name = "WHICH", default_value = "last")]
+    pad_target: String,
 
     /// Add prefix to filenames
     #[arg(long)]
@@ -72,7 +147,86 @@ struct Args {
     #[arg(long)]
     remove_suffix: Option<String>,
 
-    /// Use date insertion mode (inserts file modification date)
+    /// Insert text into the name (before the extension), see --at
+    #[arg(long, value_name = "TEXT")]
+    insert: Option<String>,
+
+    /// Delete a character range of the name, e.g. "0..4" or "-3.."
+    #[arg(long, value_name = "RANGE", allow_hyphen_values = true)]
+    delete: Option<String>,
+
+    /// Keep only a character range of the name, e.g. "..8"
+    #[arg(long, value_name = "RANGE", allow_hyphen_values = true)]
+    keep: Option<String>,
+
+    /// Cut the name at the Nth occurrence of a delimiter (see --at)
+    #[arg(long, value_name = "DELIMITER")]
+    cut_after: Option<String>,
+
+    /// Position for --insert (0 = start, -0 = end) or occurrence for --cut-after (-1 = last)
+    #[arg(long, value_name = "N", allow_hyphen_values = true)]
+    at: Option<String>,
+
+    /// Build new names from a template, e.g. "{date:%Y-%m-%d}_{stem|lower}_{n:03}.{ext}"
+    #[arg(long)]
+    template: Option<String>,
+
+    /// Build new names from audio tags, e.g. "{track:02} - {artist} - {title}.{ext}"
+    #[arg(long, value_name = "PATTERN")]
+    tags: Option<String>,
+
+    /// Build new names with a Rhai script from the scripts directory
+    /// (~/.config/rnm/scripts/NAME.rhai) or a path
+    #[arg(long, value_name = "NAME")]
+    script: Option<String>,
+
+    /// Move files into subfolders built from a template, e.g. "{date:%Y}/{date:%m}/{name}"
+    /// (missing folders are created)
+    #[arg(long, value_name = "TEMPLATE")]
+    organize: Option<String>,
+
+    /// Normalize TV episode and movie release names (S01E02, 1x02, Movie.2019.1080p)
+    #[arg(long)]
+    media: bool,
+
+    /// Naming scheme for episodes in media mode
+    /// (default from the config: "{show} - S{season:02}E{episode:02} - {title}.{ext}")
+    #[arg(long, value_name = "FORMAT")]
+    episode_format: Option<String>,
+
+    /// Naming scheme for movies in media mode (default from the config: "{title} ({year}).{ext}")
+    #[arg(long, value_name = "FORMAT")]
+    movie_format: Option<String>,
+
+    /// Change file extensions: set:EXT, lower, normalize, remove or add:EXT
+    #[arg(long = "ext", value_name = "ACTION[:EXT]")]
+    ext: Option<String>,
+
+    /// Fix missing or wrong extensions based on the file content (magic bytes)
+    #[arg(long)]
+    fix_ext: bool,
+
+    /// Name files by a checksum of their content: sha256, blake3, or crc32
+    #[arg(long, value_name = "ALGORITHM")]
+    hash: Option<String>,
+
+    /// Number of checksum characters to use (default: all)
+    #[arg(long, value_name = "N")]
+    hash_length: Option<String>,
+
+    /// Position of the checksum: prefix, suffix, or replace
+    #[arg(long, default_value = "replace")]
+    hash_position: String,
+
+    /// Separator between checksum and filename
+    #[arg(long, default_value = "_")]
+    hash_separator: String,
+
+    /// Make names safe for a file system: posix, windows, fat, or url
+    #[arg(long, value_name = "PROFILE")]
+    sanitize: Option<String>,
+
+    /// Use date insertion mode (inserts a file date, see --date-source)
     #[arg(long)]
     date: bool,
 
@@ -80,6 +234,109 @@ struct Args {
     #[arg(long, default_value = "prefix")]
     date_position: String,
 
+    /// strftime format for date insertion, e.g. "%Y-%m-%d_%H%M" (local time)
+    #[arg(long, default_value = "%Y%m%d")]
+    date_format: String,
+
+    /// Separator between date and filename for date insertion
+    #[arg(long, default_value = "_")]
+    date_separator: String,
+
+    /// Date source for date insertion: mtime, ctime, birth, exif, or now
+    #[arg(long, default_value = "mtime")]
+    date_source: String,
+
+    /// Fallback date sources if the selected one has no date, e.g. "birth,mtime"
+    /// (overrides date_fallback from the config)
+    #[arg(long, value_name = "SOURCES")]
+    date_fallback: Option<String>,
+
+    /// Stop word language for title case: en, de, fr, or none
+    /// (overrides title_case.language from the config)
+    #[arg(long, value_name = "LANG")]
+    title_lang: Option<String>,
+
+    /// Additional pipeline step, applied in order (format: mode[:search[:replace]]).
+    /// Can be given multiple times, e.g. --step lower --step "search: :_"
+    #[arg(long = "step", value_name = "STEP")]
+    steps: Vec<String>,
+
+    /// Rename by the pairs of a mapping file (old name -> new name, .csv, .tsv or .json)
+    /// instead of a rename mode
+    #[arg(long, value_name = "FILE")]
+    from_mapping: Option<PathBuf>,
+
+    /// Edit the names in $EDITOR (one per line) and rename by the changed lines
+    #[arg(long)]
+    edit: bool,
+
+    /// Only rename files matching a filter expression, e.g. "IMG_* ext:jpg size:1M.. type:file"
+    /// (the flags below override single conditions)
+    #[arg(long, value_name = "EXPR")]
+    filter: Option<String>,
+
+    /// Only rename files whose name matches a glob, e.g. "IMG_*"
+    #[arg(long, value_name = "GLOB")]
+    filter_name: Option<String>,
+
+    /// Only rename files whose name matches a regex
+    #[arg(long, value_name = "REGEX")]
+    filter_regex: Option<String>,
+
+    /// Only rename files with one of these extensions, e.g. "jpg,png"
+    #[arg(long, value_name = "LIST")]
+    filter_ext: Option<String>,
+
+    /// Only rename files of at least this size, e.g. 500K, 1.5M
+    #[arg(long, value_name = "SIZE")]
+    min_size: Option<String>,
+
+    /// Only rename files of at most this size
+    #[arg(long, value_name = "SIZE")]
+    max_size: Option<String>,
+
+    /// Only rename files modified on or after this day (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    modified_since: Option<String>,
+
+    /// Only rename files modified on or before this day (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    modified_until: Option<String>,
+
+    /// Only rename files or only directories: file or dir
+    #[arg(long, value_name = "TYPE")]
+    filter_type: Option<String>,
+
+    /// Rename directories too, not only files (overrides include_dirs from the config)
+    #[arg(long)]
+    dirs: bool,
+
+    /// List the whole directory tree, not only the directory itself
+    #[arg(long)]
+    recursive: bool,
+
+    /// Directory levels to list with --recursive (1 = only the directory itself,
+    /// overrides max_depth from the config)
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Rename by a saved rule set: each file by the first rule it matches
+    #[arg(long, value_name = "NAME")]
+    rules: Option<String>,
+
+    /// Rule for files matching a filter expression (format: condition=>step),
+    /// checked in order, e.g. --rule "ext:jpg=>date" --rule "*.mp4=>numbering:clip_###"
+    #[arg(long = "rule", value_name = "RULE")]
+    rule_args: Vec<String>,
+
+    /// Step for files no rule matches (default: leave them alone)
+    #[arg(long, value_name = "STEP")]
+    fallback: Option<String>,
+
+    /// Save the rules given with --rule and --fallback as a rule set
+    #[arg(long, value_name = "NAME")]
+    save_rules: Option<String>,
+
     /// Load a saved preset by name
     #[arg(long, short = 'p')]
     preset: Option<String>,
@@ -96,6 +353,10 @@ struct Args {
     #[arg(long)]
     list_presets: bool,
 
+    /// Delete a saved preset by name
+    #[arg(long)]
+    delete_preset: Option<String>,
+
     /// Undo the last rename operation
     #[arg(long)]
     undo: bool,
@@ -113,6 +374,11 @@ fn main() -> Result<()> {
         return list_presets();
     }
 
+    // Handle delete-preset command
+    if let Some(preset_name) = &args.delete_preset {
+        return delete_preset(preset_name);
+    }
+
     // Handle history command
     if args.history {
         return show_history();
@@ -128,54 +394,138 @@ fn main() -> Result<()> {
         return save_preset(&args, preset_name);
     }
 
+    // Handle save-rules command
+    if let Some(name) = &args.save_rules {
+        return save_rules(&args, name);
+    }
+
     // Determine if input is a glob pattern or directory
     let (directory, pattern) = parse_input(&args.path);
 
     // Check if we should run in non-interactive mode
-    let non_interactive = args.search.is_some() 
-        || args.mode.is_some() 
+    let non_interactive = args.search.is_some()
+        || args.mode.is_some()
         || args.preset.is_some()
         || args.pattern.is_some()
+        || args.template.is_some()
+        || args.tags.is_some()
+        || args.script.is_some()
+        || args.organize.is_some()
+        || args.media
+        || args.ext.is_some()
+        || args.fix_ext
+        || args.hash.is_some()
+        || args.renumber
+        || args.pad_numbers.is_some()
+        || args.insert.is_some()
+        || args.delete.is_some()
+        || args.keep.is_some()
+        || args.cut_after.is_some()
+        || args.sanitize.is_some()
         || args.prefix.is_some()
         || args.suffix.is_some()
         || args.remove_prefix.is_some()
         || args.remove_suffix.is_some()
         || args.date
+        || args.from_mapping.is_some()
+        || !args.steps.is_empty()
         || args.dry_run;
 
-    if non_interactive {
+    if let Some(mapping) = &args.from_mapping {
+        run_mapping(&args, directory, pattern, mapping)
+    } else if args.edit {
+        run_edit(&args, directory, pattern)
+    } else if args.rules.is_some() || !args.rule_args.is_empty() {
+        run_rules(&args, directory, pattern)
+    } else if non_interactive {
         run_non_interactive(&args, directory, pattern)
     } else {
-        run_interactive(directory, pattern)
+        run_interactive(&args, directory, pattern)
     }
 }
 
 /// List available presets
 fn list_presets() -> Result<()> {
     let config = Config::load()?;
-    
+
     if config.presets.is_empty() {
         println!("Keine Presets gespeichert.");
         println!("\nErstelle ein Preset mit:");
         println!("  rnm --search 'alt' --replace 'neu' --save-preset mein-preset");
+        list_rule_sets(&config);
         return Ok(());
     }
 
     println!("Verfuegbare Presets:\n");
-    
-    for (name, preset) in &config.presets {
+
+    for name in config.list_presets() {
+        let Some(preset) = config.get_preset(name) else {
+            continue;
+        };
         println!("  {} ", name);
-        println!("    Modus: {}", preset.mode.display_name());
-        if preset.mode.uses_search_replace() {
-            println!("    Suche: '{}'", preset.search);
-            println!("    Ersetze: '{}'", preset.replace);
+        let steps = preset.pipeline();
+        for (i, step) in steps.iter().enumerate() {
+            if steps.len() > 1 {
+                println!("    Schritt {}: {}", i + 1, step.mode.display_name());
+            } else {
+                println!("    Modus: {}", step.mode.display_name());
+            }
+            if step.mode.uses_search_replace() {
+                println!("    Suche: '{}'", step.search);
+                println!("    Ersetze: '{}'", step.replace);
+            }
+        }
+        if !preset.filter.is_empty() {
+            println!("    Filter: {}", preset.filter);
         }
         println!();
     }
 
+    list_rule_sets(&config);
     Ok(())
 }
 
+/// List the saved rule sets, if there are any
+fn list_rule_sets(config: &Config) {
+    if config.rule_sets.is_empty() {
+        return;
+    }
+
+    println!("\nVerfuegbare Regelsaetze:\n");
+    for name in config.list_rule_sets() {
+        let Some(rule_set) = config.get_rule_set(name) else {
+            continue;
+        };
+        println!("  {} ", name);
+        print_rule_set(rule_set);
+        println!();
+    }
+}
+
+/// Print the rules of a rule set, one line each
+fn print_rule_set(rule_set: &RuleSet) {
+    let describe = |steps: &[RenameStep]| {
+        if steps.is_empty() {
+            "unveraendert".to_string()
+        } else {
+            steps
+                .iter()
+                .map(|step| step.mode.display_name())
+                .collect::<Vec<_>>()
+                .join(" > ")
+        }
+    };
+    for (i, rule) in rule_set.rules.iter().enumerate() {
+        println!(
+            "    Regel {}: {} -> {}",
+            i + 1,
+            rule.label(),
+            describe(&rule.steps)
+        );
+    }
+    println!("    Sonst: {}", describe(&rule_set.fallback));
+}
+
 /// Show rename history
 fn show_history() -> Result<()> {
     use config::RenameHistory;
@@ -193,7 +543,7 @@ fn show_history() -> Result<()> {
     for (i, operation) in history.operations.iter().rev().enumerate() {
         let datetime = UNIX_EPOCH + Duration::from_secs(operation.timestamp);
         let time_str = format!("{:?}", datetime);
-        
+
         println!(
             "  {}. {} ({} Dateien)",
             i + 1,
@@ -202,14 +552,17 @@ fn show_history() -> Result<()> {
         );
         println!("     Verzeichnis: {}", operation.directory.display());
         println!("     Zeitpunkt: {}", time_str);
-        
+
         // Show first few entries as preview
         let preview_count = operation.entries.len().min(3);
         for entry in operation.entries.iter().take(preview_count) {
             println!("       {} -> {}", entry.original_name, entry.new_name);
         }
         if operation.entries.len() > preview_count {
-            println!("       ... und {} weitere", operation.entries.len() - preview_count);
+            println!(
+                "       ... und {} weitere",
+                operation.entries.len() - preview_count
+            );
         }
         println!();
     }
@@ -222,10 +575,14 @@ fn show_history() -> Result<()> {
 fn run_undo(args: &Args) -> Result<()> {
     // Show what will be undone
     if let Some((description, entries)) = get_undo_preview()? {
-        println!("Letzte Operation: {} ({} Dateien)", description, entries.len());
+        println!(
+            "Letzte Operation: {} ({} Dateien)",
+            description,
+            entries.len()
+        );
         println!("\nVorschau (rueckgaengig machen):");
         println!("{:-<60}", "");
-        
+
         let preview_count = entries.len().min(10);
         for (current, original) in entries.iter().take(preview_count) {
             println!("  {} -> {}", current, original);
@@ -239,10 +596,10 @@ fn run_undo(args: &Args) -> Result<()> {
         if !args.yes {
             print!("Rueckgaengig machen? [y/N] ");
             io::stdout().flush()?;
-            
+
             let mut input = String::new();
             io::stdin().read_line(&mut input)?;
-            
+
             if !input.trim().eq_ignore_ascii_case("y") {
                 println!("Abgebrochen.");
                 return Ok(());
@@ -267,18 +624,9 @@ fn run_undo(args: &Args) -> Result<()> {
 fn save_preset(args: &Args, preset_name: &str) -> Result<()> {
     let mut config = Config::load()?;
 
-    let mode = if let Some(mode_str) = &args.mode {
-        parse_mode(mode_str).ok_or_else(|| anyhow!("Unbekannter Modus: {}", mode_str))?
-    } else {
-        RenameMode::SearchReplace
-    };
-
-    let preset = Preset::new(
-        preset_name.to_string(),
-        mode,
-        args.search.clone().unwrap_or_default(),
-        args.replace.clone().unwrap_or_default(),
-    );
+    let steps = build_pipeline(args, &config)?;
+    let mut preset = Preset::with_steps(preset_name.to_string(), steps);
+    preset.filter = filter_from_args(args, &config)?;
 
     config.add_preset(preset);
     config.save()?;
@@ -287,38 +635,232 @@ fn save_preset(args: &Args, preset_name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Save the rules given on the command line as a rule set
+fn save_rules(args: &Args, name: &str) -> Result<()> {
+    let mut config = Config::load()?;
+
+    let mut rule_set = rule_set_from_args(args, &config)?;
+    rule_set.name = name.to_string();
+    if rule_set.rules.is_empty() {
+        return Err(anyhow!(
+            "Keine Regeln angegeben (--rule bedingung=>schritt)"
+        ));
+    }
+
+    config.add_rule_set(rule_set);
+    config.save()?;
+
+    println!("Regelsatz '{}' gespeichert.", name);
+    Ok(())
+}
+
+/// Delete a saved preset
+fn delete_preset(preset_name: &str) -> Result<()> {
+    let mut config = Config::load()?;
+
+    if config.remove_preset(preset_name).is_none() {
+        return Err(anyhow!("Preset nicht gefunden: {}", preset_name));
+    }
+    config.save()?;
+
+    println!("Preset '{}' geloescht.", preset_name);
+    Ok(())
+}
+
 /// Run in non-interactive mode (CLI)
 fn run_non_interactive(args: &Args, directory: PathBuf, pattern: Option<String>) -> Result<()> {
-    let config = Config::load()?;
+    let config = load_config(args)?;
 
-    // Determine mode, search, replace, prefix_action, and date_position from args
-    let (mode, search, replace, prefix_action, number_start, date_position) = determine_mode_from_args(args, &config)?;
+    // Determine the rename pipeline from args
+    let steps = build_pipeline(args, &config)?;
 
     // Validate inputs based on mode
-    validate_mode_inputs(mode, &search)?;
+    for step in &steps {
+        validate_mode_inputs(step)?;
+    }
 
     // Load files
-    let files = app::load_files(&directory, pattern.as_deref(), config.default_sort)?;
-    
+    let filter = filter_from_args(args, &config)?;
+    let files = load_filtered_files(&directory, pattern.as_deref(), &filter, &config)?;
+
     if files.is_empty() {
         println!("Keine Dateien gefunden.");
         return Ok(());
     }
 
     println!("Verzeichnis: {}", directory.display());
-    println!("Modus: {}", mode.display_name());
-    print_mode_details(mode, &search, &replace, prefix_action, date_position);
-    println!("Dateien: {}", files.len());
+    if !filter.is_empty() {
+        println!("Filter: {}", filter);
+    }
+    for (i, step) in steps.iter().enumerate() {
+        if steps.len() > 1 {
+            println!("Schritt {}: {}", i + 1, step.mode.display_name());
+        } else {
+            println!("Modus: {}", step.mode.display_name());
+        }
+        print_mode_details(step);
+    }
+    println!(
+        "Dateien: {} (Sortierung: {})",
+        files.len(),
+        config.default_sort.display_name()
+    );
 
     // Generate previews
     let selected: HashSet<usize> = HashSet::new();
-    let previews = generate_previews(&files, &selected, &search, &replace, mode, prefix_action, number_start, 1, date_position)?;
+    let hashes = compute_hashes(&files, &steps)?;
+    let previews = generate_previews(&files, &selected, &steps, &config, &hashes)?;
 
     // Print preview
     print_previews(&previews);
 
+    confirm_and_execute(
+        args,
+        &previews,
+        &directory,
+        "Umbenennung",
+        moves_to_subdirs(&steps),
+    )
+}
+
+/// Rename every file by the first rule of a rule set it matches
+fn run_rules(args: &Args, directory: PathBuf, pattern: Option<String>) -> Result<()> {
+    let config = load_config(args)?;
+    let rule_set = rule_set_from_args(args, &config)?;
+    let steps: Vec<RenameStep> = rule_set_steps(&rule_set).cloned().collect();
+    for step in &steps {
+        validate_mode_inputs(step)?;
+    }
+
+    let filter = filter_from_args(args, &config)?;
+    let files = load_filtered_files(&directory, pattern.as_deref(), &filter, &config)?;
+    if files.is_empty() {
+        println!("Keine Dateien gefunden.");
+        return Ok(());
+    }
+
+    println!("Verzeichnis: {}", directory.display());
+    if !filter.is_empty() {
+        println!("Filter: {}", filter);
+    }
+    println!("Regelsatz: {}", rule_set.name);
+    print_rule_set(&rule_set);
+    println!(
+        "Dateien: {} (Sortierung: {})",
+        files.len(),
+        config.default_sort.display_name()
+    );
+
+    let hashes = compute_hashes(&files, &steps)?;
+    let previews = rule_previews(&files, &HashSet::new(), &rule_set, &config, &hashes)?;
+
+    let unmatched = previews.iter().filter(|p| p.rule.is_none()).count();
+    if unmatched > 0 {
+        println!(
+            "\n{} Datei(en) ohne passende Regel bleiben unveraendert.",
+            unmatched
+        );
+    }
+    print_previews(&previews);
+
+    let description = format!("Regelsatz {}", rule_set.name);
+    confirm_and_execute(
+        args,
+        &previews,
+        &directory,
+        &description,
+        moves_to_subdirs(&steps),
+    )
+}
+
+/// Rename by the pairs of a mapping file
+fn run_mapping(
+    args: &Args,
+    directory: PathBuf,
+    pattern: Option<String>,
+    mapping: &Path,
+) -> Result<()> {
+    let config = load_config(args)?;
+    let entries = load_mapping(mapping)?;
+
+    // The mapping file itself may lie in the directory, it is not renamed
+    let mapping_path = mapping.canonicalize().ok();
+    let filter = filter_from_args(args, &config)?;
+    let files: Vec<_> = load_filtered_files(&directory, pattern.as_deref(), &filter, &config)?
+        .into_iter()
+        .filter(|file| file.path.canonicalize().ok() != mapping_path)
+        .collect();
+
+    println!("Verzeichnis: {}", directory.display());
+    println!(
+        "Zuordnung: {} ({} Eintraege)",
+        mapping.display(),
+        entries.len()
+    );
+
+    let (previews, report) = mapping_previews(&files, &entries);
+    if !report.unmapped.is_empty() {
+        println!(
+            "\nNicht zugeordnet ({} Dateien, bleiben unveraendert):",
+            report.unmapped.len()
+        );
+        for name in report.unmapped.iter().take(10) {
+            println!("  {}", name);
+        }
+        if report.unmapped.len() > 10 {
+            println!("  ... und {} weitere", report.unmapped.len() - 10);
+        }
+    }
+    if !report.errors.is_empty() {
+        return Err(anyhow!(
+            "Fehler in der Zuordnung:\n{}",
+            report.errors.join("\n")
+        ));
+    }
+
+    print_previews(&previews);
+
+    let description = format!(
+        "Zuordnung aus {}",
+        mapping.file_name().unwrap_or_default().to_string_lossy()
+    );
+    confirm_and_execute(args, &previews, &directory, &description, false)
+}
+
+/// Rename by editing the names in $EDITOR
+fn run_edit(args: &Args, directory: PathBuf, pattern: Option<String>) -> Result<()> {
+    let config = load_config(args)?;
+    let filter = filter_from_args(args, &config)?;
+    let files = load_filtered_files(&directory, pattern.as_deref(), &filter, &config)?;
+    let indices: Vec<usize> = (0..files.len())
+        .filter(|&i| config.include_dirs || !files[i].is_dir)
+        .collect();
+
+    if indices.is_empty() {
+        println!("Keine Dateien gefunden.");
+        return Ok(());
+    }
+
+    let names: Vec<String> = indices.iter().map(|&i| files[i].relative_name()).collect();
+    let edited = edit_names(&names)?;
+    let previews = edit_previews(&files, &indices, &edited)?;
+
+    println!("Verzeichnis: {}", directory.display());
+    print_previews(&previews);
+
+    confirm_and_execute(args, &previews, &directory, "Bearbeitung im Editor", false)
+}
+
+/// Ask for confirmation (unless --yes or --dry-run) and rename the previewed files
+fn confirm_and_execute(
+    args: &Args,
+    previews: &[RenamePreview],
+    directory: &Path,
+    description: &str,
+    allow_subdirs: bool,
+) -> Result<()> {
     let changes: Vec<_> = previews.iter().filter(|p| p.will_change).collect();
-    
+
     if changes.is_empty() {
         return Ok(());
     }
@@ -333,10 +875,10 @@ fn run_non_interactive(args: &Args, directory: PathBuf, pattern: Option<String>)
     if !args.yes {
         print!("Fortfahren? [y/N] ");
         io::stdout().flush()?;
-        
+
         let mut input = String::new();
         io::stdin().read_line(&mut input)?;
-        
+
         if !input.trim().eq_ignore_ascii_case("y") {
             println!("Abgebrochen.");
             return Ok(());
@@ -344,43 +886,472 @@ fn run_non_interactive(args: &Args, directory: PathBuf, pattern: Option<String>)
     }
 
     // Execute renames
-    let count = execute_renames(&previews, &directory)?;
+    let count =
+        execute_renames_with_history(previews, directory, Some(description), allow_subdirs)?;
     println!("{} Datei(en) erfolgreich umbenannt.", count);
 
     Ok(())
 }
 
-/// Determine mode and settings from CLI arguments
-fn determine_mode_from_args(args: &Args, config: &Config) -> Result<(RenameMode, String, String, PrefixAction, usize, DatePosition)> {
-    // Parse date position
-    let date_position = parse_date_position(&args.date_position)
-        .ok_or_else(|| anyhow!("Unbekannte Datums-Position: {} (erlaubt: prefix, suffix, replace)", args.date_position))?;
+/// Load the config with the overrides from the command line
+fn load_config(args: &Args) -> Result<Config> {
+    let mut config = Config::load()?;
+    if let Some(list) = &args.date_fallback {
+        config.date_fallback = parse_date_sources(list).ok_or_else(|| {
+            anyhow!(
+                "Ungueltige Datumsquellen: {} (erlaubt: mtime, ctime, birth, exif, now)",
+                list
+            )
+        })?;
+    }
+    if let Some(language) = &args.title_lang {
+        config.title_case.language = parse_title_language(language).ok_or_else(|| {
+            anyhow!(
+                "Unbekannte Sprache: {} (erlaubt: en, de, fr, none)",
+                language
+            )
+        })?;
+    }
+    apply_listing_args(args, &mut config);
+    Ok(config)
+}
+
+/// Apply --dirs, --recursive and --max-depth to the config
+fn apply_listing_args(args: &Args, config: &mut Config) {
+    if args.dirs {
+        config.include_dirs = true;
+    }
+    match args.max_depth {
+        Some(depth) => config.max_depth = depth,
+        None if args.recursive => config.max_depth = 0,
+        None => {}
+    }
+}
+
+/// Build the rule set from --rules, --rule entries and --fallback
+fn rule_set_from_args(args: &Args, config: &Config) -> Result<RuleSet> {
+    let mut rule_set = match &args.rules {
+        Some(name) => config
+            .get_rule_set(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("Regelsatz nicht gefunden: {}", name))?,
+        None => RuleSet {
+            name: "Regeln".to_string(),
+            rules: Vec::new(),
+            fallback: Vec::new(),
+        },
+    };
+
+    for rule_str in &args.rule_args {
+        let mut rule = parse_rule(rule_str).ok_or_else(|| {
+            anyhow!(
+                "Ungueltige Regel: {} (Format: bedingung=>modus[:suche[:ersetze]])",
+                rule_str
+            )
+        })?;
+        for step in &mut rule.steps {
+            apply_step_args(step, args)?;
+        }
+        rule_set.rules.push(rule);
+    }
+    if let Some(step_str) = &args.fallback {
+        let mut step = parse_step(step_str).ok_or_else(|| {
+            anyhow!(
+                "Ungueltiger Schritt: {} (Format: modus[:suche[:ersetze]])",
+                step_str
+            )
+        })?;
+        apply_step_args(&mut step, args)?;
+        rule_set.fallback = vec![step];
+    }
+
+    Ok(rule_set)
+}
+
+/// Build the rename pipeline from preset, shortcut arguments and --step entries
+fn build_pipeline(args: &Args, config: &Config) -> Result<Vec<RenameStep>> {
+    let mut steps = Vec::new();
 
     // Check for preset first
     if let Some(preset_name) = &args.preset {
-        let preset = config.get_preset(preset_name)
+        let preset = config
+            .get_preset(preset_name)
             .ok_or_else(|| anyhow!("Preset nicht gefunden: {}", preset_name))?;
-        return Ok((preset.mode, preset.search.clone(), preset.replace.clone(), PrefixAction::Add, 1, date_position));
+        steps.extend(preset.pipeline());
+    } else if args.steps.is_empty() || has_step_args(args) {
+        steps.push(determine_step_from_args(args)?);
+    }
+
+    for step_str in &args.steps {
+        let mut step = parse_step(step_str).ok_or_else(|| {
+            anyhow!(
+                "Ungueltiger Schritt: {} (Format: modus[:suche[:ersetze]])",
+                step_str
+            )
+        })?;
+        apply_step_args(&mut step, args)?;
+        steps.push(step);
     }
 
+    Ok(steps)
+}
+
+/// Apply the shared options (date, regex, counter, checksum) to a step given as string
+fn apply_step_args(step: &mut RenameStep, args: &Args) -> Result<()> {
+    step.date_position = date_position_from_args(args)?;
+    step.date_source = date_source_from_args(args)?;
+    step.date_format = args.date_format.clone();
+    step.date_separator = args.date_separator.clone();
+    step.regex_flags = regex_flags_from_args(args)?;
+    step.number_start = args.start;
+    step.number_step = args.increment;
+    step.number_format = number_format_from_args(args)?;
+    step.number_group = counter_group_from_args(args)?;
+    step.hash_position = hash_position_from_args(args)?;
+    if step.mode == RenameMode::Hash {
+        step.replace = args.hash_separator.clone();
+    }
+    Ok(())
+}
+
+/// Check if any argument describing a single rename step was given
+fn has_step_args(args: &Args) -> bool {
+    args.search.is_some()
+        || args.mode.is_some()
+        || args.pattern.is_some()
+        || args.template.is_some()
+        || args.tags.is_some()
+        || args.script.is_some()
+        || args.organize.is_some()
+        || args.media
+        || args.ext.is_some()
+        || args.fix_ext
+        || args.hash.is_some()
+        || args.renumber
+        || args.pad_numbers.is_some()
+        || args.insert.is_some()
+        || args.delete.is_some()
+        || args.keep.is_some()
+        || args.cut_after.is_some()
+        || args.sanitize.is_some()
+        || args.prefix.is_some()
+        || args.suffix.is_some()
+        || args.remove_prefix.is_some()
+        || args.remove_suffix.is_some()
+        || args.date
+}
+
+/// Build the file filter from the preset, --filter and the single filter flags
+fn filter_from_args(args: &Args, config: &Config) -> Result<FileFilter> {
+    let mut filter = match (&args.filter, &args.preset) {
+        (Some(expression), _) => parse_filter(expression)?,
+        (None, Some(preset_name)) => config
+            .get_preset(preset_name)
+            .map(|preset| preset.filter.clone())
+            .unwrap_or_default(),
+        (None, None) => FileFilter::default(),
+    };
+
+    if let Some(glob) = &args.filter_name {
+        filter.glob = Some(glob.clone());
+    }
+    if let Some(regex) = &args.filter_regex {
+        filter.regex = Some(regex.clone());
+    }
+    if let Some(list) = &args.filter_ext {
+        filter.extensions = parse_extensions(list);
+    }
+    if let Some(size) = &args.min_size {
+        filter.min_size = Some(parse_size(size)?);
+    }
+    if let Some(size) = &args.max_size {
+        filter.max_size = Some(parse_size(size)?);
+    }
+    if let Some(date) = &args.modified_since {
+        parse_date(date)?;
+        filter.modified_since = Some(date.clone());
+    }
+    if let Some(date) = &args.modified_until {
+        parse_date(date)?;
+        filter.modified_until = Some(date.clone());
+    }
+    if let Some(entry_type) = &args.filter_type {
+        filter.entry_type = Some(
+            parse_entry_type(entry_type)
+                .ok_or_else(|| anyhow!("Unbekannter Typ: {} (erlaubt: file, dir)", entry_type))?,
+        );
+    }
+
+    // Check patterns early instead of failing on the first file
+    PreparedFilter::new(&filter)?;
+    Ok(filter)
+}
+
+/// Load the files of the directory that pass the filter
+fn load_filtered_files(
+    directory: &Path,
+    pattern: Option<&str>,
+    filter: &FileFilter,
+    config: &Config,
+) -> Result<Vec<FileEntry>> {
+    let filter = PreparedFilter::new(filter)?;
+    let mut files = app::load_files(directory, pattern, config.default_sort, config.max_depth)?;
+    files.retain(|file| filter.matches(file));
+    Ok(files)
+}
+
+/// Parse the date position argument
+fn date_position_from_args(args: &Args) -> Result<DatePosition> {
+    parse_date_position(&args.date_position).ok_or_else(|| {
+        anyhow!(
+            "Unbekannte Datums-Position: {} (erlaubt: prefix, suffix, replace)",
+            args.date_position
+        )
+    })
+}
+
+/// Parse the date source argument
+fn date_source_from_args(args: &Args) -> Result<DateSource> {
+    parse_date_source(&args.date_source).ok_or_else(|| {
+        anyhow!(
+            "Unbekannte Datumsquelle: {} (erlaubt: mtime, ctime, birth, exif, now)",
+            args.date_source
+        )
+    })
+}
+
+/// Parse the checksum position argument
+fn hash_position_from_args(args: &Args) -> Result<DatePosition> {
+    parse_date_position(&args.hash_position).ok_or_else(|| {
+        anyhow!(
+            "Unbekannte Position: {} (erlaubt: prefix, suffix, replace)",
+            args.hash_position
+        )
+    })
+}
+
+/// Compute the checksums that hash steps need, with progress on stderr
+fn compute_hashes(files: &[FileEntry], steps: &[RenameStep]) -> Result<HashCache> {
+    let mut hashes = HashCache::default();
+    let algorithms: Vec<HashAlgorithm> = steps
+        .iter()
+        .filter(|step| step.mode == RenameMode::Hash)
+        .map(|step| step.hash_algorithm)
+        .collect();
+
+    for algorithm in algorithms {
+        let files: Vec<&FileEntry> = files
+            .iter()
+            .filter(|file| !file.is_dir && !hashes.contains(&file.path, algorithm))
+            .collect();
+        let total_bytes: u64 = files.iter().map(|file| file.size).sum::<u64>().max(1);
+        let mut done_bytes = 0;
+        let mut shown = None;
+
+        for (i, file) in files.iter().enumerate() {
+            let hash = hash_file(&file.path, algorithm, |read| {
+                done_bytes += read;
+                let percent = (done_bytes * 100 / total_bytes).min(100);
+                if shown != Some((i, percent)) {
+                    shown = Some((i, percent));
+                    eprint!(
+                        "\rBerechne {}: {}/{} Dateien ({}%)",
+                        algorithm.display_name(),
+                        i + 1,
+                        files.len(),
+                        percent
+                    );
+                }
+            })
+            .map_err(|e| format!("Datei nicht lesbar: {}", e));
+            hashes.insert(file.path.clone(), algorithm, hash);
+        }
+        if !files.is_empty() {
+            eprintln!();
+        }
+    }
+
+    Ok(hashes)
+}
+
+/// Parse the regex flags argument
+fn regex_flags_from_args(args: &Args) -> Result<RegexFlags> {
+    match &args.regex_flags {
+        Some(flags) => parse_regex_flags(flags).ok_or_else(|| {
+            anyhow!(
+                "Ungueltige Regex-Flags: {} (erlaubt: i, m, first, nth=N, all)",
+                flags
+            )
+        }),
+        None => Ok(RegexFlags::default()),
+    }
+}
+
+/// Parse the counter format argument
+fn number_format_from_args(args: &Args) -> Result<NumberFormat> {
+    match &args.number_format {
+        Some(format) => parse_number_format(format).ok_or_else(|| {
+            anyhow!(
+                "Unbekanntes Zahlenformat: {} (erlaubt: decimal, alpha, roman, hex)",
+                format
+            )
+        }),
+        None => Ok(NumberFormat::default()),
+    }
+}
+
+/// Parse the counter group argument
+fn counter_group_from_args(args: &Args) -> Result<CounterGroup> {
+    match &args.group_by {
+        Some(group) => parse_counter_group(group)
+            .ok_or_else(|| anyhow!("Unbekannte Gruppierung: {} (erlaubt: ext, dir, day)", group)),
+        None => Ok(CounterGroup::default()),
+    }
+}
+
+/// Determine mode and settings from CLI arguments
+fn determine_step_from_args(args: &Args) -> Result<RenameStep> {
+    let date_position = date_position_from_args(args)?;
+    let date_source = date_source_from_args(args)?;
+    let regex_flags = regex_flags_from_args(args)?;
+    let number_format = number_format_from_args(args)?;
+    let number_group = counter_group_from_args(args)?;
+    let step = |mode: RenameMode, search: &str, prefix_action: PrefixAction| RenameStep {
+        prefix_action,
+        regex_flags,
+        date_position,
+        date_source,
+        date_format: args.date_format.clone(),
+        date_separator: args.date_separator.clone(),
+        number_start: args.start,
+        number_step: args.increment,
+        number_format,
+        number_group,
+        ..RenameStep::new(mode, search, "")
+    };
+
     // Check for shortcut arguments
     if args.date {
-        return Ok((RenameMode::DateInsert, String::new(), String::new(), PrefixAction::Add, 1, date_position));
+        return Ok(step(RenameMode::DateInsert, "", PrefixAction::Add));
     }
     if let Some(prefix) = &args.prefix {
-        return Ok((RenameMode::Prefix, prefix.clone(), String::new(), PrefixAction::Add, 1, date_position));
+        return Ok(step(RenameMode::Prefix, prefix, PrefixAction::Add));
     }
     if let Some(suffix) = &args.suffix {
-        return Ok((RenameMode::Suffix, suffix.clone(), String::new(), PrefixAction::Add, 1, date_position));
+        return Ok(step(RenameMode::Suffix, suffix, PrefixAction::Add));
     }
     if let Some(prefix) = &args.remove_prefix {
-        return Ok((RenameMode::Prefix, prefix.clone(), String::new(), PrefixAction::Remove, 1, date_position));
+        return Ok(step(RenameMode::Prefix, prefix, PrefixAction::Remove));
     }
     if let Some(suffix) = &args.remove_suffix {
-        return Ok((RenameMode::Suffix, suffix.clone(), String::new(), PrefixAction::Remove, 1, date_position));
+        return Ok(step(RenameMode::Suffix, suffix, PrefixAction::Remove));
+    }
+    if args.renumber {
+        return Ok(RenameStep {
+            renumber: true,
+            replace: args.offset.clone().unwrap_or_default(),
+            ..step(
+                RenameMode::Numbering,
+                args.pattern.as_deref().unwrap_or_default(),
+                PrefixAction::Add,
+            )
+        });
     }
     if let Some(pattern) = &args.pattern {
-        return Ok((RenameMode::Numbering, pattern.clone(), String::new(), PrefixAction::Add, args.start, date_position));
+        return Ok(step(RenameMode::Numbering, pattern, PrefixAction::Add));
+    }
+    if let Some(width) = &args.pad_numbers {
+        let target = parse_number_target(&args.pad_target).ok_or_else(|| {
+            anyhow!(
+                "Ungueltige Auswahl: {} (erlaubt: first, last, all)",
+                args.pad_target
+            )
+        })?;
+        return Ok(RenameStep {
+            pad_target: target,
+            ..step(RenameMode::PadNumbers, width, PrefixAction::Add)
+        });
+    }
+    if let Some(template) = &args.template {
+        return Ok(step(RenameMode::Template, template, PrefixAction::Add));
+    }
+    if let Some(pattern) = &args.tags {
+        return Ok(step(RenameMode::AudioTags, pattern, PrefixAction::Add));
+    }
+    if let Some(script) = &args.script {
+        return Ok(step(RenameMode::Script, script, PrefixAction::Add));
+    }
+    if let Some(template) = &args.organize {
+        return Ok(step(RenameMode::Organize, template, PrefixAction::Add));
+    }
+    if args.media {
+        // Empty schemes are taken from the config
+        return Ok(RenameStep {
+            replace: args.movie_format.clone().unwrap_or_default(),
+            ..step(
+                RenameMode::Media,
+                args.episode_format.as_deref().unwrap_or_default(),
+                PrefixAction::Add,
+            )
+        });
+    }
+    let position = |action: PositionAction, first: &str| RenameStep {
+        position_action: action,
+        replace: args.at.clone().unwrap_or_default(),
+        ..step(RenameMode::Position, first, PrefixAction::Add)
+    };
+    if let Some(text) = &args.insert {
+        return Ok(position(PositionAction::Insert, text));
+    }
+    if let Some(range) = &args.delete {
+        return Ok(position(PositionAction::Delete, range));
+    }
+    if let Some(range) = &args.keep {
+        return Ok(position(PositionAction::Keep, range));
+    }
+    if let Some(delimiter) = &args.cut_after {
+        return Ok(position(PositionAction::CutAfter, delimiter));
+    }
+    if let Some(profile) = &args.sanitize {
+        let profile = parse_sanitize_profile(profile).ok_or_else(|| {
+            anyhow!(
+                "Unbekanntes Profil: {} (erlaubt: posix, windows, fat, url)",
+                profile
+            )
+        })?;
+        return Ok(RenameStep {
+            sanitize_profile: profile,
+            ..step(RenameMode::Sanitize, "", PrefixAction::Add)
+        });
+    }
+    if args.fix_ext {
+        return Ok(step(RenameMode::FixExtension, "", PrefixAction::Add));
+    }
+    if let Some(algorithm) = &args.hash {
+        let algorithm = parse_hash_algorithm(algorithm).ok_or_else(|| {
+            anyhow!(
+                "Unbekannter Algorithmus: {} (erlaubt: sha256, blake3, crc32)",
+                algorithm
+            )
+        })?;
+        return Ok(RenameStep {
+            hash_algorithm: algorithm,
+            hash_position: hash_position_from_args(args)?,
+            replace: args.hash_separator.clone(),
+            ..step(
+                RenameMode::Hash,
+                args.hash_length.as_deref().unwrap_or_default(),
+                PrefixAction::Add,
+            )
+        });
+    }
+    if let Some(ext) = &args.ext {
+        let (action, value) = parse_extension_action(ext)
+            .ok_or_else(|| anyhow!("Ungueltige Endungs-Aktion: {} (erlaubt: set:EXT, lower, normalize, remove, add:EXT)", ext))?;
+        return Ok(RenameStep {
+            extension_action: action,
+            ..step(RenameMode::Extension, &value, PrefixAction::Add)
+        });
     }
 
     // Use explicit mode
@@ -390,69 +1361,203 @@ fn determine_mode_from_args(args: &Args, config: &Config) -> Result<(RenameMode,
         RenameMode::SearchReplace
     };
 
-    Ok((
-        mode,
-        args.search.clone().unwrap_or_default(),
-        args.replace.clone().unwrap_or_default(),
-        PrefixAction::Add,
-        args.start,
-        date_position,
-    ))
+    Ok(RenameStep {
+        replace: args.replace.clone().unwrap_or_default(),
+        ..step(
+            mode,
+            args.search.as_deref().unwrap_or_default(),
+            PrefixAction::Add,
+        )
+    })
 }
 
 /// Validate inputs based on mode
-fn validate_mode_inputs(mode: RenameMode, search: &str) -> Result<()> {
-    match mode {
-        RenameMode::SearchReplace | RenameMode::Regex => {
-            if search.is_empty() {
-                return Err(anyhow!("Fuer diesen Modus muss --search angegeben werden"));
-            }
+fn validate_mode_inputs(step: &RenameStep) -> Result<()> {
+    match step.mode {
+        RenameMode::SearchReplace | RenameMode::Regex if step.search.is_empty() => {
+            Err(anyhow!("Fuer diesen Modus muss --search angegeben werden"))
         }
-        RenameMode::Numbering => {
-            if search.is_empty() {
-                return Err(anyhow!("Fuer Nummerierung muss --pattern angegeben werden"));
-            }
+        RenameMode::Numbering if step.search.is_empty() && !step.renumber => {
+            Err(anyhow!("Fuer Nummerierung muss --pattern angegeben werden"))
         }
-        RenameMode::Prefix | RenameMode::Suffix => {
-            if search.is_empty() {
-                return Err(anyhow!("Fuer Prefix/Suffix muss ein Wert angegeben werden"));
-            }
+        RenameMode::Prefix | RenameMode::Suffix if step.search.is_empty() => {
+            Err(anyhow!("Fuer Prefix/Suffix muss ein Wert angegeben werden"))
         }
-        RenameMode::DateInsert => {
-            // No additional validation needed for date mode
+        RenameMode::Template if step.search.is_empty() => {
+            Err(anyhow!("Fuer Templates muss --template angegeben werden"))
         }
-        _ => {}
+        RenameMode::AudioTags if step.search.is_empty() => {
+            Err(anyhow!("Fuer Audio-Tags muss --tags angegeben werden"))
+        }
+        RenameMode::Script if step.search.is_empty() => {
+            Err(anyhow!("Fuer Skripte muss --script angegeben werden"))
+        }
+        RenameMode::Organize if step.search.is_empty() => {
+            Err(anyhow!("Zum Ordnen muss --organize angegeben werden"))
+        }
+        RenameMode::Extension if step.extension_action.uses_value() && step.search.is_empty() => {
+            Err(anyhow!(
+                "Fuer '{}' muss eine Endung angegeben werden (z.B. --ext set:png)",
+                step.extension_action.display_name()
+            ))
+        }
+        RenameMode::Position if step.search.is_empty() => Err(anyhow!(
+            "Fuer '{}' muss ein Wert angegeben werden",
+            step.position_action.display_name()
+        )),
+        RenameMode::Position => {
+            PositionEdit::parse(step.position_action, &step.search, &step.replace).map(|_| ())
+        }
+        RenameMode::PadNumbers => parse_pad_width(&step.search).map(|_| ()),
+        RenameMode::Hash => parse_hash_length(&step.search).map(|_| ()),
+        RenameMode::DateInsert => validate_date_format(&step.date_format),
+        _ => Ok(()),
     }
-    Ok(())
 }
 
 /// Print mode-specific details
-fn print_mode_details(mode: RenameMode, search: &str, replace: &str, prefix_action: PrefixAction, date_position: DatePosition) {
-    match mode {
+fn print_mode_details(step: &RenameStep) {
+    let (search, replace) = (&step.search, &step.replace);
+    match step.mode {
         RenameMode::SearchReplace => {
             println!("Suche: '{}' -> Ersetze: '{}'", search, replace);
         }
         RenameMode::Regex => {
-            println!("Regex: '{}' -> '{}'", search, replace);
+            println!(
+                "Regex: '{}' -> '{}' (Flags: {})",
+                search,
+                replace,
+                step.regex_flags.display()
+            );
+        }
+        RenameMode::Numbering if step.renumber => {
+            let width = if search.is_empty() {
+                "wie bisher"
+            } else {
+                search.as_str()
+            };
+            let offset = if replace.is_empty() {
+                "0"
+            } else {
+                replace.as_str()
+            };
+            println!(
+                "Umnummerieren: Breite '{}', Versatz {} ({})",
+                width,
+                offset,
+                step.number_format.display_name()
+            );
         }
         RenameMode::Numbering => {
-            println!("Muster: '{}'", search);
+            println!(
+                "Muster: '{}' (Start {}, Schritt {}, {}, Zaehler: {})",
+                search,
+                step.number_start,
+                step.number_step,
+                step.number_format.display_name(),
+                step.number_group.display_name()
+            );
+        }
+        RenameMode::PadNumbers => {
+            let width = if parse_pad_width(search).unwrap_or(0) == 0 {
+                "automatisch"
+            } else {
+                search.as_str()
+            };
+            println!(
+                "Breite: {} (Nummern: {})",
+                width,
+                step.pad_target.display_name()
+            );
+        }
+        RenameMode::Hash => {
+            let length = if search.is_empty() {
+                "voll"
+            } else {
+                search.as_str()
+            };
+            println!(
+                "Algorithmus: {} (Laenge: {}, Position: {}, Trenner: '{}')",
+                step.hash_algorithm.display_name(),
+                length,
+                step.hash_position.display_name(),
+                replace
+            );
+        }
+        RenameMode::Template => {
+            println!("Template: '{}'", search);
+        }
+        RenameMode::AudioTags => {
+            println!("Tag-Muster: '{}'", search);
+        }
+        RenameMode::Script => {
+            println!("Skript: '{}'", search);
+        }
+        RenameMode::Organize => {
+            println!("Ziel: '{}'", search);
+        }
+        RenameMode::Media => {
+            let scheme = |value: &str| {
+                if value.is_empty() {
+                    "aus der Config".to_string()
+                } else {
+                    format!("'{}'", value)
+                }
+            };
+            println!("Serie: {}, Film: {}", scheme(search), scheme(replace));
+        }
+        RenameMode::Extension => {
+            if step.extension_action.uses_value() {
+                println!(
+                    "Endung: '{}' ({})",
+                    search,
+                    step.extension_action.display_name()
+                );
+            } else {
+                println!("Endung: {}", step.extension_action.display_name());
+            }
         }
         RenameMode::Prefix | RenameMode::Suffix => {
-            let action = if prefix_action == PrefixAction::Add { "Hinzufuegen" } else { "Entfernen" };
-            println!("{}: '{}' ({})", mode.display_name(), search, action);
+            let action = if step.prefix_action == PrefixAction::Add {
+                "Hinzufuegen"
+            } else {
+                "Entfernen"
+            };
+            println!("{}: '{}' ({})", step.mode.display_name(), search, action);
+        }
+        RenameMode::Sanitize => {
+            println!("Profil: {}", step.sanitize_profile.display_name());
+        }
+        RenameMode::Position => {
+            if step.position_action.uses_second_input() && !replace.is_empty() {
+                println!(
+                    "{}: '{}' ({})",
+                    step.position_action.display_name(),
+                    search,
+                    replace
+                );
+            } else {
+                println!("{}: '{}'", step.position_action.display_name(), search);
+            }
         }
         RenameMode::DateInsert => {
-            println!("Position: {} (Format: YYYYMMDD)", date_position.display_name());
+            println!(
+                "Position: {} (Format: '{}', Trenner: '{}', Quelle: {})",
+                step.date_position.display_name(),
+                step.date_format,
+                step.date_separator,
+                step.date_source.display_name()
+            );
         }
         _ => {}
     }
 }
 
 /// Run in interactive TUI mode
-fn run_interactive(directory: PathBuf, pattern: Option<String>) -> Result<()> {
-    // Load config for defaults
-    let config = Config::load().unwrap_or_default();
+fn run_interactive(args: &Args, directory: PathBuf, pattern: Option<String>) -> Result<()> {
+    // Load config for defaults, with the same overrides as the CLI
+    let config = load_config(args)?;
+    let filter = filter_from_args(args, &config)?;
 
     // Setup terminal
     enable_raw_mode()?;
@@ -462,12 +1567,14 @@ fn run_interactive(directory: PathBuf, pattern: Option<String>) -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
-    let mut app = App::new(directory, pattern)?;
-    
+    let default_sort = config.default_sort;
+    let default_mode = config.default_mode;
+    let mut app = App::new(directory, pattern, filter, config)?;
+
     // Apply config defaults
-    app.rename_mode = config.default_mode;
-    app.sort_order = config.default_sort;
+    app.sort_order = default_sort;
     app.apply_sort();
+    app.set_mode(default_mode);
 
     // Main loop
     let result = run_app(&mut terminal, &mut app);
@@ -500,9 +1607,7 @@ fn parse_input(input: &str) -> (PathBuf, Option<String>) {
             } else {
                 parent.to_path_buf()
             };
-            let pattern = path
-                .file_name()
-                .map(|s| s.to_string_lossy().to_string());
+            let pattern = path.file_name().map(|s| s.to_string_lossy().to_string());
             (dir, pattern)
         } else {
             (PathBuf::from("."), Some(input.to_string()))
@@ -513,8 +1618,45 @@ fn parse_input(input: &str) -> (PathBuf, Option<String>) {
     }
 }
 
+/// Suspend the TUI, edit the names in $EDITOR and show the result as preview
+fn edit_in_editor(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+) -> Result<()> {
+    let indices = app.edit_indices();
+    if indices.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<String> = indices
+        .iter()
+        .map(|&i| app.files[i].relative_name())
+        .collect();
+
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+
+    let edited = edit_names(&names);
+
+    enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableMouseCapture
+    )?;
+    terminal.clear()?;
+
+    app.apply_edited_names(&indices, edited);
+    Ok(())
+}
+
 fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> Result<()> {
     loop {
+        // Pick up checksums from the background hashing
+        app.poll_hash_job();
         terminal.draw(|frame| draw_ui(frame, app))?;
 
         if event::poll(std::time::Duration::from_millis(50))? {
@@ -522,6 +1664,7 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App)
                 match handle_key_event(app, key) {
                     AppResult::Continue => {}
                     AppResult::Quit => break,
+                    AppResult::Edit => edit_in_editor(terminal, app)?,
                 }
             }
         }
@@ -0,0 +1,351 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+
+use crate::app::FileEntry;
+use crate::operations::{target_error, RenamePreview};
+
+/// Column titles that mark the first row of a CSV/TSV file as header
+const HEADER_NAMES: &[&str] = &[
+    "old",
+    "old name",
+    "from",
+    "source",
+    "original",
+    "alt",
+    "alter name",
+    "von",
+    "quelle",
+];
+
+/// One "old name -> new name" pair of a mapping file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappingEntry {
+    pub source: String,
+    pub target: String,
+    /// Where the entry is in the file, e.g. "Zeile 3" (CSV/TSV), "Eintrag 2" (JSON list)
+    /// or "Eintrag 'a.txt'" (JSON object, whose keys are not kept in file order)
+    pub location: String,
+}
+
+/// Load a mapping file, the format follows the extension (.csv, .tsv, .json)
+pub fn load_mapping(path: &Path) -> Result<Vec<MappingEntry>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Zuordnung nicht lesbar: {}", path.display()))?;
+    // Spreadsheet exports often start with a byte order mark
+    let content = content.trim_start_matches('\u{feff}');
+
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "csv" => parse_delimited(content, detect_csv_delimiter(content)),
+        "tsv" | "tab" => parse_delimited(content, b'\t'),
+        "json" => parse_json(content),
+        _ => Err(anyhow!(
+            "Unbekanntes Format: {} (erlaubt: .csv, .tsv, .json)",
+            path.display()
+        )),
+    }
+}
+
+/// Comma or semicolon (German spreadsheet exports), whichever the first line uses more
+fn detect_csv_delimiter(content: &str) -> u8 {
+    let first = content.lines().next().unwrap_or("");
+    if first.matches(';').count() > first.matches(',').count() {
+        b';'
+    } else {
+        b','
+    }
+}
+
+/// Parse CSV/TSV rows "old<delimiter>new", extra columns are ignored
+fn parse_delimited(content: &str, delimiter: u8) -> Result<Vec<MappingEntry>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let mut entries = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| anyhow!("Ungueltige Zuordnung: {}", e))?;
+        let line = record.position().map_or(i as u64 + 1, |p| p.line());
+        let source = record.get(0).unwrap_or("").trim();
+        let target = record.get(1).unwrap_or("").trim();
+
+        if source.is_empty() && target.is_empty() {
+            continue;
+        }
+        if i == 0 && HEADER_NAMES.contains(&source.to_lowercase().as_str()) {
+            continue;
+        }
+        entries.push(entry(source, target, format!("Zeile {}", line))?);
+    }
+
+    Ok(entries)
+}
+
+/// Parse a JSON object {"old": "new"} or a list of ["old", "new"] pairs
+/// or {"from": "old", "to": "new"} objects
+fn parse_json(content: &str) -> Result<Vec<MappingEntry>> {
+    let value: Value =
+        serde_json::from_str(content).map_err(|e| anyhow!("Ungueltiges JSON: {}", e))?;
+    let as_str = |value: Option<&Value>| value.and_then(Value::as_str).unwrap_or("").to_string();
+
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(source, target)| {
+                entry(
+                    source,
+                    &as_str(Some(target)),
+                    format!("Eintrag '{}'", source),
+                )
+            })
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let (source, target) = match item {
+                    Value::Array(pair) => (as_str(pair.first()), as_str(pair.get(1))),
+                    Value::Object(object) => (
+                        as_str(object.get("from").or(object.get("old"))),
+                        as_str(object.get("to").or(object.get("new"))),
+                    ),
+                    _ => (String::new(), String::new()),
+                };
+                entry(&source, &target, format!("Eintrag {}", i + 1))
+            })
+            .collect(),
+        _ => Err(anyhow!(
+            "Ungueltiges JSON: erwartet {{\"alt\": \"neu\"}} oder [[\"alt\", \"neu\"]]"
+        )),
+    }
+}
+
+fn entry(source: &str, target: &str, location: String) -> Result<MappingEntry> {
+    if source.is_empty() || target.is_empty() {
+        return Err(anyhow!("{}: alter und neuer Name erforderlich", location));
+    }
+    Ok(MappingEntry {
+        source: source.to_string(),
+        target: target.to_string(),
+        location,
+    })
+}
+
+/// Problems found while matching a mapping against the files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MappingReport {
+    /// Missing sources and duplicate entries, nothing is renamed if there are any
+    pub errors: Vec<String>,
+    /// Files without an entry, they keep their name
+    pub unmapped: Vec<String>,
+}
+
+/// Build previews from the mapping pairs instead of a rename mode
+pub fn mapping_previews(
+    files: &[FileEntry],
+    entries: &[MappingEntry],
+) -> (Vec<RenamePreview>, MappingReport) {
+    let index: HashMap<String, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(i, file)| (file.relative_name(), i))
+        .collect();
+    let mut report = MappingReport::default();
+    let mut sources = HashSet::new();
+    let mut targets = HashSet::new();
+    let mut previews = Vec::new();
+
+    for entry in entries {
+        if !sources.insert(entry.source.as_str()) {
+            report.errors.push(format!(
+                "{}: '{}' ist mehrfach zugeordnet",
+                entry.location, entry.source
+            ));
+            continue;
+        }
+        if !targets.insert(entry.target.as_str()) {
+            report.errors.push(format!(
+                "{}: Ziel '{}' ist mehrfach vergeben",
+                entry.location, entry.target
+            ));
+        }
+        let Some(&file_index) = index.get(entry.source.as_str()) else {
+            report.errors.push(format!(
+                "{}: '{}' nicht gefunden",
+                entry.location, entry.source
+            ));
+            continue;
+        };
+        // Mapped files stay in their folder, invalid targets are skipped
+        let error = (entry.source != entry.target)
+            .then(|| target_error(&entry.source, &entry.target, false))
+            .flatten();
+        let new_name = if error.is_some() {
+            &entry.source
+        } else {
+            &entry.target
+        };
+        previews.push(RenamePreview {
+            original_name: entry.source.clone(),
+            new_name: new_name.clone(),
+            will_change: entry.source != *new_name,
+            error,
+            type_mismatch: None,
+            rule: None,
+            file_index,
+        });
+    }
+
+    report.unmapped = files
+        .iter()
+        .filter(|file| !file.is_dir)
+        .map(|file| file.relative_name())
+        .filter(|name| !sources.contains(name.as_str()))
+        .collect();
+
+    // Sort by original name for display
+    previews.sort_by(|a, b| a.original_name.cmp(&b.original_name));
+
+    (previews, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn make_file(name: &str) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(name),
+            name: name.to_string(),
+            subdir: PathBuf::new(),
+            is_dir: false,
+            size: 0,
+            modified: None,
+            extension: String::new(),
+        }
+    }
+
+    fn pair(source: &str, target: &str, line: usize) -> MappingEntry {
+        entry(source, target, format!("Zeile {}", line)).unwrap()
+    }
+
+    fn pairs(entries: &[MappingEntry]) -> Vec<(&str, &str)> {
+        entries
+            .iter()
+            .map(|e| (e.source.as_str(), e.target.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let content = "alter Name;neuer Name\nIMG_1.jpg;Strand.jpg\n\"a;b.txt\";c.txt;Notiz\n";
+        let entries = parse_delimited(content, detect_csv_delimiter(content)).unwrap();
+        assert_eq!(
+            pairs(&entries),
+            [("IMG_1.jpg", "Strand.jpg"), ("a;b.txt", "c.txt")]
+        );
+        assert_eq!(entries[1].location, "Zeile 3");
+
+        let entries = parse_delimited("old,new\nx.txt, y.txt\n", b',').unwrap();
+        assert_eq!(pairs(&entries), [("x.txt", "y.txt")]);
+        assert!(parse_delimited("x.txt\n", b',').is_err());
+    }
+
+    #[test]
+    fn test_parse_tsv() {
+        let entries = parse_delimited("a b.txt\tc d.txt\n", b'\t').unwrap();
+        assert_eq!(pairs(&entries), [("a b.txt", "c d.txt")]);
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let entries = parse_json(r#"{"a.txt": "b.txt"}"#).unwrap();
+        assert_eq!(pairs(&entries), [("a.txt", "b.txt")]);
+
+        let entries =
+            parse_json(r#"[["a.txt", "b.txt"], {"from": "c.txt", "to": "d.txt"}]"#).unwrap();
+        assert_eq!(pairs(&entries), [("a.txt", "b.txt"), ("c.txt", "d.txt")]);
+
+        assert!(parse_json(r#"[["a.txt"]]"#).is_err());
+        // Object entries are named by their key, not by a position
+        let error = parse_json(r#"{"z.txt": "y.txt", "a.txt": ""}"#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Eintrag 'a.txt': alter und neuer Name erforderlich"
+        );
+        let entries = parse_json(r#"{"b.txt": "c.txt"}"#).unwrap();
+        assert_eq!(entries[0].location, "Eintrag 'b.txt'");
+        assert!(parse_json(r#""a.txt""#).is_err());
+    }
+
+    #[test]
+    fn test_mapping_previews() {
+        let files = vec![
+            make_file("a.txt"),
+            make_file("b.txt"),
+            make_file("c.txt"),
+            make_file("d.txt"),
+        ];
+        let entries = vec![
+            pair("b.txt", "zwei.txt", 1),
+            pair("a.txt", "eins.txt", 2),
+            pair("c.txt", "c.txt", 3),
+        ];
+        let (previews, report) = mapping_previews(&files, &entries);
+
+        assert_eq!(previews.len(), 3);
+        assert_eq!(previews[0].original_name, "a.txt");
+        assert_eq!(previews[0].new_name, "eins.txt");
+        assert!(!previews[2].will_change);
+        assert!(report.errors.is_empty());
+        assert_eq!(report.unmapped, ["d.txt"]);
+    }
+
+    #[test]
+    fn test_mapping_errors() {
+        let files = vec![make_file("a.txt"), make_file("b.txt")];
+        let entries = vec![
+            pair("a.txt", "x.txt", 1),
+            pair("a.txt", "y.txt", 2),
+            pair("b.txt", "x.txt", 3),
+            pair("fehlt.txt", "z.txt", 4),
+        ];
+        let (_, report) = mapping_previews(&files, &entries);
+
+        assert_eq!(
+            report.errors,
+            [
+                "Zeile 2: 'a.txt' ist mehrfach zugeordnet",
+                "Zeile 3: Ziel 'x.txt' ist mehrfach vergeben",
+                "Zeile 4: 'fehlt.txt' nicht gefunden",
+            ]
+        );
+        assert!(report.unmapped.is_empty());
+    }
+
+    #[test]
+    fn test_target_in_subfolder_is_rejected() {
+        let files = [make_file("a.txt"), make_file("b.txt")];
+        let entries = [pair("a.txt", "neu/a.txt", 1), pair("b.txt", "../b.txt", 2)];
+        let (previews, report) = mapping_previews(&files, &entries);
+        assert!(report.errors.is_empty());
+
+        assert!(!previews[0].will_change);
+        assert_eq!(previews[0].new_name, "a.txt");
+        assert_eq!(
+            previews[0].error.as_deref(),
+            Some("Ungueltiger Dateiname: neu/a.txt (Unterordner nur im Ordnen-Modus)")
+        );
+        assert!(!previews[1].will_change);
+        assert!(previews[1].error.is_some());
+    }
+}
@@ -0,0 +1,541 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use regex::{Regex, RegexBuilder};
+
+/// Extensions of subtitle files, a language code before them stays with the extension
+const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "sub", "idx", "ass", "ssa", "vtt"];
+
+/// What was recognized in the name of an episode or a movie
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MediaInfo {
+    /// Name of the show, or the title of a movie
+    pub name: String,
+    pub year: Option<String>,
+    /// Season and episode, None for movies
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+    /// Last episode of a file with several episodes (S01E01E02)
+    pub last_episode: Option<u32>,
+    /// Episode title, empty if there is none
+    pub title: String,
+    /// Release tags, e.g. "1080p", "WEB-DL", "x264"
+    pub resolution: Option<String>,
+    pub source: Option<String>,
+    pub codec: Option<String>,
+}
+
+impl MediaInfo {
+    pub fn is_episode(&self) -> bool {
+        self.episode.is_some()
+    }
+}
+
+/// Recognizes release names like "Show.Name.S01E02.Title.1080p.WEB-DL.x264.mkv",
+/// "Show 1x02", "Movie.Name.2019.1080p" and episodes in season folders
+pub struct MediaParser {
+    season_episode: Regex,
+    cross_episode: Regex,
+    folder_episode: Regex,
+    leading_number: Regex,
+    season_folder: Regex,
+    year: Regex,
+    resolution: Regex,
+    source: Regex,
+    codec: Regex,
+    /// Other release tags that only mark the end of the title (audio, language, ...)
+    other_tags: Regex,
+    release_group: Regex,
+}
+
+impl MediaParser {
+    pub fn new() -> Self {
+        let re = |pattern: &str| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .expect("valid media pattern")
+        };
+        Self {
+            season_episode: re(r"s(\d{1,2})[ ._-]?e(\d{1,3})(?:-?e(\d{1,3})|-(\d{1,3}))?"),
+            cross_episode: re(r"(\d{1,2})x(\d{2,3})"),
+            folder_episode: re(r"(?:episode|folge|ep|e)[ ._]?(\d{1,3})"),
+            leading_number: re(r"^(\d{1,3})"),
+            season_folder: re(r"^(?:season|staffel|series|s)[ ._-]*(\d{1,2})$"),
+            year: re(r"(?:19|20)\d{2}"),
+            resolution: re(r"\d{3,4}[pi]|4k|uhd"),
+            source: re(r"blu-?ray|bdrip|brrip|web-?dl|web-?rip|web|hdtv|dvd-?rip|dvd|hdrip|remux"),
+            codec: re(r"[xh]\.?26[45]|hevc|avc|xvid|divx|av1"),
+            other_tags: re(
+                r"aac(?:2\.0|5\.1)?|ac3|e?ac3|dts(?:-hd)?|ddp?(?:2\.0|5\.1)?|atmos|truehd|10bit|hdr(?:10)?|dv|proper|repack|extended|internal|limited|multi|german|dl|subbed",
+            ),
+            release_group: re(r"^\s*\[[^\]]*\]\s*"),
+        }
+    }
+
+    /// Parse the stem of a file name, the folders above it help with season folders.
+    /// None if neither an episode nor a movie with year is recognized.
+    pub fn parse(&self, stem: &str, parent: &str, grandparent: &str) -> Option<MediaInfo> {
+        // "[Group] Show - 01" starts with the release group
+        let stem = self.release_group.replace(stem, "");
+        let stem = stem.as_ref();
+        let season_folder = self
+            .season_folder
+            .captures(parent.trim())
+            .and_then(|caps| caps[1].parse::<u32>().ok());
+        // Show name from the folders if the file name has none
+        let folder_show = if season_folder.is_some() {
+            grandparent
+        } else {
+            parent
+        };
+
+        let mut info = MediaInfo::default();
+        let rest = if let Some(caps) = find_word(&self.season_episode, stem) {
+            let whole = caps.get(0)?;
+            info.season = caps[1].parse().ok();
+            info.episode = caps[2].parse().ok();
+            info.last_episode = caps
+                .get(3)
+                .or_else(|| caps.get(4))
+                .and_then(|m| m.as_str().parse().ok());
+            info.name = self.show_name(&stem[..whole.start()], folder_show, &mut info.year);
+            &stem[whole.end()..]
+        } else if let Some(caps) = find_word(&self.cross_episode, stem) {
+            let whole = caps.get(0)?;
+            info.season = caps[1].parse().ok();
+            info.episode = caps[2].parse().ok();
+            info.name = self.show_name(&stem[..whole.start()], folder_show, &mut info.year);
+            &stem[whole.end()..]
+        } else if let Some(season) = season_folder {
+            let caps = find_word(&self.folder_episode, stem)
+                .or_else(|| find_word(&self.leading_number, stem))?;
+            info.season = Some(season);
+            info.episode = caps[1].parse().ok();
+            info.name = clean(grandparent);
+            &stem[caps.get(0)?.end()..]
+        } else {
+            // Movie: the last year before the release tags ("Blade.Runner.2049.2017")
+            let end = self.tags_start(stem);
+            let year = find_words(&self.year, &stem[..end])
+                .filter(|m| !clean(&stem[..m.start()]).is_empty())
+                .last()?;
+            info.year = Some(year.as_str().to_string());
+            info.name = clean(&stem[..year.start()]);
+            &stem[year.end()..]
+        };
+
+        if info.is_episode() {
+            info.title = clean(&rest[..self.tags_start(rest)]);
+        }
+        info.resolution = find_words(&self.resolution, stem)
+            .next()
+            .map(|m| m.as_str().to_string());
+        info.source = find_words(&self.source, stem)
+            .next()
+            .map(|m| m.as_str().to_string());
+        info.codec = find_words(&self.codec, stem)
+            .next()
+            .map(|m| m.as_str().to_string());
+
+        if info.name.is_empty() {
+            return None;
+        }
+        Some(info)
+    }
+
+    /// Show name before the episode marker, a trailing year goes to `year`
+    fn show_name(&self, text: &str, folder: &str, year: &mut Option<String>) -> String {
+        let mut name = clean(text);
+        if let Some(found) = find_words(&self.year, &name).last() {
+            let before = clean(&name[..found.start()]);
+            if !before.is_empty() && clean(&name[found.end()..]).is_empty() {
+                *year = Some(found.as_str().to_string());
+                name = before;
+            }
+        }
+        if name.is_empty() {
+            clean(folder)
+        } else {
+            name
+        }
+    }
+
+    /// Position of the first release tag, the end of the text if there is none
+    fn tags_start(&self, text: &str) -> usize {
+        [
+            &self.resolution,
+            &self.source,
+            &self.codec,
+            &self.other_tags,
+        ]
+        .iter()
+        .filter_map(|re| find_words(re, text).next())
+        .map(|m| m.start())
+        .min()
+        .unwrap_or(text.len())
+    }
+}
+
+impl Default for MediaParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check that a match stands on its own: separators or the ends of the text around it
+fn is_word(text: &str, start: usize, end: usize) -> bool {
+    let separator = |c: char| !c.is_alphanumeric();
+    text[..start].chars().next_back().is_none_or(separator)
+        && text[end..].chars().next().is_none_or(separator)
+}
+
+/// Matches of a pattern that are whole words
+fn find_words<'a>(re: &'a Regex, text: &'a str) -> impl Iterator<Item = regex::Match<'a>> {
+    // Every start position, so "2049.2017" finds both years
+    (0..text.len())
+        .filter(|&start| text.is_char_boundary(start))
+        .filter_map(move |start| {
+            let m = re.find_at(text, start)?;
+            (m.start() == start && is_word(text, m.start(), m.end())).then_some(m)
+        })
+}
+
+/// First whole-word match of a pattern with its groups
+fn find_word<'a>(re: &Regex, text: &'a str) -> Option<regex::Captures<'a>> {
+    let start = find_words(re, text).next()?.start();
+    re.captures_at(text, start)
+}
+
+/// Turn a part of a release name into words: separators become spaces,
+/// names written all lowercase get capitals
+fn clean(text: &str) -> String {
+    let words: Vec<&str> = text
+        .split(|c: char| c == '.' || c == '_' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let joined = words.join(" ");
+    let trimmed = joined.trim_matches(|c: char| {
+        c == ' ' || c == '-' || c == '[' || c == ']' || c == '(' || c == ')'
+    });
+
+    if trimmed.chars().any(|c| c.is_uppercase()) {
+        return trimmed.to_string();
+    }
+    trimmed
+        .split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Part of a naming scheme
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    /// Show or movie name
+    Show,
+    /// Episode title, for movies the movie title
+    Title,
+    Year,
+    Season {
+        width: usize,
+    },
+    Episode {
+        width: usize,
+    },
+    Resolution,
+    Source,
+    Codec,
+    Ext,
+}
+
+/// Naming scheme like `{show} - S{season:02}E{episode:02} - {title}.{ext}`
+#[derive(Debug, Clone)]
+pub struct MediaScheme {
+    parts: Vec<Part>,
+}
+
+impl MediaScheme {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = input;
+        while let Some(open) = rest.find('{') {
+            if open > 0 {
+                parts.push(Part::Literal(rest[..open].to_string()));
+            }
+            let close = rest[open..]
+                .find('}')
+                .map(|pos| open + pos)
+                .ok_or_else(|| anyhow!("Ungueltiges Schema: '{{' ohne '}}'"))?;
+            parts.push(parse_part(&rest[open + 1..close])?);
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Render the scheme, separators around empty values are dropped
+    pub fn render(&self, info: &MediaInfo, ext: &str) -> String {
+        let mut result = String::new();
+        for part in &self.parts {
+            let value = match part {
+                Part::Literal(text) => text.clone(),
+                Part::Show => info.name.clone(),
+                Part::Title if info.is_episode() => info.title.clone(),
+                Part::Title => info.name.clone(),
+                Part::Year => info.year.clone().unwrap_or_default(),
+                Part::Season { width } => info
+                    .season
+                    .map(|season| format!("{:0width$}", season, width = *width))
+                    .unwrap_or_default(),
+                Part::Episode { width } => match (info.episode, info.last_episode) {
+                    (Some(first), Some(last)) => {
+                        format!("{:0width$}-E{:0width$}", first, last, width = *width)
+                    }
+                    (Some(first), None) => format!("{:0width$}", first, width = *width),
+                    _ => String::new(),
+                },
+                Part::Resolution => info.resolution.clone().unwrap_or_default(),
+                Part::Source => info.source.clone().unwrap_or_default(),
+                Part::Codec => info.codec.clone().unwrap_or_default(),
+                Part::Ext => ext.to_string(),
+            };
+            result.push_str(&value);
+        }
+        tidy(&result, ext)
+    }
+}
+
+fn parse_part(content: &str) -> Result<Part> {
+    let (name, width) = match content.split_once(':') {
+        Some((name, width)) => {
+            let width = width
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("Ungueltige Breite in {{{}}}", content))?;
+            (name.trim(), width)
+        }
+        None => (content.trim(), 1),
+    };
+    Ok(match name {
+        "show" | "name" => Part::Show,
+        "title" => Part::Title,
+        "year" => Part::Year,
+        "season" => Part::Season { width },
+        "episode" => Part::Episode { width },
+        "resolution" => Part::Resolution,
+        "source" => Part::Source,
+        "codec" => Part::Codec,
+        "ext" => Part::Ext,
+        other => return Err(anyhow!("Unbekannter Platzhalter: {{{}}}", other)),
+    })
+}
+
+/// Drop what empty values leave behind: "()", doubled and dangling " - "
+fn tidy(name: &str, ext: &str) -> String {
+    let mut name = name.replace("()", "").replace("[]", "");
+    while name.contains(" -  - ") || name.contains("  ") {
+        name = name.replace(" -  - ", " - ").replace("  ", " ");
+    }
+    let suffix = format!(".{}", ext);
+    let (stem, ext) = match name.strip_suffix(&suffix) {
+        Some(stem) if !ext.is_empty() => (stem, suffix.as_str()),
+        // No extension: drop the dot in front of {ext}
+        _ => (name.trim_end_matches('.'), ""),
+    };
+    let stem = stem
+        .trim_end_matches([' ', '-'])
+        .trim_start_matches([' ', '-']);
+    format!("{}{}", stem, ext)
+}
+
+/// Renames media files by a scheme for episodes and one for movies
+pub struct MediaRenamer {
+    parser: MediaParser,
+    episode_scheme: MediaScheme,
+    movie_scheme: MediaScheme,
+}
+
+impl MediaRenamer {
+    pub fn new(episode_format: &str, movie_format: &str) -> Result<Self> {
+        Ok(Self {
+            parser: MediaParser::new(),
+            episode_scheme: MediaScheme::parse(episode_format)?,
+            movie_scheme: MediaScheme::parse(movie_format)?,
+        })
+    }
+
+    /// New name for a file or folder, errors if the name is not recognized
+    pub fn rename(&self, filename: &str, path: &Path, is_dir: bool) -> Result<String> {
+        // Folders have no extension ("Movie.Name.2019.1080p")
+        let (stem, ext) = if is_dir {
+            (filename, "")
+        } else {
+            split_media_extension(filename)
+        };
+        let folder = |path: Option<&Path>| {
+            path.and_then(|p| p.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        let parent = path.parent();
+        let info = self
+            .parser
+            .parse(
+                stem,
+                &folder(parent),
+                &folder(parent.and_then(Path::parent)),
+            )
+            .ok_or_else(|| anyhow!("Keine Folge und kein Film mit Jahr erkannt"))?;
+
+        let scheme = if info.is_episode() {
+            &self.episode_scheme
+        } else {
+            &self.movie_scheme
+        };
+        Ok(scheme.render(&info, ext))
+    }
+}
+
+/// Split off the extension (without the dot); subtitles keep their language
+/// code with it ("Show.S01E02.en.srt" -> "en.srt")
+fn split_media_extension(filename: &str) -> (&str, &str) {
+    let Some(dot) = filename.rfind('.').filter(|&pos| pos > 0) else {
+        return (filename, "");
+    };
+    let (stem, ext) = (&filename[..dot], &filename[dot + 1..]);
+    if SUBTITLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()) {
+        if let Some(lang) = stem.rfind('.').filter(|&pos| pos > 0) {
+            let code = &stem[lang + 1..];
+            if (2..=3).contains(&code.len()) && code.chars().all(|c| c.is_ascii_alphabetic()) {
+                return (&filename[..lang], &filename[lang + 1..]);
+            }
+        }
+    }
+    (stem, ext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPISODE: &str = "{show} - S{season:02}E{episode:02} - {title}.{ext}";
+    const MOVIE: &str = "{title} ({year}).{ext}";
+
+    fn rename(path: &str) -> Result<String> {
+        let path = Path::new(path);
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        MediaRenamer::new(EPISODE, MOVIE)
+            .unwrap()
+            .rename(&name, path, false)
+    }
+
+    #[test]
+    fn test_release_name_corpus() {
+        let corpus = [
+            (
+                "Show.Name.S01E02.Pilot.1080p.WEB-DL.x264-GROUP.mkv",
+                "Show Name - S01E02 - Pilot.mkv",
+            ),
+            ("show.name.s01e02.720p.hdtv.mkv", "Show Name - S01E02.mkv"),
+            (
+                "Show_Name_1x02_The_Title.avi",
+                "Show Name - S01E02 - The Title.avi",
+            ),
+            (
+                "Show Name - 3x10 - Finale.mp4",
+                "Show Name - S03E10 - Finale.mp4",
+            ),
+            (
+                "The.Office.US.S02E01E02.The.Dundies.mkv",
+                "The Office US - S02E01-E02 - The Dundies.mkv",
+            ),
+            ("Show.S01E01-02.mkv", "Show - S01E01-E02.mkv"),
+            (
+                "Doctor.Who.2005.S01E01.Rose.mkv",
+                "Doctor Who - S01E01 - Rose.mkv",
+            ),
+            (
+                "[SubGroup] Show.S02E05.Title.mkv",
+                "Show - S02E05 - Title.mkv",
+            ),
+            (
+                "Serie.S01E03.Der.Titel.German.DL.1080p.mkv",
+                "Serie - S01E03 - Der Titel.mkv",
+            ),
+            ("Show.Name.S01E02.en.srt", "Show Name - S01E02.en.srt"),
+            (
+                "Movie.Name.2019.1080p.BluRay.x264.mkv",
+                "Movie Name (2019).mkv",
+            ),
+            (
+                "Blade.Runner.2049.2017.2160p.UHD.mkv",
+                "Blade Runner 2049 (2017).mkv",
+            ),
+            (
+                "2001.A.Space.Odyssey.1968.mkv",
+                "2001 A Space Odyssey (1968).mkv",
+            ),
+            ("Some Movie (1999).avi", "Some Movie (1999).avi"),
+            // Season folders: episode number only, show from the folder above
+            (
+                "Breaking Bad/Season 2/03 - Bit by a Dead Bee.mkv",
+                "Breaking Bad - S02E03 - Bit by a Dead Bee.mkv",
+            ),
+            ("Dark/Staffel 1/Folge 4.mkv", "Dark - S01E04.mkv"),
+            // Name only in the folder
+            ("Show Name/S01E02.mkv", "Show Name - S01E02.mkv"),
+        ];
+        for (input, expected) in corpus {
+            assert_eq!(rename(input).unwrap(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_unrecognized_names() {
+        assert!(rename("holiday.jpg").is_err());
+        assert!(rename("notes 1080p.txt").is_err());
+        assert!(rename("1999.mkv").is_err());
+
+        // Folders keep no extension
+        let renamer = MediaRenamer::new(EPISODE, MOVIE).unwrap();
+        let folder = "Movie.Name.2019.1080p.BluRay";
+        assert_eq!(
+            renamer.rename(folder, Path::new(folder), true).unwrap(),
+            "Movie Name (2019)"
+        );
+    }
+
+    #[test]
+    fn test_release_tags() {
+        let info = MediaParser::new()
+            .parse("Show.S01E02.Title.2160p.WEB-DL.H.265-GRP", "", "")
+            .unwrap();
+        assert_eq!(info.resolution.as_deref(), Some("2160p"));
+        assert_eq!(info.source.as_deref(), Some("WEB-DL"));
+        assert_eq!(info.codec.as_deref(), Some("H.265"));
+        assert_eq!(info.title, "Title");
+
+        // Tags can be kept in the scheme
+        let scheme =
+            MediaScheme::parse("{show} S{season:02}E{episode:02} [{resolution} {codec}].{ext}")
+                .unwrap();
+        assert_eq!(scheme.render(&info, "mkv"), "Show S01E02 [2160p H.265].mkv");
+    }
+
+    #[test]
+    fn test_scheme_errors() {
+        assert!(MediaScheme::parse("{show").is_err());
+        assert!(MediaScheme::parse("{unknown}").is_err());
+        assert!(MediaScheme::parse("{season:x}").is_err());
+    }
+}
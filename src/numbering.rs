@@ -0,0 +1,261 @@
+use anyhow::{anyhow, Result};
+
+use std::ops::Range;
+
+use crate::app::{NumberFormat, NumberTarget};
+
+/// Format a counter value, decimal and hex are zero-padded to `width`
+pub fn format_counter(value: i64, format: NumberFormat, width: usize) -> Result<String> {
+    match format {
+        NumberFormat::Decimal => Ok(format!("{:0width$}", value, width = width)),
+        NumberFormat::Hex if value >= 0 => Ok(format!("{:0width$x}", value, width = width)),
+        NumberFormat::Alpha if value >= 1 => Ok(to_alpha(value as u64)),
+        NumberFormat::Roman if (1..=3999).contains(&value) => Ok(to_roman(value as u32)),
+        _ => Err(anyhow!(
+            "Zaehler {} ist als {} nicht darstellbar",
+            value,
+            format.display_name()
+        )),
+    }
+}
+
+/// Spreadsheet style letters: 1 -> a, 26 -> z, 27 -> aa
+fn to_alpha(mut value: u64) -> String {
+    let mut letters = Vec::new();
+    while value > 0 {
+        value -= 1;
+        letters.push((b'a' + (value % 26) as u8) as char);
+        value /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+/// Roman numerals from 1 to 3999
+fn to_roman(mut value: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut result = String::new();
+    for (amount, numeral) in NUMERALS {
+        while value >= amount {
+            result.push_str(numeral);
+            value -= amount;
+        }
+    }
+    result
+}
+
+/// Replace every run of # in the pattern with the counter, the run length is the width
+pub fn render_pattern(pattern: &str, value: i64, format: NumberFormat) -> Result<String> {
+    let mut result = String::new();
+    let mut hashes = 0;
+
+    for c in pattern.chars() {
+        if c == '#' {
+            hashes += 1;
+            continue;
+        }
+        if hashes > 0 {
+            result.push_str(&format_counter(value, format, hashes)?);
+            hashes = 0;
+        }
+        result.push(c);
+    }
+    if hashes > 0 {
+        result.push_str(&format_counter(value, format, hashes)?);
+    }
+
+    Ok(result)
+}
+
+/// Renumber the last number in a stem: add the offset and render it with the
+/// pattern ("" keeps the original width), e.g. "Folge 7" + 10 -> "Folge 17"
+pub fn renumber(stem: &str, pattern: &str, offset: i64, format: NumberFormat) -> Result<String> {
+    let end = stem
+        .rfind(|c: char| c.is_ascii_digit())
+        .map(|i| i + 1)
+        .ok_or_else(|| anyhow!("Keine Nummer im Namen"))?;
+    let start = stem[..end]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |i| i + 1);
+
+    let digits = &stem[start..end];
+    let value = digits
+        .parse::<i64>()
+        .map_err(|_| anyhow!("Nummer zu gross: {}", digits))?
+        + offset;
+
+    let number = if pattern.is_empty() {
+        format_counter(value, format, digits.len())?
+    } else {
+        render_pattern(pattern, value, format)?
+    };
+
+    Ok(format!("{}{}{}", &stem[..start], number, &stem[end..]))
+}
+
+/// Byte ranges of the selected digit runs in a stem
+fn number_runs(stem: &str, target: NumberTarget) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (i, c) in stem.char_indices() {
+        match (c.is_ascii_digit(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                runs.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        runs.push(s..stem.len());
+    }
+
+    match target {
+        NumberTarget::First => runs.into_iter().take(1).collect(),
+        NumberTarget::Last => runs.pop().into_iter().collect(),
+        NumberTarget::All => runs,
+    }
+}
+
+/// Digits of a number without leading zeros ("007" -> "7", "000" -> "0")
+fn significant_digits(digits: &str) -> &str {
+    let trimmed = digits.trim_start_matches('0');
+    if trimmed.is_empty() {
+        &digits[digits.len() - 1..]
+    } else {
+        trimmed
+    }
+}
+
+/// Width of the largest selected number in a batch of stems
+pub fn auto_pad_width<'a>(stems: impl IntoIterator<Item = &'a str>, target: NumberTarget) -> usize {
+    stems
+        .into_iter()
+        .flat_map(|stem| {
+            number_runs(stem, target)
+                .into_iter()
+                .map(move |run| significant_digits(&stem[run]).len())
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Zero-pad the selected numbers of a stem to the width, existing leading
+/// zeros are replaced and longer numbers are kept: "page7" -> "page007"
+pub fn pad_numbers(stem: &str, target: NumberTarget, width: usize) -> String {
+    let mut result = String::with_capacity(stem.len());
+    let mut last = 0;
+    for run in number_runs(stem, target) {
+        result.push_str(&stem[last..run.start]);
+        let digits = significant_digits(&stem[run.clone()]);
+        result.push_str(&format!("{:0>width$}", digits, width = width));
+        last = run.end;
+    }
+    result.push_str(&stem[last..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_counter() {
+        assert_eq!(format_counter(7, NumberFormat::Decimal, 3).unwrap(), "007");
+        assert_eq!(format_counter(-3, NumberFormat::Decimal, 3).unwrap(), "-03");
+        assert_eq!(format_counter(255, NumberFormat::Hex, 4).unwrap(), "00ff");
+        assert_eq!(format_counter(1, NumberFormat::Alpha, 3).unwrap(), "a");
+        assert_eq!(format_counter(26, NumberFormat::Alpha, 1).unwrap(), "z");
+        assert_eq!(format_counter(28, NumberFormat::Alpha, 1).unwrap(), "ab");
+        assert_eq!(format_counter(703, NumberFormat::Alpha, 1).unwrap(), "aaa");
+        assert_eq!(format_counter(4, NumberFormat::Roman, 1).unwrap(), "IV");
+        assert_eq!(
+            format_counter(1994, NumberFormat::Roman, 1).unwrap(),
+            "MCMXCIV"
+        );
+    }
+
+    #[test]
+    fn test_format_counter_out_of_range() {
+        assert!(format_counter(0, NumberFormat::Alpha, 1).is_err());
+        assert!(format_counter(0, NumberFormat::Roman, 1).is_err());
+        assert!(format_counter(4000, NumberFormat::Roman, 1).is_err());
+        assert!(format_counter(-1, NumberFormat::Hex, 1).is_err());
+    }
+
+    #[test]
+    fn test_render_pattern() {
+        assert_eq!(
+            render_pattern("img_###", 5, NumberFormat::Decimal).unwrap(),
+            "img_005"
+        );
+        assert_eq!(
+            render_pattern("Teil #", 3, NumberFormat::Roman).unwrap(),
+            "Teil III"
+        );
+        assert_eq!(
+            render_pattern("##-x-#", 12, NumberFormat::Decimal).unwrap(),
+            "12-x-12"
+        );
+    }
+
+    #[test]
+    fn test_renumber() {
+        let decimal = NumberFormat::Decimal;
+        assert_eq!(renumber("IMG_7", "####", 0, decimal).unwrap(), "IMG_0007");
+        assert_eq!(renumber("IMG_0042", "", 10, decimal).unwrap(), "IMG_0052");
+        assert_eq!(
+            renumber("2024 Folge 9 (HD)", "", -4, decimal).unwrap(),
+            "2024 Folge 5 (HD)"
+        );
+        assert_eq!(
+            renumber("Kapitel 4", "#", 0, NumberFormat::Roman).unwrap(),
+            "Kapitel IV"
+        );
+        assert_eq!(renumber("15", "E##", 1, decimal).unwrap(), "E16");
+        assert!(renumber("ohne Nummer", "", 0, decimal).is_err());
+    }
+
+    #[test]
+    fn test_pad_numbers() {
+        assert_eq!(pad_numbers("page7", NumberTarget::Last, 3), "page007");
+        assert_eq!(pad_numbers("page0007", NumberTarget::Last, 3), "page007");
+        assert_eq!(pad_numbers("page1234", NumberTarget::Last, 3), "page1234");
+        assert_eq!(
+            pad_numbers("s1e2 take 3", NumberTarget::First, 2),
+            "s01e2 take 3"
+        );
+        assert_eq!(
+            pad_numbers("s1e2 take 3", NumberTarget::Last, 2),
+            "s1e2 take 03"
+        );
+        assert_eq!(
+            pad_numbers("s1e2 take 3", NumberTarget::All, 2),
+            "s01e02 take 03"
+        );
+        assert_eq!(pad_numbers("ohne", NumberTarget::All, 3), "ohne");
+    }
+
+    #[test]
+    fn test_auto_pad_width() {
+        let stems = ["page1", "page10", "page0100", "cover"];
+        assert_eq!(auto_pad_width(stems, NumberTarget::Last), 3);
+        assert_eq!(auto_pad_width(["a1b22", "a3b4"], NumberTarget::First), 1);
+        assert_eq!(auto_pad_width(["a1b22", "a3b4"], NumberTarget::All), 2);
+        assert_eq!(auto_pad_width(["cover"], NumberTarget::All), 0);
+    }
+}
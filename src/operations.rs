@@ -1,12 +1,28 @@
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
 use std::time::SystemTime;
 
 use anyhow::{anyhow, Result};
-use regex::Regex;
-
-use crate::app::{DatePosition, FileEntry, PrefixAction, RenameMode};
-use crate::config::{RenameHistory, RenameHistoryEntry, RenameOperation};
+use regex::{Regex, RegexBuilder};
+
+use crate::app::{
+    CounterGroup, DatePosition, DateSource, ExtensionAction, FileEntry, NumberFormat, PrefixAction,
+    RenameMode, RenameStep,
+};
+use crate::case::{convert_case, title_case, CaseStyle};
+use crate::config::{Config, RenameHistory, RenameHistoryEntry, RenameOperation, TitleCaseConfig};
+use crate::hash::HashCache;
+use crate::media::MediaRenamer;
+use crate::numbering::{auto_pad_width, pad_numbers, render_pattern, renumber};
+use crate::position::PositionEdit;
+use crate::replace::{replace_matches, Replacement};
+use crate::sanitize::{check_filename, sanitize_filename};
+use crate::script::{RenameScript, ScriptContext};
+use crate::sniff::{detect_file_type, FileType};
+use crate::template::{
+    format_date, format_local_time, validate_date_format, Template, TemplateContext,
+    DEFAULT_DATE_FORMAT,
+};
 
 /// Preview of a rename operation
 #[derive(Debug, Clone)]
@@ -17,24 +33,45 @@ pub struct RenamePreview {
     pub new_name: String,
     /// Whether this file will actually change
     pub will_change: bool,
+    /// Problem with this file (e.g. missing tags), the file is not renamed
+    pub error: Option<String>,
+    /// Content type that does not fit the current extension (type detection mode)
+    pub type_mismatch: Option<TypeMismatch>,
+    /// Rule of a rule set that renamed this file
+    pub rule: Option<String>,
     /// Index of the file in the original list
+    #[allow(dead_code)]
     pub file_index: usize,
 }
 
-/// Generate previews for all selected files based on mode and search/replace
+/// Detected content type of a file whose extension does not fit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeMismatch {
+    /// Name of the detected type (e.g. "JPEG")
+    pub detected: &'static str,
+    /// Current extension of the file, empty if it has none
+    pub extension: String,
+}
+
+impl TypeMismatch {
+    /// Short description for the preview, e.g. "JPEG, Endung: .png"
+    pub fn describe(&self) -> String {
+        if self.extension.is_empty() {
+            format!("{}, keine Endung", self.detected)
+        } else {
+            format!("{}, Endung: .{}", self.detected, self.extension)
+        }
+    }
+}
+
+/// Generate previews for all selected files by running the pipeline steps in order
 pub fn generate_previews(
     files: &[FileEntry],
     selected: &HashSet<usize>,
-    search: &str,
-    replace: &str,
-    mode: RenameMode,
-    prefix_action: PrefixAction,
-    number_start: usize,
-    number_step: usize,
-    date_position: DatePosition,
+    steps: &[RenameStep],
+    config: &Config,
+    hashes: &HashCache,
 ) -> Result<Vec<RenamePreview>> {
-    let mut previews = Vec::new();
-
     // If nothing is selected, preview all files
     let indices: Vec<usize> = if selected.is_empty() {
         (0..files.len()).collect()
@@ -44,99 +81,487 @@ pub fn generate_previews(
         v
     };
 
-    // Pre-compile regex if in regex mode
-    let regex = if mode == RenameMode::Regex && !search.is_empty() {
-        Some(Regex::new(search).map_err(|e| anyhow!("Ungueltiger Regex: {}", e))?)
-    } else {
-        None
-    };
+    // Directories only if enabled
+    let indices: Vec<usize> = indices
+        .into_iter()
+        .filter(|&index| {
+            files
+                .get(index)
+                .is_some_and(|file| config.include_dirs || !file.is_dir)
+        })
+        .collect();
+
+    // Intermediate names, each step works on the output of the previous one
+    let mut names: Vec<String> = indices.iter().map(|&i| files[i].name.clone()).collect();
+    // Files that failed in a step keep their name and skip the remaining steps
+    let mut errors: Vec<Option<String>> = vec![None; indices.len()];
+
+    // Same "now" for every file of this preview
+    let now = SystemTime::now();
+
+    // Only sniff file contents if a step detects types
+    let detects_types = steps.iter().any(|s| s.mode == RenameMode::FixExtension);
+    // Only organize mode may move files into subfolders
+    let organizes = moves_to_subdirs(steps);
+
+    for step in steps {
+        let mut prepared = PreparedStep::new(step, config, hashes, now)?;
+
+        // Automatic padding width: the largest number of the whole batch
+        if step.mode == RenameMode::PadNumbers && prepared.pad_width == 0 {
+            let stems = names
+                .iter()
+                .zip(&errors)
+                .filter(|(_, error)| error.is_none())
+                .map(|(name, _)| split_extension(name).0);
+            prepared.pad_width = auto_pad_width(stems, step.pad_target);
+        }
 
-    // Counter for numbering mode
-    let mut counter = number_start;
+        // Counters for numbering/template mode (every step counts on its own,
+        // files of a counter group share one counter)
+        let mut counters: HashMap<String, i64> = HashMap::new();
+
+        for ((name, error), &index) in names.iter_mut().zip(&mut errors).zip(&indices) {
+            let counter = counters
+                .entry(counter_key(name, &files[index], &prepared))
+                .or_insert(step.number_start);
+            if error.is_none() {
+                match apply_rename_mode(name, &prepared, *counter, &files[index]) {
+                    Ok(new_name) => *name = new_name,
+                    // Regex expressions fail like the pattern itself (shown as regex error)
+                    Err(e) if step.mode == RenameMode::Regex => {
+                        return Err(anyhow!("{} ({})", e, files[index].name))
+                    }
+                    Err(e) => *error = Some(e.to_string()),
+                }
+            }
+            *counter += step.number_step;
+        }
+    }
 
-    for index in indices {
-        if let Some(file) = files.get(index) {
-            // Skip directories for now
-            if file.is_dir {
-                continue;
+    let mut previews: Vec<RenamePreview> = indices
+        .into_iter()
+        .zip(names)
+        .zip(errors)
+        .map(|((index, new_name), mut error)| {
+            // Names of a tree keep their directory
+            let original_name = files[index].relative_name();
+            let new_name = files[index]
+                .subdir
+                .join(new_name)
+                .to_string_lossy()
+                .to_string();
+            // Targets execution would reject are shown as errors already
+            if error.is_none() && new_name != original_name {
+                error = target_error(&original_name, &new_name, organizes);
+            }
+            let new_name = if error.is_some() {
+                original_name.clone()
+            } else {
+                new_name
+            };
+            let type_mismatch = if detects_types {
+                detect_type_mismatch(&files[index])
+            } else {
+                None
+            };
+            RenamePreview {
+                will_change: new_name != original_name,
+                original_name,
+                new_name,
+                error,
+                type_mismatch,
+                rule: None,
+                file_index: index,
             }
+        })
+        .collect();
 
-            let new_name = apply_rename_mode(
-                &file.name,
-                search,
-                replace,
-                mode,
-                prefix_action,
-                regex.as_ref(),
-                counter,
-                date_position,
-                file.modified,
-            );
+    // Sort by original name for display
+    previews.sort_by(|a, b| a.original_name.cmp(&b.original_name));
 
-            let will_change = new_name != file.name;
+    Ok(previews)
+}
 
-            previews.push(RenamePreview {
-                original_name: file.name.clone(),
-                new_name,
-                will_change,
-                file_index: index,
-            });
+/// A pipeline step with its pattern compiled once before it runs
+struct PreparedStep<'a> {
+    step: &'a RenameStep,
+    regex: Option<Regex>,
+    /// Regex replacement with case escapes and expressions
+    replacement: Option<Replacement>,
+    template: Option<Template>,
+    /// Compiled script for script mode
+    script: Option<RenameScript>,
+    /// Release name parser and naming schemes for media mode
+    media: Option<MediaRenamer>,
+    position: Option<PositionEdit>,
+    /// Value added to existing numbers when renumbering
+    renumber_offset: i64,
+    /// Width for padding numbers (0 = not yet determined)
+    pad_width: usize,
+    /// Number of checksum characters to keep (0 = all)
+    hash_length: usize,
+    /// Checksums of the files for hash mode
+    hashes: &'a HashCache,
+    /// Date sources to try in order for date insertion
+    date_sources: Vec<DateSource>,
+    now: SystemTime,
+    /// Extension aliases for normalizing (jpeg -> jpg)
+    extension_aliases: &'a HashMap<String, String>,
+    /// Stop words and preserved words for title case
+    title_case: &'a TitleCaseConfig,
+}
+
+impl<'a> PreparedStep<'a> {
+    fn new(
+        step: &'a RenameStep,
+        config: &'a Config,
+        hashes: &'a HashCache,
+        now: SystemTime,
+    ) -> Result<Self> {
+        // Pre-compile regex if in regex mode
+        let regex = if step.mode == RenameMode::Regex && !step.search.is_empty() {
+            Some(
+                RegexBuilder::new(&step.search)
+                    .case_insensitive(step.regex_flags.ignore_case)
+                    .multi_line(step.regex_flags.multi_line)
+                    .build()
+                    .map_err(|e| anyhow!("Ungueltiger Regex: {}", e))?,
+            )
+        } else {
+            None
+        };
+        let replacement = if step.mode == RenameMode::Regex {
+            Some(Replacement::parse(&step.replace)?)
+        } else {
+            None
+        };
+
+        // Parse template if in template or audio tag mode
+        let uses_template = matches!(
+            step.mode,
+            RenameMode::Template | RenameMode::AudioTags | RenameMode::Organize
+        );
+        let template = if uses_template && !step.search.is_empty() {
+            Some(Template::parse(&step.search)?)
+        } else {
+            None
+        };
+
+        let script = if step.mode == RenameMode::Script && !step.search.is_empty() {
+            Some(RenameScript::load(&step.search)?)
+        } else {
+            None
+        };
+
+        // Empty schemes fall back to the ones from the config
+        let media = if step.mode == RenameMode::Media {
+            let or_config = |value: &'a str, default: &'a str| {
+                if value.is_empty() {
+                    default
+                } else {
+                    value
+                }
+            };
+            Some(MediaRenamer::new(
+                or_config(&step.search, &config.media.episode_format),
+                or_config(&step.replace, &config.media.movie_format),
+            )?)
+        } else {
+            None
+        };
+
+        if step.mode == RenameMode::DateInsert {
+            validate_date_format(&step.date_format)?;
+        }
+
+        let position = if step.mode == RenameMode::Position {
+            Some(PositionEdit::parse(
+                step.position_action,
+                &step.search,
+                &step.replace,
+            )?)
+        } else {
+            None
+        };
+
+        let renumber_offset = if step.mode == RenameMode::Numbering && step.renumber {
+            if !step.search.is_empty() && !step.search.contains('#') {
+                return Err(anyhow!("Das Muster braucht # fuer die Nummer (z.B. ###)"));
+            }
+            parse_offset(&step.replace)?
+        } else {
+            0
+        };
+
+        let pad_width = if step.mode == RenameMode::PadNumbers {
+            parse_pad_width(&step.search)?
+        } else {
+            0
+        };
 
-            // Increment counter for numbering mode
-            if mode == RenameMode::Numbering {
-                counter += number_step;
+        let hash_length = if step.mode == RenameMode::Hash {
+            parse_hash_length(&step.search)?
+        } else {
+            0
+        };
+
+        // Selected date source first, then the configured fallback chain
+        let mut date_sources = vec![step.date_source];
+        for &source in &config.date_fallback {
+            if !date_sources.contains(&source) {
+                date_sources.push(source);
             }
         }
+
+        Ok(Self {
+            step,
+            regex,
+            replacement,
+            template,
+            script,
+            media,
+            position,
+            renumber_offset,
+            pad_width,
+            hash_length,
+            hashes,
+            date_sources,
+            now,
+            extension_aliases: &config.extension_aliases,
+            title_case: &config.title_case,
+        })
     }
+}
 
-    // Sort by original name for display
-    previews.sort_by(|a, b| a.original_name.cmp(&b.original_name));
+/// Parse the renumber offset, e.g. "10", "+10" or "-5" (empty = 0)
+fn parse_offset(value: &str) -> Result<i64> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(0);
+    }
+    value
+        .parse()
+        .map_err(|_| anyhow!("Ungueltiger Versatz: '{}' (z.B. 10 oder -5)", value))
+}
 
-    Ok(previews)
+/// Parse the width for padding numbers, empty or "auto" = 0 (largest number of the batch)
+pub fn parse_pad_width(value: &str) -> Result<usize> {
+    let value = value.trim();
+    if value.is_empty() || value.eq_ignore_ascii_case("auto") {
+        return Ok(0);
+    }
+    match value.parse() {
+        Ok(width) if width > 0 => Ok(width),
+        _ => Err(anyhow!(
+            "Ungueltige Breite: '{}' (Zahl oder leer fuer automatisch)",
+            value
+        )),
+    }
+}
+
+/// Parse the checksum length, empty = full checksum
+pub fn parse_hash_length(value: &str) -> Result<usize> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(0);
+    }
+    match value.parse() {
+        Ok(length) if length > 0 => Ok(length),
+        _ => Err(anyhow!(
+            "Ungueltige Laenge: '{}' (Zahl oder leer fuer die ganze Pruefsumme)",
+            value
+        )),
+    }
+}
+
+/// Key of the counter a file counts with, files with the same key share a counter
+fn counter_key(name: &str, file: &FileEntry, prepared: &PreparedStep) -> String {
+    match prepared.step.number_group {
+        CounterGroup::All => String::new(),
+        CounterGroup::Extension => split_extension(name).1.to_lowercase(),
+        CounterGroup::Directory => file
+            .path
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        CounterGroup::Day => resolve_date(file, &prepared.date_sources, prepared.now)
+            .map(|date| format_local_time(date, "%Y-%m-%d"))
+            .unwrap_or_default(),
+    }
 }
 
-/// Apply the rename mode to a filename
+/// Apply the rename mode of a single step to a filename.
+/// Errors only concern this file and are shown in its preview.
 fn apply_rename_mode(
     filename: &str,
-    search: &str,
-    replace: &str,
-    mode: RenameMode,
-    prefix_action: PrefixAction,
-    regex: Option<&Regex>,
-    counter: usize,
-    date_position: DatePosition,
-    modified: Option<SystemTime>,
-) -> String {
-    match mode {
+    prepared: &PreparedStep,
+    counter: i64,
+    file: &FileEntry,
+) -> Result<String> {
+    let step = prepared.step;
+    let search = step.search.as_str();
+    let new_name = match step.mode {
         RenameMode::SearchReplace => {
             if search.is_empty() {
                 filename.to_string()
             } else {
-                filename.replace(search, replace)
+                filename.replace(search, &step.replace)
             }
         }
-        RenameMode::Regex => {
-            if let Some(re) = regex {
-                re.replace_all(filename, replace).to_string()
-            } else {
-                filename.to_string()
+        RenameMode::Regex => match (&prepared.regex, &prepared.replacement) {
+            (Some(re), Some(replacement)) => {
+                replace_matches(re, filename, replacement, &step.regex_flags)?
             }
+            _ => filename.to_string(),
+        },
+        RenameMode::Numbering if step.renumber => {
+            let (stem, ext) = split_extension(filename);
+            let stem = renumber(stem, search, prepared.renumber_offset, step.number_format)?;
+            format!("{}{}", stem, ext)
         }
-        RenameMode::Numbering => apply_numbering(filename, search, counter),
-        RenameMode::Prefix => apply_prefix(filename, search, prefix_action),
-        RenameMode::Suffix => apply_suffix(filename, search, prefix_action),
-        RenameMode::DateInsert => apply_date_insert(filename, date_position, modified),
+        RenameMode::Numbering => apply_numbering(filename, search, counter, step.number_format)?,
+        RenameMode::PadNumbers => {
+            let (stem, ext) = split_extension(filename);
+            format!(
+                "{}{}",
+                pad_numbers(stem, step.pad_target, prepared.pad_width),
+                ext
+            )
+        }
+        RenameMode::Prefix => apply_prefix(filename, search, step.prefix_action),
+        RenameMode::Suffix => apply_suffix(filename, search, step.prefix_action),
+        RenameMode::Position => match &prepared.position {
+            Some(edit) => {
+                let (name, ext) = split_extension(filename);
+                format!("{}{}", edit.apply(name), ext)
+            }
+            None => filename.to_string(),
+        },
+        RenameMode::DateInsert => {
+            let date = resolve_date(file, &prepared.date_sources, prepared.now);
+            apply_date_insert(
+                filename,
+                step.date_position,
+                date,
+                &step.date_format,
+                &step.date_separator,
+            )
+        }
+        RenameMode::Template | RenameMode::AudioTags => match &prepared.template {
+            Some(template) => render_template(template, filename, counter, file)?,
+            None => filename.to_string(),
+        },
+        // Empty values must not move the file a level up
+        RenameMode::Organize => match &prepared.template {
+            Some(template) => render_template(template, filename, counter, file)?
+                .split('/')
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("/"),
+            None => filename.to_string(),
+        },
+        RenameMode::Media => match &prepared.media {
+            Some(media) => media.rename(filename, &file.path, file.is_dir)?,
+            None => filename.to_string(),
+        },
+        RenameMode::Script => match &prepared.script {
+            Some(script) => {
+                let (stem, ext) = split_extension(filename);
+                script.run(&ScriptContext {
+                    name: filename,
+                    stem,
+                    ext: ext.strip_prefix('.').unwrap_or(ext),
+                    size: file.size,
+                    modified: file.modified,
+                    counter,
+                })?
+            }
+            None => filename.to_string(),
+        },
+        // Directories have no extension to change
+        RenameMode::Extension if file.is_dir => filename.to_string(),
+        RenameMode::Extension => apply_extension(
+            filename,
+            step.extension_action,
+            search,
+            prepared.extension_aliases,
+        ),
+        RenameMode::FixExtension => match detect_file_type(&file.path) {
+            Some(file_type) => fix_extension(filename, file_type),
+            None => filename.to_string(),
+        },
+        RenameMode::Hash if file.is_dir => return Err(anyhow!("Ordner haben keine Pruefsumme")),
+        RenameMode::Hash => match prepared.hashes.get(&file.path, step.hash_algorithm) {
+            Some(Ok(hash)) => apply_hash(
+                filename,
+                hash,
+                prepared.hash_length,
+                step.hash_position,
+                &step.replace,
+            ),
+            Some(Err(e)) => return Err(anyhow!("{}", e)),
+            None => return Err(anyhow!("Pruefsumme wird berechnet...")),
+        },
+        RenameMode::Sanitize => sanitize_filename(filename, step.sanitize_profile),
         RenameMode::Uppercase => to_uppercase_preserve_extension(filename),
         RenameMode::Lowercase => to_lowercase_preserve_extension(filename),
-        RenameMode::TitleCase => to_titlecase_preserve_extension(filename),
+        RenameMode::TitleCase => to_titlecase_preserve_extension(filename, prepared.title_case),
+        RenameMode::SnakeCase => apply_case_style(filename, CaseStyle::Snake),
+        RenameMode::KebabCase => apply_case_style(filename, CaseStyle::Kebab),
+        RenameMode::CamelCase => apply_case_style(filename, CaseStyle::Camel),
+        RenameMode::PascalCase => apply_case_style(filename, CaseStyle::Pascal),
+        RenameMode::DotCase => apply_case_style(filename, CaseStyle::Dot),
+        RenameMode::SentenceCase => apply_case_style(filename, CaseStyle::Sentence),
+    };
+    Ok(new_name)
+}
+
+/// Render a template for a file, reading its audio tags if the template needs them
+fn render_template(
+    template: &Template,
+    filename: &str,
+    counter: i64,
+    file: &FileEntry,
+) -> Result<String> {
+    let parent = file
+        .path
+        .parent()
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tags = if template.uses_tags() {
+        crate::tags::read_tags(&file.path)
+    } else {
+        None
+    };
+
+    let ctx = TemplateContext {
+        name: filename,
+        counter,
+        parent: &parent,
+        size: file.size,
+        modified: file.modified,
+        tags: tags.as_ref(),
+    };
+
+    let missing = template.missing_tags(&ctx);
+    if !missing.is_empty() {
+        return Err(anyhow!("Fehlende Tags: {}", missing.join(", ")));
     }
+    Ok(template.render(&ctx))
 }
 
 /// Apply numbering pattern to filename
 /// Pattern uses # for digits: file_### -> file_001, file_002, etc.
-fn apply_numbering(filename: &str, pattern: &str, counter: usize) -> String {
+fn apply_numbering(
+    filename: &str,
+    pattern: &str,
+    counter: i64,
+    format: NumberFormat,
+) -> Result<String> {
     if pattern.is_empty() {
-        return filename.to_string();
+        return Ok(filename.to_string());
     }
 
     // Find the extension of the original file
@@ -146,44 +571,12 @@ fn apply_numbering(filename: &str, pattern: &str, counter: usize) -> String {
         ""
     };
 
-    // Count consecutive # characters to determine padding
-    let hash_count = pattern.chars().filter(|&c| c == '#').count();
-
-    if hash_count == 0 {
-        // No # in pattern, just use pattern as-is with extension
-        return format!("{}{}", pattern, extension);
-    }
-
-    // Replace # sequence with padded number
-    let mut result = String::new();
-    let mut in_hash_sequence = false;
-    let mut hash_start = 0;
-
-    for (i, c) in pattern.chars().enumerate() {
-        if c == '#' {
-            if !in_hash_sequence {
-                in_hash_sequence = true;
-                hash_start = i;
-            }
-        } else {
-            if in_hash_sequence {
-                // End of hash sequence, insert padded number
-                let padding = i - hash_start;
-                result.push_str(&format!("{:0>width$}", counter, width = padding));
-                in_hash_sequence = false;
-            }
-            result.push(c);
-        }
-    }
-
-    // Handle trailing hash sequence
-    if in_hash_sequence {
-        let padding = pattern.len() - hash_start;
-        result.push_str(&format!("{:0>width$}", counter, width = padding));
-    }
-
-    // Add extension
-    format!("{}{}", result, extension)
+    // Replace # sequences with the padded counter
+    Ok(format!(
+        "{}{}",
+        render_pattern(pattern, counter, format)?,
+        extension
+    ))
 }
 
 /// Apply prefix to filename
@@ -194,13 +587,10 @@ fn apply_prefix(filename: &str, prefix: &str, action: PrefixAction) -> String {
 
     match action {
         PrefixAction::Add => format!("{}{}", prefix, filename),
-        PrefixAction::Remove => {
-            if filename.starts_with(prefix) {
-                filename[prefix.len()..].to_string()
-            } else {
-                filename.to_string()
-            }
-        }
+        PrefixAction::Remove => match filename.strip_prefix(prefix) {
+            Some(rest) => rest.to_string(),
+            None => filename.to_string(),
+        },
     }
 }
 
@@ -219,85 +609,54 @@ fn apply_suffix(filename: &str, suffix: &str, action: PrefixAction) -> String {
 
     match action {
         PrefixAction::Add => format!("{}{}{}", name, suffix, ext),
-        PrefixAction::Remove => {
-            if name.ends_with(suffix) {
-                format!("{}{}", &name[..name.len() - suffix.len()], ext)
-            } else {
-                filename.to_string()
-            }
-        }
+        PrefixAction::Remove => match name.strip_suffix(suffix) {
+            Some(rest) => format!("{}{}", rest, ext),
+            None => filename.to_string(),
+        },
     }
 }
 
-/// Format SystemTime as YYYYMMDD string
-fn format_date(time: SystemTime) -> String {
-    use std::time::UNIX_EPOCH;
-
-    // Calculate date from SystemTime
-    let duration = time.duration_since(UNIX_EPOCH).unwrap_or_default();
-    let secs = duration.as_secs();
-
-    // Simple date calculation (not accounting for leap seconds, but good enough)
-    let days = secs / 86400;
-
-    // Calculate year, month, day
-    let mut year = 1970;
-    let mut remaining_days = days as i64;
-
-    loop {
-        let days_in_year = if is_leap_year(year) { 366 } else { 365 };
-        if remaining_days < days_in_year {
-            break;
-        }
-        remaining_days -= days_in_year;
-        year += 1;
-    }
-
-    let is_leap = is_leap_year(year);
-    let days_in_months: [i64; 12] = [
-        31,
-        if is_leap { 29 } else { 28 },
-        31,
-        30,
-        31,
-        30,
-        31,
-        31,
-        30,
-        31,
-        30,
-        31,
-    ];
-
-    let mut month = 1;
-    for days_in_month in days_in_months {
-        if remaining_days < days_in_month {
-            break;
-        }
-        remaining_days -= days_in_month;
-        month += 1;
-    }
+/// Get the date of a file from the first source that has one
+fn resolve_date(file: &FileEntry, sources: &[DateSource], now: SystemTime) -> Option<SystemTime> {
+    sources.iter().find_map(|source| match source {
+        DateSource::Modified => file.modified,
+        DateSource::Changed => changed_time(&file.path),
+        DateSource::Created => std::fs::metadata(&file.path).ok()?.created().ok(),
+        DateSource::Exif => crate::exif::read_capture_date(&file.path),
+        DateSource::Now => Some(now),
+    })
+}
 
-    let day = remaining_days + 1;
+/// Status change time (ctime) of a file
+#[cfg(unix)]
+fn changed_time(path: &Path) -> Option<SystemTime> {
+    use std::os::unix::fs::MetadataExt;
+    use std::time::{Duration, UNIX_EPOCH};
 
-    format!("{:04}{:02}{:02}", year, month, day)
+    let secs = u64::try_from(std::fs::metadata(path).ok()?.ctime()).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
 }
 
-/// Check if a year is a leap year
-fn is_leap_year(year: i64) -> bool {
-    (year % 4 == 0 && year % 100 != 0) || (year % 400 == 0)
+/// Status change time is not available on this platform
+#[cfg(not(unix))]
+fn changed_time(_path: &Path) -> Option<SystemTime> {
+    None
 }
 
 /// Apply date insertion to filename
 fn apply_date_insert(
     filename: &str,
     position: DatePosition,
-    modified: Option<SystemTime>,
+    date: Option<SystemTime>,
+    format: &str,
+    separator: &str,
 ) -> String {
-    let date_str = match modified {
-        Some(time) => format_date(time),
-        None => "00000000".to_string(), // Fallback if no date available
+    let format = if format.is_empty() {
+        DEFAULT_DATE_FORMAT
+    } else {
+        format
     };
+    let date_str = format_date(date, format);
 
     // Split filename and extension
     let (name, ext) = if let Some(dot_pos) = filename.rfind('.') {
@@ -307,20 +666,124 @@ fn apply_date_insert(
     };
 
     match position {
-        DatePosition::Prefix => format!("{}_{}{}", date_str, name, ext),
-        DatePosition::Suffix => format!("{}_{}{}", name, date_str, ext),
+        DatePosition::Prefix => format!("{}{}{}{}", date_str, separator, name, ext),
+        DatePosition::Suffix => format!("{}{}{}{}", name, separator, date_str, ext),
         DatePosition::Replace => format!("{}{}", date_str, ext),
     }
 }
 
-/// Convert filename to uppercase, preserving extension case optionally
-fn to_uppercase_preserve_extension(filename: &str) -> String {
-    if let Some(dot_pos) = filename.rfind('.') {
-        let (name, ext) = filename.split_at(dot_pos);
-        format!("{}{}", name.to_uppercase(), ext.to_lowercase())
-    } else {
-        filename.to_uppercase()
+/// Insert a checksum (shortened to `length` characters, 0 = all) into a filename
+fn apply_hash(
+    filename: &str,
+    hash: &str,
+    length: usize,
+    position: DatePosition,
+    separator: &str,
+) -> String {
+    let hash = match hash.get(..length) {
+        Some(short) if length > 0 => short,
+        _ => hash,
+    };
+    let (name, ext) = split_extension(filename);
+
+    match position {
+        DatePosition::Prefix => format!("{}{}{}{}", hash, separator, name, ext),
+        DatePosition::Suffix => format!("{}{}{}{}", name, separator, hash, ext),
+        DatePosition::Replace => format!("{}{}", hash, ext),
+    }
+}
+
+/// Compression suffixes that form a multi-part extension after ".tar"
+const TAR_COMPRESSIONS: &[&str] = &["gz", "bz2", "xz", "zst", "lz", "lzma", "z"];
+
+/// Split a filename into stem and extension (including the dot).
+/// Multi-part extensions like ".tar.gz" are kept together.
+fn split_extension(filename: &str) -> (&str, &str) {
+    let Some(dot_pos) = filename.rfind('.').filter(|&pos| pos > 0) else {
+        return (filename, "");
+    };
+
+    let (stem, ext) = filename.split_at(dot_pos);
+    if TAR_COMPRESSIONS.contains(&ext[1..].to_lowercase().as_str()) {
+        if let Some(tar_pos) = stem.rfind('.').filter(|&pos| pos > 0) {
+            if stem[tar_pos + 1..].eq_ignore_ascii_case("tar") {
+                return filename.split_at(tar_pos);
+            }
+        }
+    }
+    (stem, ext)
+}
+
+/// Change the extension of a filename
+fn apply_extension(
+    filename: &str,
+    action: ExtensionAction,
+    value: &str,
+    aliases: &HashMap<String, String>,
+) -> String {
+    let (name, ext) = split_extension(filename);
+    let value = value.trim().trim_start_matches('.');
+
+    match action {
+        ExtensionAction::Set if value.is_empty() => filename.to_string(),
+        ExtensionAction::Set => format!("{}.{}", name, value),
+        ExtensionAction::Lowercase => format!("{}{}", name, ext.to_lowercase()),
+        ExtensionAction::Normalize => {
+            if ext.is_empty() {
+                return filename.to_string();
+            }
+            let ext = ext[1..].to_lowercase();
+            // Whole extension first (e.g. "tar.gz"), then each part
+            let normalized = match aliases.get(&ext) {
+                Some(alias) => alias.clone(),
+                None => ext
+                    .split('.')
+                    .map(|part| aliases.get(part).map(String::as_str).unwrap_or(part))
+                    .collect::<Vec<_>>()
+                    .join("."),
+            };
+            format!("{}.{}", name, normalized)
+        }
+        ExtensionAction::Remove => name.to_string(),
+        ExtensionAction::Add => {
+            let already_there = ext
+                .strip_prefix('.')
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(value));
+            if value.is_empty() || already_there {
+                filename.to_string()
+            } else {
+                format!("{}.{}", filename, value)
+            }
+        }
+    }
+}
+
+/// Replace or add the extension if it does not fit the detected content type
+fn fix_extension(filename: &str, file_type: &FileType) -> String {
+    let (name, ext) = split_extension(filename);
+    match ext.strip_prefix('.') {
+        Some(ext) if file_type.matches(ext) => filename.to_string(),
+        Some(_) => format!("{}.{}", name, file_type.extension),
+        None => format!("{}.{}", filename, file_type.extension),
+    }
+}
+
+/// Check the content type of a file against its current extension
+fn detect_type_mismatch(file: &FileEntry) -> Option<TypeMismatch> {
+    let file_type = detect_file_type(&file.path)?;
+    if file_type.matches(&file.extension) {
+        return None;
     }
+    Some(TypeMismatch {
+        detected: file_type.name,
+        extension: file.extension.clone(),
+    })
+}
+
+/// Convert filename to uppercase, the extension is left as it is
+fn to_uppercase_preserve_extension(filename: &str) -> String {
+    let (name, ext) = split_extension(filename);
+    format!("{}{}", name.to_uppercase(), ext)
 }
 
 /// Convert filename to lowercase
@@ -329,52 +792,62 @@ fn to_lowercase_preserve_extension(filename: &str) -> String {
 }
 
 /// Convert filename to title case
-fn to_titlecase_preserve_extension(filename: &str) -> String {
-    if let Some(dot_pos) = filename.rfind('.') {
-        let (name, ext) = filename.split_at(dot_pos);
-        format!("{}{}", to_titlecase(name), ext.to_lowercase())
-    } else {
-        to_titlecase(filename)
-    }
+fn to_titlecase_preserve_extension(filename: &str, rules: &TitleCaseConfig) -> String {
+    let (name, ext) = split_extension(filename);
+    format!("{}{}", title_case(name, rules), ext.to_lowercase())
 }
 
-/// Convert a string to title case
-fn to_titlecase(s: &str) -> String {
-    let mut result = String::with_capacity(s.len());
-    let mut capitalize_next = true;
-
-    for c in s.chars() {
-        if c.is_whitespace() || c == '_' || c == '-' {
-            result.push(c);
-            capitalize_next = true;
-        } else if capitalize_next {
-            result.extend(c.to_uppercase());
-            capitalize_next = false;
-        } else {
-            result.extend(c.to_lowercase());
-        }
+/// Convert the name to a case style, keeping leading dots and the extension
+fn apply_case_style(filename: &str, style: CaseStyle) -> String {
+    let (name, ext) = split_extension(filename);
+    let stem = name.trim_start_matches('.');
+    let hidden = &name[..name.len() - stem.len()];
+    match convert_case(stem, style) {
+        Some(converted) => format!("{}{}{}", hidden, converted, ext.to_lowercase()),
+        None => filename.to_string(),
     }
+}
 
-    result
+/// Whether a pipeline may move files into subfolders (only organize mode does)
+pub fn moves_to_subdirs<'a>(steps: impl IntoIterator<Item = &'a RenameStep>) -> bool {
+    steps
+        .into_iter()
+        .any(|step| step.mode == RenameMode::Organize)
 }
 
 /// Execute the actual rename operations and record history
-pub fn execute_renames(previews: &[RenamePreview], directory: &PathBuf) -> Result<usize> {
-    execute_renames_with_history(previews, directory, Some("Umbenennung"))
+pub fn execute_renames(
+    previews: &[RenamePreview],
+    directory: &Path,
+    allow_subdirs: bool,
+) -> Result<usize> {
+    execute_renames_with_history(previews, directory, Some("Umbenennung"), allow_subdirs)
 }
 
-/// Execute the actual rename operations with optional history recording
+/// Execute the actual rename operations with optional history recording.
+/// Without `allow_subdirs` every file has to stay in its folder.
 pub fn execute_renames_with_history(
     previews: &[RenamePreview],
-    directory: &PathBuf,
+    directory: &Path,
     description: Option<&str>,
+    allow_subdirs: bool,
 ) -> Result<usize> {
     let mut renamed_count = 0;
     let mut errors = Vec::new();
     let mut history_entries = Vec::new();
 
+    // Files inside a directory are renamed before the directory itself, so the
+    // paths of a tree stay valid until their turn
+    let mut changes: Vec<&RenamePreview> = previews.iter().filter(|p| p.will_change).collect();
+    changes.sort_by_key(|preview| {
+        std::cmp::Reverse(Path::new(&preview.original_name).components().count())
+    });
+
+    // Targets already taken by another file of this batch
+    let mut targets = HashSet::new();
+
     // First, validate all operations
-    for preview in previews.iter().filter(|p| p.will_change) {
+    for preview in &changes {
         let old_path = directory.join(&preview.original_name);
         let new_path = directory.join(&preview.new_name);
 
@@ -398,15 +871,30 @@ pub fn execute_renames_with_history(
             }
         }
 
-        // Check for invalid characters in new name
-        if preview.new_name.contains('/') || preview.new_name.contains('\\') {
-            errors.push(format!("Ungueltiger Dateiname: {}", preview.new_name));
+        // Two files with the same new name would overwrite each other
+        if !targets.insert(preview.new_name.as_str()) {
+            errors.push(format!("Zielname mehrfach vergeben: {}", preview.new_name));
+            continue;
+        }
+
+        if let Some(error) = target_error(&preview.original_name, &preview.new_name, allow_subdirs)
+        {
+            errors.push(error);
             continue;
         }
 
-        // Check for empty filename
-        if preview.new_name.is_empty() {
-            errors.push("Leerer Dateiname ist nicht erlaubt".to_string());
+        // Folders of the target are created, they must not exist as files
+        if let Some(file) = new_path
+            .ancestors()
+            .skip(1)
+            .take_while(|path| *path != directory)
+            .find(|path| path.exists() && !path.is_dir())
+        {
+            errors.push(format!(
+                "Kein Ordner: {} (fuer {})",
+                file.strip_prefix(directory).unwrap_or(file).display(),
+                preview.new_name
+            ));
             continue;
         }
     }
@@ -416,10 +904,19 @@ pub fn execute_renames_with_history(
     }
 
     // Execute renames
-    for preview in previews.iter().filter(|p| p.will_change) {
+    let mut created_dirs = Vec::new();
+    for preview in changes {
         let old_path = directory.join(&preview.original_name);
         let new_path = directory.join(&preview.new_name);
 
+        if let Err(e) = create_target_dirs(directory, &preview.new_name, &mut created_dirs) {
+            return Err(anyhow!(
+                "Fehler beim Anlegen der Ordner fuer '{}': {}",
+                preview.new_name,
+                e
+            ));
+        }
+
         match std::fs::rename(&old_path, &new_path) {
             Ok(_) => {
                 renamed_count += 1;
@@ -443,11 +940,12 @@ pub fn execute_renames_with_history(
     // Save to history if we renamed any files
     if !history_entries.is_empty() && description.is_some() {
         if let Ok(mut history) = RenameHistory::load() {
-            let operation = RenameOperation::new(
-                directory.clone(),
+            let mut operation = RenameOperation::new(
+                directory.to_path_buf(),
                 history_entries,
                 description.unwrap_or("Umbenennung").to_string(),
             );
+            operation.created_dirs = created_dirs;
             history.add_operation(operation);
             let _ = history.save(); // Ignore save errors to not break the main operation
         }
@@ -456,6 +954,59 @@ pub fn execute_renames_with_history(
     Ok(renamed_count)
 }
 
+/// Check the new name of a file, returns the error if it can't be renamed.
+/// Invalid characters, reserved names and length are checked for every folder
+/// of the target too; only organize mode (`allow_subdirs`) moves files.
+pub fn target_error(original_name: &str, new_name: &str, allow_subdirs: bool) -> Option<String> {
+    if new_name.is_empty() {
+        return Some("Leerer Dateiname ist nicht erlaubt".to_string());
+    }
+    let reason = check_target(new_name).or_else(|| {
+        let moves = Path::new(new_name).parent() != Path::new(original_name).parent();
+        (moves && !allow_subdirs).then_some("Unterordner nur im Ordnen-Modus")
+    })?;
+    Some(format!("Ungueltiger Dateiname: {} ({})", new_name, reason))
+}
+
+/// Check a new name relative to the directory, returns the reason if it is invalid.
+/// Subfolders are allowed, but every part must be a valid name.
+fn check_target(new_name: &str) -> Option<&'static str> {
+    if new_name.ends_with('/') {
+        return Some("endet mit /");
+    }
+    for component in Path::new(new_name).components() {
+        match component {
+            Component::Normal(part) => {
+                if let Some(reason) = check_filename(&part.to_string_lossy()) {
+                    return Some(reason);
+                }
+            }
+            _ => return Some("ausserhalb des Verzeichnisses"),
+        }
+    }
+    None
+}
+
+/// Create the missing folders of a target, parents first, and remember them
+fn create_target_dirs(
+    directory: &Path,
+    new_name: &str,
+    created: &mut Vec<String>,
+) -> std::io::Result<()> {
+    let Some(parent) = Path::new(new_name).parent() else {
+        return Ok(());
+    };
+    let mut path = PathBuf::new();
+    for component in parent.components() {
+        path.push(component);
+        if !directory.join(&path).exists() {
+            std::fs::create_dir(directory.join(&path))?;
+            created.push(path.to_string_lossy().to_string());
+        }
+    }
+    Ok(())
+}
+
 /// Undo the last rename operation
 pub fn undo_last_rename() -> Result<(usize, PathBuf)> {
     let mut history = RenameHistory::load()?;
@@ -468,8 +1019,10 @@ pub fn undo_last_rename() -> Result<(usize, PathBuf)> {
     let mut undone_count = 0;
     let mut errors = Vec::new();
 
-    // Validate all undo operations first
-    for entry in &operation.entries {
+    // Undo in reverse order (new_name -> original_name): a directory gets its
+    // name back before the files inside it, whose paths use that name.
+    // Every entry is checked right before it is undone.
+    for entry in operation.entries.iter().rev() {
         let current_path = directory.join(&entry.new_name);
         let original_path = directory.join(&entry.original_name);
 
@@ -483,34 +1036,16 @@ pub fn undo_last_rename() -> Result<(usize, PathBuf)> {
         }
 
         // Check if original name is already taken by another file
-        if original_path.exists() && current_path != original_path {
-            if current_path.to_string_lossy().to_lowercase()
-                != original_path.to_string_lossy().to_lowercase()
-            {
-                errors.push(format!(
-                    "Urspruenglicher Name bereits vergeben: {} (uebersprungen)",
-                    entry.original_name
-                ));
-                continue;
-            }
-        }
-    }
-
-    // Execute undo renames (reverse: new_name -> original_name)
-    for entry in &operation.entries {
-        let current_path = directory.join(&entry.new_name);
-        let original_path = directory.join(&entry.original_name);
-
-        if !current_path.exists() {
-            continue; // Skip files that no longer exist
-        }
-
-        if original_path.exists() && current_path != original_path {
-            if current_path.to_string_lossy().to_lowercase()
+        if original_path.exists()
+            && current_path != original_path
+            && current_path.to_string_lossy().to_lowercase()
                 != original_path.to_string_lossy().to_lowercase()
-            {
-                continue; // Skip if original name is taken
-            }
+        {
+            errors.push(format!(
+                "Urspruenglicher Name bereits vergeben: {} (uebersprungen)",
+                entry.original_name
+            ));
+            continue;
         }
 
         match std::fs::rename(&current_path, &original_path) {
@@ -524,6 +1059,11 @@ pub fn undo_last_rename() -> Result<(usize, PathBuf)> {
         }
     }
 
+    // Folders created by the operation go away again once they are empty
+    for dir in operation.created_dirs.iter().rev() {
+        let _ = std::fs::remove_dir(directory.join(dir));
+    }
+
     // Save updated history (with operation removed)
     history.save()?;
 
@@ -534,8 +1074,11 @@ pub fn undo_last_rename() -> Result<(usize, PathBuf)> {
     Ok((undone_count, directory))
 }
 
+/// Description of the last operation and its (current, original) name pairs
+pub type UndoPreview = (String, Vec<(String, String)>);
+
 /// Get a preview of what undo would do
-pub fn get_undo_preview() -> Result<Option<(String, Vec<(String, String)>)>> {
+pub fn get_undo_preview() -> Result<Option<UndoPreview>> {
     let history = RenameHistory::load()?;
 
     if let Some(operation) = history.last_operation() {
@@ -554,6 +1097,18 @@ pub fn get_undo_preview() -> Result<Option<(String, Vec<(String, String)>)>> {
 pub fn print_previews(previews: &[RenamePreview]) {
     let changes: Vec<_> = previews.iter().filter(|p| p.will_change).collect();
 
+    let problems: Vec<_> = previews.iter().filter(|p| p.error.is_some()).collect();
+    if !problems.is_empty() {
+        println!("\nUebersprungen:");
+        for preview in &problems {
+            println!(
+                "  {} ! {}",
+                preview.original_name,
+                preview.error.as_deref().unwrap_or_default()
+            );
+        }
+    }
+
     if changes.is_empty() {
         println!("Keine Aenderungen.");
         return;
@@ -563,7 +1118,19 @@ pub fn print_previews(previews: &[RenamePreview]) {
     println!("{:-<60}", "");
 
     for preview in &changes {
-        println!("  {} -> {}", preview.original_name, preview.new_name);
+        match (&preview.type_mismatch, &preview.rule) {
+            (Some(mismatch), _) => println!(
+                "  {} -> {}  (erkannt: {})",
+                preview.original_name,
+                preview.new_name,
+                mismatch.describe()
+            ),
+            (None, Some(rule)) => println!(
+                "  {} -> {}  [{}]",
+                preview.original_name, preview.new_name, rule
+            ),
+            (None, None) => println!("  {} -> {}", preview.original_name, preview.new_name),
+        }
     }
 
     println!("{:-<60}", "");
@@ -573,11 +1140,15 @@ pub fn print_previews(previews: &[RenamePreview]) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app::HashAlgorithm;
+    use crate::app::{PositionAction, RegexFlags};
+    use crate::hash::HashCache;
 
     fn make_file(name: &str) -> FileEntry {
         FileEntry {
             path: PathBuf::from(name),
             name: name.to_string(),
+            subdir: PathBuf::new(),
             is_dir: false,
             size: 0,
             modified: None,
@@ -593,13 +1164,13 @@ mod tests {
         let previews = generate_previews(
             &files,
             &selected,
-            "",
-            "replacement",
-            RenameMode::SearchReplace,
-            PrefixAction::Add,
-            1,
-            1,
-            DatePosition::Prefix,
+            &[RenameStep::new(
+                RenameMode::SearchReplace,
+                "",
+                "replacement",
+            )],
+            &Config::default(),
+            &HashCache::default(),
         )
         .unwrap();
 
@@ -617,13 +1188,9 @@ mod tests {
         let previews = generate_previews(
             &files,
             &selected,
-            "image",
-            "photo",
-            RenameMode::SearchReplace,
-            PrefixAction::Add,
-            1,
-            1,
-            DatePosition::Prefix,
+            &[RenameStep::new(RenameMode::SearchReplace, "image", "photo")],
+            &Config::default(),
+            &HashCache::default(),
         )
         .unwrap();
 
@@ -642,13 +1209,9 @@ mod tests {
         let previews = generate_previews(
             &files,
             &selected,
-            "",
-            "",
-            RenameMode::Uppercase,
-            PrefixAction::Add,
-            1,
-            1,
-            DatePosition::Prefix,
+            &[RenameStep::new(RenameMode::Uppercase, "", "")],
+            &Config::default(),
+            &HashCache::default(),
         )
         .unwrap();
 
@@ -665,13 +1228,9 @@ mod tests {
         let previews = generate_previews(
             &files,
             &selected,
-            "",
-            "",
-            RenameMode::TitleCase,
-            PrefixAction::Add,
-            1,
-            1,
-            DatePosition::Prefix,
+            &[RenameStep::new(RenameMode::TitleCase, "", "")],
+            &Config::default(),
+            &HashCache::default(),
         )
         .unwrap();
 
@@ -688,13 +1247,9 @@ mod tests {
         let previews = generate_previews(
             &files,
             &selected,
-            r"IMG_(\d+)",
-            "photo_$1",
-            RenameMode::Regex,
-            PrefixAction::Add,
-            1,
-            1,
-            DatePosition::Prefix,
+            &[RenameStep::new(RenameMode::Regex, r"IMG_(\d+)", "photo_$1")],
+            &Config::default(),
+            &HashCache::default(),
         )
         .unwrap();
 
@@ -713,13 +1268,9 @@ mod tests {
         let result = generate_previews(
             &files,
             &selected,
-            r"[invalid",
-            "replace",
-            RenameMode::Regex,
-            PrefixAction::Add,
-            1,
-            1,
-            DatePosition::Prefix,
+            &[RenameStep::new(RenameMode::Regex, r"[invalid", "replace")],
+            &Config::default(),
+            &HashCache::default(),
         );
 
         assert!(result.is_err());
@@ -736,13 +1287,9 @@ mod tests {
         let previews = generate_previews(
             &files,
             &selected,
-            "photo_###",
-            "",
-            RenameMode::Numbering,
-            PrefixAction::Add,
-            1,
-            1,
-            DatePosition::Prefix,
+            &[RenameStep::new(RenameMode::Numbering, "photo_###", "")],
+            &Config::default(),
+            &HashCache::default(),
         )
         .unwrap();
 
@@ -755,10 +1302,10 @@ mod tests {
 
     #[test]
     fn test_numbering_with_padding() {
-        let result = apply_numbering("test.jpg", "file_####", 42);
+        let result = apply_numbering("test.jpg", "file_####", 42, NumberFormat::Decimal).unwrap();
         assert_eq!(result, "file_0042.jpg");
 
-        let result = apply_numbering("test.jpg", "img#", 5);
+        let result = apply_numbering("test.jpg", "img#", 5, NumberFormat::Decimal).unwrap();
         assert_eq!(result, "img5.jpg");
     }
 
@@ -770,13 +1317,9 @@ mod tests {
         let previews = generate_previews(
             &files,
             &selected,
-            "backup_",
-            "",
-            RenameMode::Prefix,
-            PrefixAction::Add,
-            1,
-            1,
-            DatePosition::Prefix,
+            &[RenameStep::new(RenameMode::Prefix, "backup_", "")],
+            &Config::default(),
+            &HashCache::default(),
         )
         .unwrap();
 
@@ -791,13 +1334,12 @@ mod tests {
         let previews = generate_previews(
             &files,
             &selected,
-            "backup_",
-            "",
-            RenameMode::Prefix,
-            PrefixAction::Remove,
-            1,
-            1,
-            DatePosition::Prefix,
+            &[RenameStep {
+                prefix_action: PrefixAction::Remove,
+                ..RenameStep::new(RenameMode::Prefix, "backup_", "")
+            }],
+            &Config::default(),
+            &HashCache::default(),
         )
         .unwrap();
 
@@ -812,13 +1354,9 @@ mod tests {
         let previews = generate_previews(
             &files,
             &selected,
-            "_backup",
-            "",
-            RenameMode::Suffix,
-            PrefixAction::Add,
-            1,
-            1,
-            DatePosition::Prefix,
+            &[RenameStep::new(RenameMode::Suffix, "_backup", "")],
+            &Config::default(),
+            &HashCache::default(),
         )
         .unwrap();
 
@@ -833,13 +1371,12 @@ mod tests {
         let previews = generate_previews(
             &files,
             &selected,
-            "_old",
-            "",
-            RenameMode::Suffix,
-            PrefixAction::Remove,
-            1,
-            1,
-            DatePosition::Prefix,
+            &[RenameStep {
+                prefix_action: PrefixAction::Remove,
+                ..RenameStep::new(RenameMode::Suffix, "_old", "")
+            }],
+            &Config::default(),
+            &HashCache::default(),
         )
         .unwrap();
 
@@ -847,39 +1384,345 @@ mod tests {
     }
 
     #[test]
-    fn test_date_insert_prefix() {
-        use std::time::{Duration, UNIX_EPOCH};
+    fn test_pipeline_applies_steps_in_order() {
+        let files = vec![make_file("My Holiday Photo.JPG")];
+        let selected = HashSet::new();
 
-        // November 30, 2024 = days since epoch
-        let days = 20058; // Approximate days to Nov 30, 2024
-        let time = UNIX_EPOCH + Duration::from_secs(days * 86400);
+        let previews = generate_previews(
+            &files,
+            &selected,
+            &[
+                RenameStep::new(RenameMode::Lowercase, "", ""),
+                RenameStep::new(RenameMode::SearchReplace, " ", "_"),
+                RenameStep::new(RenameMode::Prefix, "2024_", ""),
+            ],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
 
-        let result = apply_date_insert("photo.jpg", DatePosition::Prefix, Some(time));
-        assert!(result.starts_with("2024"));
-        assert!(result.ends_with("_photo.jpg"));
+        assert_eq!(previews.len(), 1);
+        assert!(previews[0].will_change);
+        assert_eq!(previews[0].new_name, "2024_my_holiday_photo.jpg");
     }
 
     #[test]
-    fn test_date_insert_suffix() {
-        use std::time::{Duration, UNIX_EPOCH};
+    fn test_pipeline_numbering_step_has_own_counter() {
+        let files = vec![make_file("a.jpg"), make_file("b.jpg")];
+        let selected = HashSet::new();
 
-        let days = 20058;
-        let time = UNIX_EPOCH + Duration::from_secs(days * 86400);
+        let previews = generate_previews(
+            &files,
+            &selected,
+            &[
+                RenameStep {
+                    number_start: 10,
+                    ..RenameStep::new(RenameMode::Numbering, "img_##", "")
+                },
+                RenameStep::new(RenameMode::Suffix, "_x", ""),
+            ],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
 
-        let result = apply_date_insert("photo.jpg", DatePosition::Suffix, Some(time));
-        assert!(result.starts_with("photo_"));
-        assert!(result.contains("2024"));
-        assert!(result.ends_with(".jpg"));
+        assert_eq!(previews[0].new_name, "img_10_x.jpg");
+        assert_eq!(previews[1].new_name, "img_11_x.jpg");
     }
 
     #[test]
-    fn test_date_insert_replace() {
-        use std::time::{Duration, UNIX_EPOCH};
+    fn test_numbering_formats_and_negative_step() {
+        let files = vec![make_file("a.jpg"), make_file("b.jpg"), make_file("c.jpg")];
+        let run = |step: RenameStep| {
+            generate_previews(
+                &files,
+                &HashSet::new(),
+                &[step],
+                &Config::default(),
+                &HashCache::default(),
+            )
+            .unwrap()
+            .into_iter()
+            .map(|p| p.new_name)
+            .collect::<Vec<_>>()
+        };
+
+        let roman = RenameStep {
+            number_format: NumberFormat::Roman,
+            ..RenameStep::new(RenameMode::Numbering, "Teil #", "")
+        };
+        assert_eq!(run(roman), ["Teil I.jpg", "Teil II.jpg", "Teil III.jpg"]);
+
+        let countdown = RenameStep {
+            number_start: 3,
+            number_step: -1,
+            ..RenameStep::new(RenameMode::Numbering, "img_##", "")
+        };
+        assert_eq!(run(countdown), ["img_03.jpg", "img_02.jpg", "img_01.jpg"]);
+    }
 
-        let days = 20058;
+    #[test]
+    fn test_numbering_counter_per_extension() {
+        let files = vec![
+            make_file("a.jpg"),
+            make_file("b.png"),
+            make_file("c.JPG"),
+            make_file("d.png"),
+        ];
+        let step = RenameStep {
+            number_group: CounterGroup::Extension,
+            ..RenameStep::new(RenameMode::Numbering, "img_#", "")
+        };
+
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+
+        let names: Vec<&str> = previews.iter().map(|p| p.new_name.as_str()).collect();
+        assert_eq!(names, ["img_1.jpg", "img_1.png", "img_2.JPG", "img_2.png"]);
+    }
+
+    #[test]
+    fn test_renumber_existing_numbers() {
+        let files = vec![make_file("Folge 3.mkv"), make_file("Trailer.mkv")];
+        let step = RenameStep {
+            renumber: true,
+            ..RenameStep::new(RenameMode::Numbering, "E##", "+10")
+        };
+
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+
+        assert_eq!(previews[0].new_name, "Folge E13.mkv");
+        assert!(previews[1].error.is_some());
+
+        let invalid = RenameStep {
+            renumber: true,
+            ..RenameStep::new(RenameMode::Numbering, "", "zehn")
+        };
+        assert!(generate_previews(
+            &files,
+            &HashSet::new(),
+            &[invalid],
+            &Config::default(),
+            &HashCache::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_pad_numbers_auto_width() {
+        let files = vec![
+            make_file("page1.tif"),
+            make_file("page10.tif"),
+            make_file("page100.tif"),
+            make_file("cover.mp3"),
+        ];
+        let step = RenameStep::new(RenameMode::PadNumbers, "", "");
+
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+
+        let names: Vec<&str> = previews.iter().map(|p| p.new_name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["cover.mp3", "page001.tif", "page010.tif", "page100.tif"]
+        );
+
+        let step = RenameStep::new(RenameMode::PadNumbers, "4", "");
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+        assert_eq!(previews[1].new_name, "page0001.tif");
+
+        let step = RenameStep::new(RenameMode::PadNumbers, "x", "");
+        assert!(generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_regex_flags() {
+        let files = vec![make_file("IMG_01_img_02.JPG")];
+        let step = RenameStep {
+            regex_flags: RegexFlags {
+                ignore_case: true,
+                occurrence: 2,
+                ..RegexFlags::default()
+            },
+            ..RenameStep::new(RenameMode::Regex, r"img_(\d+)", r"\Uphoto\E-$1")
+        };
+
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+
+        assert_eq!(previews[0].new_name, "IMG_01_PHOTO-02.JPG");
+    }
+
+    #[test]
+    fn test_regex_capture_arithmetic() {
+        let files = vec![make_file("ep7.mkv"), make_file("ep10.mkv")];
+        let step = RenameStep::new(RenameMode::Regex, r"ep(\d+)", "episode_{$1+12:03}");
+
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+
+        assert_eq!(previews[0].new_name, "episode_022.mkv");
+        assert_eq!(previews[1].new_name, "episode_019.mkv");
+
+        let step = RenameStep::new(RenameMode::Regex, r"(\w+)\.", "{$1+1}.");
+        let err = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("keine Zahl"));
+    }
+
+    #[test]
+    fn test_pipeline_invalid_regex_in_later_step() {
+        let files = vec![make_file("test.txt")];
+        let selected = HashSet::new();
+
+        let result = generate_previews(
+            &files,
+            &selected,
+            &[
+                RenameStep::new(RenameMode::Uppercase, "", ""),
+                RenameStep::new(RenameMode::Regex, "[invalid", ""),
+            ],
+            &Config::default(),
+            &HashCache::default(),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_template_mode() {
+        let mut files = vec![make_file("My Photo.JPG"), make_file("Other.JPG")];
+        files[0].path = PathBuf::from("/pictures/Urlaub/My Photo.JPG");
+        files[1].path = PathBuf::from("/pictures/Urlaub/Other.JPG");
+        let selected = HashSet::new();
+
+        let previews = generate_previews(
+            &files,
+            &selected,
+            &[RenameStep::new(
+                RenameMode::Template,
+                "{parent}_{stem|slug}_{n:03}.{ext|lower}",
+                "",
+            )],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+
+        assert_eq!(previews[0].new_name, "Urlaub_my-photo_001.jpg");
+        assert_eq!(previews[1].new_name, "Urlaub_other_002.jpg");
+    }
+
+    #[test]
+    fn test_template_invalid() {
+        let files = vec![make_file("test.txt")];
+        let selected = HashSet::new();
+
+        let result = generate_previews(
+            &files,
+            &selected,
+            &[RenameStep::new(RenameMode::Template, "{stem", "")],
+            &Config::default(),
+            &HashCache::default(),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_date_insert_prefix() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        // November 30, 2024 = days since epoch
+        let days = 20058; // Approximate days to Nov 30, 2024
+        let time = UNIX_EPOCH + Duration::from_secs(days * 86400);
+
+        let result =
+            apply_date_insert("photo.jpg", DatePosition::Prefix, Some(time), "%Y%m%d", "_");
+        assert!(result.starts_with("2024"));
+        assert!(result.ends_with("_photo.jpg"));
+    }
+
+    #[test]
+    fn test_date_insert_suffix() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let days = 20058;
         let time = UNIX_EPOCH + Duration::from_secs(days * 86400);
 
-        let result = apply_date_insert("photo.jpg", DatePosition::Replace, Some(time));
+        let result =
+            apply_date_insert("photo.jpg", DatePosition::Suffix, Some(time), "%Y%m%d", "_");
+        assert!(result.starts_with("photo_"));
+        assert!(result.contains("2024"));
+        assert!(result.ends_with(".jpg"));
+    }
+
+    #[test]
+    fn test_date_insert_replace() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let days = 20058;
+        let time = UNIX_EPOCH + Duration::from_secs(days * 86400);
+
+        let result = apply_date_insert(
+            "photo.jpg",
+            DatePosition::Replace,
+            Some(time),
+            "%Y%m%d",
+            "_",
+        );
         assert!(result.starts_with("2024"));
         assert!(result.ends_with(".jpg"));
         assert!(!result.contains("photo"));
@@ -887,7 +1730,710 @@ mod tests {
 
     #[test]
     fn test_date_insert_no_date() {
-        let result = apply_date_insert("photo.jpg", DatePosition::Prefix, None);
+        let result = apply_date_insert("photo.jpg", DatePosition::Prefix, None, "%Y%m%d", "_");
         assert_eq!(result, "00000000_photo.jpg");
     }
+
+    #[test]
+    fn test_date_insert_custom_format() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        // Noon UTC, so the local date is the same in every time zone
+        let time = UNIX_EPOCH + Duration::from_secs(20058 * 86400 + 12 * 3600);
+
+        let result = apply_date_insert(
+            "photo.jpg",
+            DatePosition::Prefix,
+            Some(time),
+            "%Y-%m-%d",
+            "--",
+        );
+        assert_eq!(result, "2024-12-01--photo.jpg");
+
+        let result = apply_date_insert("photo.jpg", DatePosition::Suffix, Some(time), "%Y", "");
+        assert_eq!(result, "photo2024.jpg");
+
+        let result = apply_date_insert("photo.jpg", DatePosition::Prefix, None, "%Y-%m-%d", "_");
+        assert_eq!(result, "0000-00-00_photo.jpg");
+    }
+
+    #[test]
+    fn test_date_insert_invalid_format() {
+        let files = vec![make_file("photo.jpg")];
+        let steps = [RenameStep {
+            date_format: "%Y-%Q".to_string(),
+            ..RenameStep::new(RenameMode::DateInsert, "", "")
+        }];
+
+        let result = generate_previews(
+            &files,
+            &HashSet::new(),
+            &steps,
+            &Config::default(),
+            &HashCache::default(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_date_source_fallback_chain() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let mut file = make_file("missing_photo.jpg");
+        file.modified = Some(UNIX_EPOCH + Duration::from_secs(20058 * 86400 + 12 * 3600));
+        let files = vec![file];
+        let selected = HashSet::new();
+        let steps = [RenameStep {
+            date_source: DateSource::Exif,
+            ..RenameStep::new(RenameMode::DateInsert, "", "")
+        }];
+
+        // No EXIF data, falls back to the modification time
+        let previews = generate_previews(
+            &files,
+            &selected,
+            &steps,
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+        assert_eq!(previews[0].new_name, "20241201_missing_photo.jpg");
+
+        // Empty fallback chain leaves no date
+        let config = Config {
+            date_fallback: Vec::new(),
+            ..Config::default()
+        };
+        let previews =
+            generate_previews(&files, &selected, &steps, &config, &HashCache::default()).unwrap();
+        assert_eq!(previews[0].new_name, "00000000_missing_photo.jpg");
+    }
+
+    #[test]
+    fn test_case_style_modes() {
+        let config = Config::default();
+        let rename = |name: &str, mode: RenameMode| {
+            let step = RenameStep::new(mode, "", "");
+            generate_previews(
+                &[make_file(name)],
+                &HashSet::new(),
+                &[step],
+                &config,
+                &HashCache::default(),
+            )
+            .unwrap()[0]
+                .new_name
+                .clone()
+        };
+
+        assert_eq!(
+            rename("myHTTPServer2.rs", RenameMode::SnakeCase),
+            "my_http_server_2.rs"
+        );
+        assert_eq!(
+            rename("My Holiday Photo.JPG", RenameMode::KebabCase),
+            "my-holiday-photo.jpg"
+        );
+        assert_eq!(
+            rename("user_profile_view.tsx", RenameMode::PascalCase),
+            "UserProfileView.tsx"
+        );
+        assert_eq!(
+            rename("backup-2024.tar.gz", RenameMode::CamelCase),
+            "backup2024.tar.gz"
+        );
+        assert_eq!(rename(".env_local", RenameMode::DotCase), ".env.local");
+        assert_eq!(
+            rename("THE_FINAL_cut.mkv", RenameMode::SentenceCase),
+            "The final cut.mkv"
+        );
+        assert_eq!(rename("___.txt", RenameMode::SnakeCase), "___.txt");
+    }
+
+    #[test]
+    fn test_position_mode_keeps_extension() {
+        let files = vec![make_file("IMG_1234.tar.gz"), make_file("Übung.txt")];
+        let mut step = RenameStep::new(RenameMode::Position, "0..4", "");
+        step.position_action = PositionAction::Delete;
+
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+        assert_eq!(previews[0].new_name, "1234.tar.gz");
+        assert_eq!(previews[1].new_name, "g.txt");
+
+        let mut step = RenameStep::new(RenameMode::Position, "x", "abc");
+        step.position_action = PositionAction::Insert;
+        assert!(generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_split_extension() {
+        assert_eq!(split_extension("photo.JPG"), ("photo", ".JPG"));
+        assert_eq!(split_extension("backup.tar.gz"), ("backup", ".tar.gz"));
+        assert_eq!(split_extension("data.v2.gz"), ("data.v2", ".gz"));
+        assert_eq!(split_extension(".bashrc"), (".bashrc", ""));
+        assert_eq!(split_extension("README"), ("README", ""));
+    }
+
+    #[test]
+    fn test_extension_mode() {
+        let config = Config::default();
+        let rename = |name: &str, action: ExtensionAction, value: &str| {
+            let mut step = RenameStep::new(RenameMode::Extension, value, "");
+            step.extension_action = action;
+            generate_previews(
+                &[make_file(name)],
+                &HashSet::new(),
+                &[step],
+                &config,
+                &HashCache::default(),
+            )
+            .unwrap()[0]
+                .new_name
+                .clone()
+        };
+
+        assert_eq!(rename("a.txt", ExtensionAction::Set, ".md"), "a.md");
+        assert_eq!(rename("a.tar.gz", ExtensionAction::Set, "zip"), "a.zip");
+        assert_eq!(rename("a.JPG", ExtensionAction::Lowercase, ""), "a.jpg");
+        assert_eq!(rename("a.JPEG", ExtensionAction::Normalize, ""), "a.jpg");
+        assert_eq!(rename("a.Tif", ExtensionAction::Normalize, ""), "a.tiff");
+        assert_eq!(
+            rename("a.TAR.GZ", ExtensionAction::Normalize, ""),
+            "a.tar.gz"
+        );
+        assert_eq!(rename("a.tgz", ExtensionAction::Normalize, ""), "a.tar.gz");
+        assert_eq!(rename("a.tar.gz", ExtensionAction::Remove, ""), "a");
+        assert_eq!(rename("notes", ExtensionAction::Add, "txt"), "notes.txt");
+        assert_eq!(
+            rename("notes.TXT", ExtensionAction::Add, "txt"),
+            "notes.TXT"
+        );
+    }
+
+    #[test]
+    fn test_uppercase_keeps_tar_extension() {
+        assert_eq!(
+            to_uppercase_preserve_extension("backup.tar.gz"),
+            "BACKUP.tar.gz"
+        );
+        // The extension is left as it is
+        assert_eq!(to_uppercase_preserve_extension("foto.JPG"), "FOTO.JPG");
+        assert_eq!(to_uppercase_preserve_extension("foto.Jpg"), "FOTO.Jpg");
+    }
+
+    #[test]
+    fn test_fix_extension_by_content() {
+        let dir = std::env::temp_dir().join(format!("rnm-sniff-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+        let contents: [(&str, &[u8]); 4] = [
+            ("image.png", &jpeg),
+            ("download", b"%PDF-1.4\n"),
+            ("photo.JPEG", &jpeg),
+            ("notes.txt", b"just text"),
+        ];
+        let files: Vec<FileEntry> = contents
+            .iter()
+            .map(|(name, data)| {
+                let path = dir.join(name);
+                std::fs::write(&path, data).unwrap();
+                FileEntry {
+                    extension: path
+                        .extension()
+                        .map(|e| e.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    path,
+                    ..make_file(name)
+                }
+            })
+            .collect();
+
+        let step = RenameStep::new(RenameMode::FixExtension, "", "");
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let find = |name: &str| previews.iter().find(|p| p.original_name == name).unwrap();
+        assert_eq!(find("image.png").new_name, "image.jpg");
+        assert_eq!(
+            find("image.png").type_mismatch.as_ref().unwrap().describe(),
+            "JPEG, Endung: .png"
+        );
+        assert_eq!(find("download").new_name, "download.pdf");
+        assert_eq!(
+            find("download").type_mismatch.as_ref().unwrap().extension,
+            ""
+        );
+        assert!(!find("photo.JPEG").will_change);
+        assert!(find("photo.JPEG").type_mismatch.is_none());
+        assert!(!find("notes.txt").will_change);
+    }
+
+    #[test]
+    fn test_audio_tags_missing_are_flagged() {
+        let files = vec![make_file("track01.mp3"), make_file("track02.mp3")];
+        let steps = [
+            RenameStep::new(
+                RenameMode::AudioTags,
+                "{track:02} - {artist} - {title}.{ext}",
+                "",
+            ),
+            RenameStep::new(RenameMode::Uppercase, "", ""),
+        ];
+
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &steps,
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+
+        assert_eq!(previews.len(), 2);
+        for preview in &previews {
+            assert!(!preview.will_change);
+            assert_eq!(preview.new_name, preview.original_name);
+            assert_eq!(
+                preview.error.as_deref(),
+                Some("Fehlende Tags: track, artist, title")
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_mode() {
+        let files = vec![
+            make_file("a.tar.gz"),
+            make_file("b.jpg"),
+            make_file("c.jpg"),
+        ];
+        let mut hashes = HashCache::default();
+        let algorithm = HashAlgorithm::Sha256;
+        hashes.insert(
+            PathBuf::from("a.tar.gz"),
+            algorithm,
+            Ok("0123abcd".to_string()),
+        );
+        hashes.insert(
+            PathBuf::from("b.jpg"),
+            algorithm,
+            Err("Datei nicht lesbar".to_string()),
+        );
+
+        let step = RenameStep::new(RenameMode::Hash, "4", "_");
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &hashes,
+        )
+        .unwrap();
+        assert_eq!(previews[0].new_name, "0123.tar.gz");
+        assert_eq!(previews[1].error.as_deref(), Some("Datei nicht lesbar"));
+        assert_eq!(
+            previews[2].error.as_deref(),
+            Some("Pruefsumme wird berechnet...")
+        );
+
+        let step = RenameStep {
+            hash_position: DatePosition::Suffix,
+            ..RenameStep::new(RenameMode::Hash, "", "-")
+        };
+        let previews = generate_previews(
+            &files[..1],
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &hashes,
+        )
+        .unwrap();
+        assert_eq!(previews[0].new_name, "a-0123abcd.tar.gz");
+
+        let invalid = RenameStep::new(RenameMode::Hash, "0", "_");
+        assert!(generate_previews(
+            &files,
+            &HashSet::new(),
+            &[invalid],
+            &Config::default(),
+            &hashes
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_script_mode() {
+        let path = std::env::temp_dir().join(format!("rnm_script_{}.rhai", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"if stem == "b" { throw "kein b" } `${counter}-${stem}.${ext}`"#,
+        )
+        .unwrap();
+        let files = vec![make_file("a.txt"), make_file("b.txt")];
+        let step = RenameStep::new(RenameMode::Script, &path.to_string_lossy(), "");
+
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(previews[0].new_name, "1-a.txt");
+        assert!(!previews[1].will_change);
+        assert!(previews[1].error.as_deref().unwrap().contains("kein b"));
+
+        let missing = RenameStep::new(RenameMode::Script, "/nicht/da.rhai", "");
+        assert!(generate_previews(
+            &files,
+            &HashSet::new(),
+            &[missing],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_directories_only_if_enabled() {
+        let mut dir = make_file("Album 2024");
+        dir.is_dir = true;
+        dir.extension = String::new();
+        let files = vec![dir, make_file("a.jpg")];
+        let upper = RenameStep::new(RenameMode::Uppercase, "", "");
+
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            std::slice::from_ref(&upper),
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+        assert_eq!(previews.len(), 1);
+
+        let config = Config {
+            include_dirs: true,
+            ..Config::default()
+        };
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[upper],
+            &config,
+            &HashCache::default(),
+        )
+        .unwrap();
+        assert_eq!(previews[0].new_name, "ALBUM 2024");
+        assert_eq!(previews[1].new_name, "A.jpg");
+
+        // Extension and checksum modes leave directories alone
+        let ext = RenameStep {
+            extension_action: ExtensionAction::Set,
+            ..RenameStep::new(RenameMode::Extension, "png", "")
+        };
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[ext],
+            &config,
+            &HashCache::default(),
+        )
+        .unwrap();
+        assert!(!previews[0].will_change);
+        assert_eq!(previews[1].new_name, "a.png");
+
+        let hash = RenameStep::new(RenameMode::Hash, "", "_");
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[hash],
+            &config,
+            &HashCache::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            previews[0].error.as_deref(),
+            Some("Ordner haben keine Pruefsumme")
+        );
+    }
+
+    #[test]
+    fn test_execute_renames_directories() {
+        let dir = std::env::temp_dir().join(format!("rnm-dir-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("alt")).unwrap();
+        std::fs::write(dir.join("alt").join("a.txt"), "a").unwrap();
+        std::fs::write(dir.join("b.txt"), "b").unwrap();
+
+        let preview = |from: &str, to: &str| RenamePreview {
+            original_name: from.to_string(),
+            new_name: to.to_string(),
+            will_change: true,
+            error: None,
+            type_mismatch: None,
+            rule: None,
+            file_index: 0,
+        };
+
+        // The same target twice is rejected before anything is renamed
+        let result = execute_renames_with_history(
+            &[preview("alt", "neu"), preview("b.txt", "neu")],
+            &dir,
+            None,
+            false,
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Zielname mehrfach vergeben: neu"));
+        assert!(dir.join("alt").is_dir());
+
+        let renamed =
+            execute_renames_with_history(&[preview("alt", "neu")], &dir, None, false).unwrap();
+        assert_eq!(renamed, 1);
+        assert!(dir.join("neu").join("a.txt").is_file());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unreadable_subdirectory_is_skipped() {
+        let dir = std::env::temp_dir().join(format!("rnm-locked-test-{}", std::process::id()));
+        let locked = dir.join("locked");
+        std::fs::create_dir_all(locked.join("inner")).unwrap();
+        std::fs::write(dir.join("a.txt"), "").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+        }
+
+        let files = crate::app::load_files(&dir, None, crate::app::SortOrder::Name, 0);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // The locked folder itself is listed, its content only if readable (root)
+        let names: Vec<_> = files.unwrap().iter().map(|f| f.relative_name()).collect();
+        assert!(names.contains(&"a.txt".to_string()));
+        assert!(names.contains(&"locked".to_string()));
+    }
+
+    #[test]
+    fn test_rename_directory_tree() {
+        let dir = std::env::temp_dir().join(format!("rnm-tree-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("album").join("cd 1")).unwrap();
+        std::fs::write(dir.join("album").join("intro.mp3"), "").unwrap();
+        std::fs::write(dir.join("album").join("cd 1").join("track.mp3"), "").unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let flat = crate::app::load_files(&dir, None, crate::app::SortOrder::Name, 1).unwrap();
+        assert_eq!(flat.len(), 2);
+        let files = crate::app::load_files(&dir, None, crate::app::SortOrder::Name, 0).unwrap();
+        let names: Vec<_> = files.iter().map(|f| f.relative_name()).collect();
+        assert_eq!(
+            names,
+            [
+                "album",
+                "notes.txt",
+                "album/cd 1",
+                "album/intro.mp3",
+                "album/cd 1/track.mp3"
+            ]
+        );
+        let mp3 =
+            crate::app::load_files(&dir, Some("*.mp3"), crate::app::SortOrder::Name, 0).unwrap();
+        assert_eq!(mp3.len(), 2);
+
+        // A directory and the files inside it in one batch
+        let config = Config {
+            include_dirs: true,
+            ..Config::default()
+        };
+        let step = RenameStep::new(RenameMode::Uppercase, "", "");
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &config,
+            &HashCache::default(),
+        )
+        .unwrap();
+        let preview = previews
+            .iter()
+            .find(|p| p.original_name == "album/cd 1/track.mp3")
+            .unwrap();
+        assert_eq!(preview.new_name, "album/cd 1/TRACK.mp3");
+
+        let renamed = execute_renames_with_history(&previews, &dir, None, false).unwrap();
+        assert_eq!(renamed, 5);
+        assert!(dir.join("ALBUM").join("CD 1").join("TRACK.mp3").is_file());
+        assert!(dir.join("ALBUM").join("INTRO.mp3").is_file());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_check_target() {
+        assert_eq!(check_target("b.txt"), None);
+        assert_eq!(check_target("2024/05/b.txt"), None);
+        assert!(check_target("../b.txt").is_some());
+        assert!(check_target("/tmp/b.txt").is_some());
+        assert!(check_target("2024/").is_some());
+        assert!(check_target("a/tab\there").is_some());
+    }
+
+    #[test]
+    fn test_organize_mode() {
+        let files = vec![make_file("a.jpg"), make_file("b.txt")];
+        let step = RenameStep::new(RenameMode::Organize, "{ext|upper}/{name}", "");
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+        assert_eq!(previews[0].new_name, "JPG/a.jpg");
+        assert_eq!(previews[1].new_name, "TXT/b.txt");
+
+        // Subfolders only in organize mode
+        let step = RenameStep::new(RenameMode::SearchReplace, ".", "/");
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+        assert!(!previews[0].will_change);
+        assert!(previews[0].error.as_deref().unwrap().contains("Ordnen"));
+
+        // Targets outside the directory are errors in the preview already
+        let step = RenameStep::new(RenameMode::Organize, "../{name}", "");
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+        assert!(!previews[0].will_change);
+        assert_eq!(previews[0].new_name, "a.jpg");
+        assert_eq!(
+            previews[0].error.as_deref(),
+            Some("Ungueltiger Dateiname: ../a.jpg (ausserhalb des Verzeichnisses)")
+        );
+    }
+
+    #[test]
+    fn test_media_mode() {
+        let files = vec![
+            make_file("Movie.2019.1080p.mkv"),
+            make_file("notes.txt"),
+            make_file("show.name.s01e02.pilot.720p.mkv"),
+        ];
+        // Empty schemes come from the config
+        let step = RenameStep::new(RenameMode::Media, "", "");
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+        assert_eq!(previews[0].new_name, "Movie (2019).mkv");
+        assert!(previews[1].error.is_some());
+        assert_eq!(previews[2].new_name, "Show Name - S01E02 - Pilot.mkv");
+
+        let step = RenameStep::new(RenameMode::Media, "{show} {season}x{episode:02}.{ext}", "");
+        let previews = generate_previews(
+            &files,
+            &HashSet::new(),
+            &[step],
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+        assert_eq!(previews[2].new_name, "Show Name 1x02.mkv");
+    }
+
+    #[test]
+    fn test_execute_creates_target_dirs() {
+        let dir = std::env::temp_dir().join(format!("rnm-organize-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.jpg"), "").unwrap();
+        std::fs::write(dir.join("b.jpg"), "").unwrap();
+        std::fs::write(dir.join("2023"), "").unwrap();
+
+        let preview = |from: &str, to: &str| RenamePreview {
+            original_name: from.to_string(),
+            new_name: to.to_string(),
+            will_change: true,
+            error: None,
+            type_mismatch: None,
+            rule: None,
+            file_index: 0,
+        };
+
+        // Subfolders only for organize mode
+        let err =
+            execute_renames_with_history(&[preview("a.jpg", "2024/a.jpg")], &dir, None, false)
+                .unwrap_err();
+        assert!(err.to_string().contains("Unterordner nur im Ordnen-Modus"));
+        assert!(!dir.join("2024").exists());
+
+        // A folder of the target exists as file
+        let err = execute_renames_with_history(&[preview("a.jpg", "2023/a.jpg")], &dir, None, true)
+            .unwrap_err();
+        assert!(err.to_string().contains("Kein Ordner: 2023"));
+
+        let renamed = execute_renames_with_history(
+            &[
+                preview("a.jpg", "2024/05/a.jpg"),
+                preview("b.jpg", "2024/06/b.jpg"),
+            ],
+            &dir,
+            None,
+            true,
+        )
+        .unwrap();
+        assert_eq!(renamed, 2);
+        assert!(dir.join("2024").join("05").join("a.jpg").is_file());
+        assert!(dir.join("2024").join("06").join("b.jpg").is_file());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
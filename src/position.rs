@@ -0,0 +1,223 @@
+use anyhow::{anyhow, Result};
+
+use crate::app::PositionAction;
+
+/// A character position, counted from the start or (with a leading '-') from the end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    offset: usize,
+    from_end: bool,
+}
+
+impl Position {
+    /// Parse "3" (after the third character) or "-2" (two characters before the end)
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let (digits, from_end) = match value.strip_prefix('-') {
+            Some(rest) => (rest, true),
+            None => (value, false),
+        };
+        let offset = digits
+            .parse()
+            .map_err(|_| anyhow!("Ungueltige Position: '{}' (z.B. 3 oder -2)", value))?;
+        Ok(Self { offset, from_end })
+    }
+
+    /// Character index in a name of the given length, clamped to the name
+    fn resolve(&self, len: usize) -> usize {
+        if self.from_end {
+            len.saturating_sub(self.offset)
+        } else {
+            self.offset.min(len)
+        }
+    }
+}
+
+/// A character range "N..M", either side can be left out ("..3", "-4..")
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharRange {
+    start: Option<Position>,
+    end: Option<Position>,
+}
+
+impl CharRange {
+    pub fn parse(value: &str) -> Result<Self> {
+        let (start, end) = value
+            .split_once("..")
+            .ok_or_else(|| anyhow!("Ungueltiger Bereich: '{}' (Format: N..M)", value))?;
+        let parse_side = |side: &str| {
+            if side.trim().is_empty() {
+                Ok(None)
+            } else {
+                Position::parse(side).map(Some)
+            }
+        };
+        Ok(Self {
+            start: parse_side(start)?,
+            end: parse_side(end)?,
+        })
+    }
+
+    /// Start and end character index, the end is exclusive
+    fn resolve(&self, len: usize) -> (usize, usize) {
+        let start = self.start.map_or(0, |p| p.resolve(len));
+        let end = self.end.map_or(len, |p| p.resolve(len));
+        (start, end.max(start))
+    }
+}
+
+/// A position based edit of the file stem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionEdit {
+    Insert {
+        text: String,
+        at: Position,
+    },
+    Delete(CharRange),
+    Keep(CharRange),
+    CutAfter {
+        delimiter: String,
+        occurrence: Position,
+    },
+}
+
+impl PositionEdit {
+    /// Build the edit from the step inputs: the first input holds the text,
+    /// range or delimiter, the second one the position or occurrence
+    pub fn parse(action: PositionAction, first: &str, second: &str) -> Result<Self> {
+        let second_or = |default| {
+            if second.trim().is_empty() {
+                default
+            } else {
+                second
+            }
+        };
+        let edit = match action {
+            PositionAction::Insert => PositionEdit::Insert {
+                text: first.to_string(),
+                at: Position::parse(second_or("0"))?,
+            },
+            PositionAction::Delete => PositionEdit::Delete(CharRange::parse(first)?),
+            PositionAction::Keep => PositionEdit::Keep(CharRange::parse(first)?),
+            PositionAction::CutAfter => {
+                let occurrence = Position::parse(second_or("1"))?;
+                if occurrence.offset == 0 {
+                    return Err(anyhow!(
+                        "Das Vorkommen zaehlt ab 1 (oder -1 fuer das letzte)"
+                    ));
+                }
+                PositionEdit::CutAfter {
+                    delimiter: first.to_string(),
+                    occurrence,
+                }
+            }
+        };
+        Ok(edit)
+    }
+
+    /// Apply the edit to a stem, positions count characters, not bytes
+    pub fn apply(&self, stem: &str) -> String {
+        let chars: Vec<char> = stem.chars().collect();
+        let len = chars.len();
+
+        match self {
+            PositionEdit::Insert { text, at } => {
+                let index = at.resolve(len);
+                let mut result: String = chars[..index].iter().collect();
+                result.push_str(text);
+                result.extend(&chars[index..]);
+                result
+            }
+            PositionEdit::Delete(range) => {
+                let (start, end) = range.resolve(len);
+                chars[..start].iter().chain(&chars[end..]).collect()
+            }
+            PositionEdit::Keep(range) => {
+                let (start, end) = range.resolve(len);
+                chars[start..end].iter().collect()
+            }
+            PositionEdit::CutAfter {
+                delimiter,
+                occurrence,
+            } => {
+                if delimiter.is_empty() {
+                    return stem.to_string();
+                }
+                let matches: Vec<usize> = stem
+                    .match_indices(delimiter.as_str())
+                    .map(|(i, _)| i)
+                    .collect();
+                let n = occurrence.offset;
+                let found = if occurrence.from_end {
+                    matches.len().checked_sub(n).map(|i| matches[i])
+                } else {
+                    matches.get(n - 1).copied()
+                };
+                match found {
+                    Some(byte_index) => stem[..byte_index].to_string(),
+                    None => stem.to_string(),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(action: PositionAction, first: &str, second: &str, stem: &str) -> String {
+        PositionEdit::parse(action, first, second)
+            .unwrap()
+            .apply(stem)
+    }
+
+    #[test]
+    fn test_insert() {
+        assert_eq!(edit(PositionAction::Insert, "X", "0", "photo"), "Xphoto");
+        assert_eq!(edit(PositionAction::Insert, "_", "3", "photo"), "pho_to");
+        assert_eq!(edit(PositionAction::Insert, "!", "-0", "photo"), "photo!");
+        assert_eq!(edit(PositionAction::Insert, "-", "-2", "Größe"), "Grö-ße");
+        assert_eq!(edit(PositionAction::Insert, "_", "99", "abc"), "abc_");
+    }
+
+    #[test]
+    fn test_delete_and_keep() {
+        assert_eq!(edit(PositionAction::Delete, "0..4", "", "IMG_1234"), "1234");
+        assert_eq!(
+            edit(PositionAction::Delete, "-3..", "", "Übung_alt"),
+            "Übung_"
+        );
+        assert_eq!(edit(PositionAction::Keep, "..5", "", "Übersicht"), "Übers");
+        assert_eq!(
+            edit(PositionAction::Keep, "4..-2", "", "IMG_1234_v2"),
+            "1234_"
+        );
+        assert_eq!(
+            edit(PositionAction::Delete, "5..2", "", "abcdefg"),
+            "abcdefg"
+        );
+    }
+
+    #[test]
+    fn test_cut_after() {
+        let stem = "Artist - Title - Live - 2024";
+        assert_eq!(edit(PositionAction::CutAfter, " - ", "", stem), "Artist");
+        assert_eq!(
+            edit(PositionAction::CutAfter, " - ", "2", stem),
+            "Artist - Title"
+        );
+        assert_eq!(
+            edit(PositionAction::CutAfter, " - ", "-1", stem),
+            "Artist - Title - Live"
+        );
+        assert_eq!(edit(PositionAction::CutAfter, " - ", "5", stem), stem);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(PositionEdit::parse(PositionAction::Insert, "x", "abc").is_err());
+        assert!(PositionEdit::parse(PositionAction::Delete, "3", "").is_err());
+        assert!(PositionEdit::parse(PositionAction::CutAfter, "_", "0").is_err());
+    }
+}
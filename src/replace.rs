@@ -0,0 +1,325 @@
+use anyhow::{anyhow, Result};
+use regex::{Captures, Regex};
+
+use crate::app::RegexFlags;
+use crate::template::Filter;
+
+/// Widest zero-padding of an expression, no file name is longer
+const MAX_WIDTH: usize = 255;
+
+/// Case conversion from a \U, \L, \u or \l escape in a replacement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaseEscape {
+    /// \U: uppercase until \E or \L
+    Upper,
+    /// \L: lowercase until \E or \U
+    Lower,
+    /// \E: end of \U or \L
+    End,
+    /// \u: uppercase the next character
+    UpperNext,
+    /// \l: lowercase the next character
+    LowerNext,
+}
+
+/// An expression on a capture group: {$1+12:03|upper}
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Expression {
+    /// Group number or name
+    group: String,
+    /// Value added to the number in the group
+    offset: i64,
+    /// Zero-padding width of the number
+    width: Option<usize>,
+    filters: Vec<Filter>,
+}
+
+impl Expression {
+    /// Parse the content between "{$" and "}"
+    fn parse(content: &str) -> Result<Self> {
+        let invalid = || anyhow!("Ungueltiger Ausdruck: {{${}}} (z.B. {{$1+12:03}})", content);
+
+        let mut parts = content.split('|');
+        let head = parts.next().unwrap_or("");
+        let filters = parts.map(Filter::parse).collect::<Result<Vec<_>>>()?;
+
+        let (head, width) = match head.split_once(':') {
+            Some((head, width)) => (head, Some(width.trim().parse().map_err(|_| invalid())?)),
+            None => (head, None),
+        };
+        if width.is_some_and(|width| width > MAX_WIDTH) {
+            return Err(anyhow!(
+                "Breite zu gross in {{${}}} (hoechstens {})",
+                content,
+                MAX_WIDTH
+            ));
+        }
+
+        let name_end = head
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(head.len());
+        let (group, offset) = head.split_at(name_end);
+        if group.is_empty() {
+            return Err(invalid());
+        }
+        let offset = match offset.trim() {
+            "" => 0,
+            offset if offset.starts_with(['+', '-']) => {
+                offset.replace(' ', "").parse().map_err(|_| invalid())?
+            }
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            group: group.to_string(),
+            offset,
+            width,
+            filters,
+        })
+    }
+
+    fn evaluate(&self, caps: &Captures) -> Result<String> {
+        let capture = match self.group.parse::<usize>() {
+            Ok(index) => caps.get(index),
+            Err(_) => caps.name(&self.group),
+        };
+        let text = capture.map_or("", |m| m.as_str());
+
+        let mut value = if self.offset != 0 || self.width.is_some() {
+            let number: i64 = text
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("Gruppe ${} ist keine Zahl: '{}'", self.group, text))?;
+            let number = number
+                .checked_add(self.offset)
+                .ok_or_else(|| anyhow!("Zahl zu gross: {}{:+}", number, self.offset))?;
+            format!("{:0width$}", number, width = self.width.unwrap_or(0))
+        } else {
+            text.to_string()
+        };
+        for filter in &self.filters {
+            value = filter.apply(&value);
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    /// Replacement text with $1/${name} references, expanded by the regex crate
+    Text(String),
+    Expr(Expression),
+    Case(CaseEscape),
+}
+
+/// A regex replacement string with Perl/sed style case escapes
+/// (`\U$2\E_$1` uppercases the second group) and expressions on groups
+/// (`{$1+12:03}` adds 12 and pads to three digits)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    parts: Vec<Part>,
+}
+
+impl Replacement {
+    pub fn parse(replacement: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = replacement;
+
+        while let Some(c) = rest.chars().next() {
+            let escape = match rest.get(..2) {
+                Some("\\U") => Some(CaseEscape::Upper),
+                Some("\\L") => Some(CaseEscape::Lower),
+                Some("\\E") => Some(CaseEscape::End),
+                Some("\\u") => Some(CaseEscape::UpperNext),
+                Some("\\l") => Some(CaseEscape::LowerNext),
+                _ => None,
+            };
+            let part = if let Some(escape) = escape {
+                rest = &rest[2..];
+                Part::Case(escape)
+            } else if let Some(expression) = rest.strip_prefix("{$") {
+                let end = expression
+                    .find('}')
+                    .ok_or_else(|| anyhow!("Ausdruck ohne schliessende Klammer: {}", rest))?;
+                rest = &expression[end + 1..];
+                Part::Expr(Expression::parse(&expression[..end])?)
+            } else {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            };
+            if !text.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut text)));
+            }
+            parts.push(part);
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Build the replacement for one match
+    fn expand(&self, caps: &Captures) -> Result<String> {
+        let mut result = String::new();
+        let mut mode = CaseEscape::End;
+        let mut next = None;
+
+        for part in &self.parts {
+            let text = match part {
+                Part::Case(escape @ (CaseEscape::UpperNext | CaseEscape::LowerNext)) => {
+                    next = Some(*escape);
+                    continue;
+                }
+                Part::Case(escape) => {
+                    mode = *escape;
+                    continue;
+                }
+                Part::Text(template) => {
+                    let mut text = String::new();
+                    caps.expand(template, &mut text);
+                    text
+                }
+                Part::Expr(expression) => expression.evaluate(caps)?,
+            };
+            let text = match mode {
+                CaseEscape::Upper => text.to_uppercase(),
+                CaseEscape::Lower => text.to_lowercase(),
+                _ => text,
+            };
+            let mut chars = text.chars();
+            match (next, chars.next()) {
+                (Some(escape), Some(first)) => {
+                    if escape == CaseEscape::UpperNext {
+                        result.extend(first.to_uppercase());
+                    } else {
+                        result.extend(first.to_lowercase());
+                    }
+                    result.push_str(chars.as_str());
+                    next = None;
+                }
+                _ => result.push_str(&text),
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Replace the matches of a regex in a name. With an occurrence only that match
+/// is replaced (1 = first), otherwise all of them.
+pub fn replace_matches(
+    re: &Regex,
+    name: &str,
+    replacement: &Replacement,
+    flags: &RegexFlags,
+) -> Result<String> {
+    let mut result = String::with_capacity(name.len());
+    let mut last = 0;
+
+    for (i, caps) in re.captures_iter(name).enumerate() {
+        if flags.occurrence > 0 && i + 1 != flags.occurrence {
+            continue;
+        }
+        let Some(m) = caps.get(0) else {
+            continue;
+        };
+        result.push_str(&name[last..m.start()]);
+        result.push_str(&replacement.expand(&caps)?);
+        last = m.end();
+        if flags.occurrence > 0 {
+            break;
+        }
+    }
+    result.push_str(&name[last..]);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replace(pattern: &str, name: &str, replacement: &str, occurrence: usize) -> String {
+        let flags = RegexFlags {
+            occurrence,
+            ..RegexFlags::default()
+        };
+        replace_matches(
+            &Regex::new(pattern).unwrap(),
+            name,
+            &Replacement::parse(replacement).unwrap(),
+            &flags,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_case_escapes() {
+        assert_eq!(
+            replace(r"(.*)_(\w+)", "holiday_photo", r"\U$2\E_$1", 0),
+            "PHOTO_holiday"
+        );
+        assert_eq!(
+            replace(r"(\w+) (\w+)", "JOHN SMITH", r"\u\L$1\E \u\L$2", 0),
+            "John Smith"
+        );
+        assert_eq!(replace(r"^(\w)", "report", r"\u$1", 0), "Report");
+        assert_eq!(replace(r"^(\w+)", "REPORT", r"\l$1", 0), "rEPORT");
+        assert_eq!(replace(r"(\d+)", "a1", r"\d$1", 0), r"a\d1");
+    }
+
+    #[test]
+    fn test_occurrence() {
+        assert_eq!(replace("a", "banana", "o", 0), "bonono");
+        assert_eq!(replace("a", "banana", "o", 1), "bonana");
+        assert_eq!(replace("a", "banana", "o", 3), "banano");
+        assert_eq!(replace("a", "banana", "o", 4), "banana");
+        assert_eq!(replace(r"(\d)", "1-2-3", r"[$1]", 2), "1-[2]-3");
+    }
+
+    #[test]
+    fn test_expressions() {
+        assert_eq!(
+            replace(r"ep(\d+)", "ep7", "episode_{$1+12:03}", 0),
+            "episode_019"
+        );
+        assert_eq!(replace(r"(\d+)", "scan 0042", "{$1-2}", 0), "scan 40");
+        assert_eq!(replace(r"(\d+)", "p5", "{$1:04}", 0), "p0005");
+        assert_eq!(
+            replace(
+                r"(?P<show>\w+)_s(\d+)",
+                "lost_s1",
+                "{$show|upper}-S{$2:02}",
+                0
+            ),
+            "LOST-S01"
+        );
+        assert_eq!(replace(r"(\w+)", "abc", "{$1|title}", 0), "Abc");
+        assert_eq!(replace(r"(\w+)", "abc", r"{x}", 0), "{x}");
+    }
+
+    #[test]
+    fn test_expression_errors() {
+        assert!(Replacement::parse("{$1+x}").is_err());
+        assert!(Replacement::parse("{$1:ab}").is_err());
+        assert!(Replacement::parse("{$}").is_err());
+        assert!(Replacement::parse("{$1|shout}").is_err());
+        assert!(Replacement::parse("{$1+2").is_err());
+
+        let replacement = Replacement::parse("{$1+1}").unwrap();
+        let re = Regex::new(r"(\w+)").unwrap();
+        let result = replace_matches(&re, "abc", &replacement, &RegexFlags::default());
+        assert!(result.unwrap_err().to_string().contains("keine Zahl"));
+
+        let replacement = Replacement::parse("p{$1+9223372036854775807}").unwrap();
+        let re = Regex::new(r"page(\d+)").unwrap();
+        let result = replace_matches(&re, "page1", &replacement, &RegexFlags::default());
+        assert!(result.unwrap_err().to_string().contains("Zahl zu gross"));
+
+        assert!(Replacement::parse("{$1:255}").is_ok());
+        assert!(Replacement::parse("{$1:999999999}").is_err());
+    }
+}
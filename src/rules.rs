@@ -0,0 +1,276 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+
+use crate::app::{FileEntry, RenameStep};
+use crate::config::{Config, RuleSet};
+use crate::filter::{parse_filter, PreparedFilter};
+use crate::hash::HashCache;
+use crate::operations::{generate_previews, RenamePreview};
+
+/// Label of the fallback steps in the preview
+pub const FALLBACK_LABEL: &str = "sonst";
+
+/// Generate previews for the selected files with a rule set: every file is
+/// renamed by the steps of the first rule whose condition it matches, or by the
+/// fallback. Counters count per rule.
+pub fn rule_previews(
+    files: &[FileEntry],
+    selected: &HashSet<usize>,
+    rule_set: &RuleSet,
+    config: &Config,
+    hashes: &HashCache,
+) -> Result<Vec<RenamePreview>> {
+    let conditions = rule_set
+        .rules
+        .iter()
+        .map(|rule| {
+            parse_filter(&rule.when)
+                .and_then(|filter| PreparedFilter::new(&filter))
+                .map_err(|e| anyhow!("Regel '{}': {}", rule.label(), e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Files of each rule, then the fallback
+    let mut groups: Vec<HashSet<usize>> = vec![HashSet::new(); rule_set.rules.len() + 1];
+    for (index, file) in files.iter().enumerate() {
+        let wanted = selected.is_empty() || selected.contains(&index);
+        if !wanted || (file.is_dir && !config.include_dirs) {
+            continue;
+        }
+        let group = conditions
+            .iter()
+            .position(|condition| condition.matches(file))
+            .unwrap_or(rule_set.rules.len());
+        groups[group].insert(index);
+    }
+
+    let actions = rule_set
+        .rules
+        .iter()
+        .map(|rule| (rule.label(), &rule.steps))
+        .chain([(FALLBACK_LABEL, &rule_set.fallback)]);
+
+    let mut previews = Vec::new();
+    for ((label, steps), group) in actions.zip(&groups) {
+        if group.is_empty() {
+            continue;
+        }
+        if steps.is_empty() {
+            previews.extend(group.iter().map(|&index| unchanged(&files[index], index)));
+            continue;
+        }
+        let group_previews = generate_previews(files, group, steps, config, hashes)
+            .map_err(|e| anyhow!("Regel '{}': {}", label, e))?;
+        previews.extend(group_previews.into_iter().map(|preview| RenamePreview {
+            rule: Some(label.to_string()),
+            ..preview
+        }));
+    }
+
+    mark_duplicate_targets(&mut previews);
+
+    // Sort by original name for display
+    previews.sort_by(|a, b| a.original_name.cmp(&b.original_name));
+
+    Ok(previews)
+}
+
+/// Each rule is previewed on its own, so a name given by two rules (or by a rule
+/// and a file that keeps its name) is only found in the merged previews
+fn mark_duplicate_targets(previews: &mut [RenamePreview]) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for preview in previews.iter() {
+        *counts.entry(preview.new_name.clone()).or_default() += 1;
+    }
+    for preview in previews.iter_mut() {
+        if preview.will_change && counts[&preview.new_name] > 1 {
+            preview.error = Some(format!("Zielname mehrfach vergeben: {}", preview.new_name));
+            preview.new_name = preview.original_name.clone();
+            preview.will_change = false;
+        }
+    }
+}
+
+/// Preview of a file that keeps its name
+fn unchanged(file: &FileEntry, index: usize) -> RenamePreview {
+    RenamePreview {
+        original_name: file.relative_name(),
+        new_name: file.relative_name(),
+        will_change: false,
+        error: None,
+        type_mismatch: None,
+        rule: None,
+        file_index: index,
+    }
+}
+
+/// All steps of a rule set (to find out which data the steps need)
+pub fn rule_set_steps(rule_set: &RuleSet) -> impl Iterator<Item = &RenameStep> {
+    rule_set
+        .rules
+        .iter()
+        .flat_map(|rule| &rule.steps)
+        .chain(&rule_set.fallback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::RenameMode;
+    use crate::config::{parse_rule, parse_step};
+    use std::path::PathBuf;
+
+    fn make_file(name: &str) -> FileEntry {
+        let path = PathBuf::from(name);
+        FileEntry {
+            extension: path
+                .extension()
+                .map(|e| e.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path,
+            name: name.to_string(),
+            subdir: PathBuf::new(),
+            is_dir: false,
+            size: 0,
+            modified: None,
+        }
+    }
+
+    fn rule_set(rules: &[&str], fallback: Option<&str>) -> RuleSet {
+        RuleSet {
+            name: "test".to_string(),
+            rules: rules.iter().map(|rule| parse_rule(rule).unwrap()).collect(),
+            fallback: fallback
+                .into_iter()
+                .map(|step| parse_step(step).unwrap())
+                .collect(),
+        }
+    }
+
+    fn previews(files: &[FileEntry], rule_set: &RuleSet) -> Vec<RenamePreview> {
+        rule_previews(
+            files,
+            &HashSet::new(),
+            rule_set,
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let files = vec![
+            make_file("a.jpg"),
+            make_file("b.mp4"),
+            make_file("c.mp4"),
+            make_file("d.txt"),
+            make_file("x.tmp"),
+        ];
+        let rules = rule_set(
+            &[
+                "*.tmp=>none",
+                "ext:jpg=>upper",
+                "ext:mp4=>numbering:clip_##",
+            ],
+            None,
+        );
+        let result = previews(&files, &rules);
+
+        let names: Vec<_> = result.iter().map(|p| p.new_name.as_str()).collect();
+        // Numbering counts only the files of its rule
+        assert_eq!(
+            names,
+            ["A.jpg", "clip_01.mp4", "clip_02.mp4", "d.txt", "x.tmp"]
+        );
+        assert_eq!(result[0].rule.as_deref(), Some("ext:jpg"));
+        assert_eq!(result[1].rule.as_deref(), Some("ext:mp4"));
+        assert_eq!(result[3].rule, None);
+        assert!(!result[4].will_change);
+    }
+
+    #[test]
+    fn test_fallback_and_selection() {
+        let files = vec![make_file("a.jpg"), make_file("b.txt"), make_file("c.txt")];
+        let rules = rule_set(&["ext:jpg=>upper"], Some("prefix:alt_"));
+
+        let result = previews(&files, &rules);
+        assert_eq!(result[1].new_name, "alt_b.txt");
+        assert_eq!(result[1].rule.as_deref(), Some(FALLBACK_LABEL));
+
+        let selected: HashSet<usize> = [0, 2].into();
+        let result = rule_previews(
+            &files,
+            &selected,
+            &rules,
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap();
+        let names: Vec<_> = result.iter().map(|p| p.new_name.as_str()).collect();
+        assert_eq!(names, ["A.jpg", "alt_c.txt"]);
+    }
+
+    #[test]
+    fn test_rule_errors() {
+        let files = vec![make_file("a.jpg")];
+        let rules = rule_set(&["size:viel..=>upper"], None);
+        let err = rule_previews(
+            &files,
+            &HashSet::new(),
+            &rules,
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Regel 'size:viel..'"));
+
+        let mut rules = rule_set(&["ext:jpg=>regex:(:x"], None);
+        rules.rules[0].name = "Fotos".to_string();
+        assert_eq!(rules.rules[0].steps[0].mode, RenameMode::Regex);
+        let err = rule_previews(
+            &files,
+            &HashSet::new(),
+            &rules,
+            &Config::default(),
+            &HashCache::default(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Regel 'Fotos'"));
+    }
+
+    #[test]
+    fn test_same_target_from_two_rules() {
+        let files = vec![
+            make_file("a.jpg"),
+            make_file("b.png"),
+            make_file("c.txt"),
+            make_file("d.md"),
+        ];
+        let rules = rule_set(
+            &[
+                "ext:jpg=>search:a:x",
+                "ext:png=>search:b.png:x.jpg",
+                "ext:md=>search:d.md:c.txt",
+            ],
+            None,
+        );
+        let result = previews(&files, &rules);
+
+        // Both files renamed to x.jpg and the rename onto the unchanged c.txt fail
+        for index in [0, 1, 3] {
+            assert!(!result[index].will_change);
+            assert_eq!(result[index].new_name, result[index].original_name);
+        }
+        assert_eq!(
+            result[0].error.as_deref(),
+            Some("Zielname mehrfach vergeben: x.jpg")
+        );
+        assert_eq!(
+            result[3].error.as_deref(),
+            Some("Zielname mehrfach vergeben: c.txt")
+        );
+        assert_eq!(result[2].error, None);
+    }
+}
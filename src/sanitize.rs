@@ -0,0 +1,368 @@
+use crate::app::SanitizeProfile;
+
+/// Maximum filename length in bytes on common file systems
+const MAX_NAME_LEN: usize = 255;
+
+/// Device names that Windows does not allow as filenames, with or without extension
+const WINDOWS_RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Characters Windows does not allow in filenames
+const WINDOWS_FORBIDDEN: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Characters that are dropped instead of replaced, they do not separate words
+const STRIPPED: &[char] = &['\'', '"', '`', '´', '?', '!', '*', ','];
+
+impl SanitizeProfile {
+    /// Character used in place of forbidden characters
+    fn separator(&self) -> char {
+        match self {
+            SanitizeProfile::Url => '-',
+            _ => '_',
+        }
+    }
+
+    /// Check if a character may stay in the name as it is
+    fn allows(&self, c: char) -> bool {
+        if c.is_control() {
+            return false;
+        }
+        match self {
+            SanitizeProfile::Posix => c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'),
+            SanitizeProfile::Windows => !WINDOWS_FORBIDDEN.contains(&c),
+            SanitizeProfile::Fat => {
+                !WINDOWS_FORBIDDEN.contains(&c) && !matches!(c, '+' | ',' | ';' | '=' | '[' | ']')
+            }
+            SanitizeProfile::Url => c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '~'),
+        }
+    }
+
+    /// Windows based file systems reject reserved names but allow spaces
+    fn windows_rules(&self) -> bool {
+        matches!(self, SanitizeProfile::Windows | SanitizeProfile::Fat)
+    }
+}
+
+/// ASCII replacement for common non-ASCII letters and punctuation
+fn transliterate(c: char) -> Option<&'static str> {
+    let replacement = match c {
+        'ä' => "ae",
+        'ö' => "oe",
+        'ü' => "ue",
+        'Ä' => "Ae",
+        'Ö' => "Oe",
+        'Ü' => "Ue",
+        'ß' => "ss",
+        'ẞ' => "SS",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'ø' => "o",
+        'Ø' => "O",
+        'å' => "a",
+        'Å' => "A",
+        'þ' => "th",
+        'Þ' => "Th",
+        'ð' | 'đ' => "d",
+        'Ð' | 'Đ' => "D",
+        'ł' => "l",
+        'Ł' => "L",
+        'à' | 'á' | 'â' | 'ã' | 'ā' | 'ă' | 'ą' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'ç' | 'ć' | 'č' | 'ĉ' => "c",
+        'Ç' | 'Ć' | 'Č' | 'Ĉ' => "C",
+        'ď' => "d",
+        'Ď' => "D",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'ğ' | 'ĝ' => "g",
+        'Ğ' | 'Ĝ' => "G",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' | 'Į' | 'İ' => "I",
+        'ñ' | 'ń' | 'ň' => "n",
+        'Ñ' | 'Ń' | 'Ň' => "N",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ō' | 'ő' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ō' | 'Ő' => "O",
+        'ř' => "r",
+        'Ř' => "R",
+        'ś' | 'š' | 'ş' | 'ș' => "s",
+        'Ś' | 'Š' | 'Ş' | 'Ș' => "S",
+        'ť' | 'ţ' | 'ț' => "t",
+        'Ť' | 'Ţ' | 'Ț' => "T",
+        'ù' | 'ú' | 'û' | 'ū' | 'ů' | 'ű' | 'ų' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ū' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'ý' | 'ÿ' => "y",
+        'Ý' | 'Ÿ' => "Y",
+        'ź' | 'ż' | 'ž' => "z",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        '–' | '—' | '‐' => "-",
+        '‘' | '’' | '‚' | '“' | '”' | '„' | '«' | '»' => "'",
+        '€' => "EUR",
+        '©' => "(c)",
+        '\u{a0}' => " ",
+        _ => return None,
+    };
+    Some(replacement)
+}
+
+/// Make a filename safe for the given profile: transliterate, replace or
+/// strip forbidden characters and collapse repeated separators
+pub fn sanitize_filename(filename: &str, profile: SanitizeProfile) -> String {
+    let separator = profile.separator();
+
+    // Split first, so a name like "???.txt" does not turn into a hidden file
+    let (stem, ext) = split_name(filename);
+    let mut stem = clean_part(stem, profile);
+    let mut ext = clean_part(ext, profile);
+
+    // No separators around the name or the extension dot
+    let trim = |c: char| c == separator || c == ' ' || c == '_' || c == '-';
+    stem = stem.trim_matches(trim).to_string();
+    ext = ext.trim_matches(trim).to_string();
+    // Trailing dots and spaces are dropped by Windows and confusing elsewhere
+    ext = ext.trim_end_matches(['.', ' ']).to_string();
+    if ext.is_empty() {
+        stem = stem.trim_end_matches(['.', ' ']).to_string();
+    }
+
+    if stem.is_empty() || stem == "." || stem == ".." {
+        stem = separator.to_string();
+    }
+    if profile.windows_rules() && is_windows_reserved(&stem) {
+        stem.push(separator);
+    }
+
+    truncate_stem(&mut stem, ext.len() + 1);
+    if ext.is_empty() {
+        stem
+    } else {
+        format!("{}.{}", stem, ext)
+    }
+}
+
+/// Transliterate, replace or strip forbidden characters and collapse separators
+fn clean_part(part: &str, profile: SanitizeProfile) -> String {
+    let mut cleaned = String::with_capacity(part.len());
+    for c in part.chars() {
+        // Transliterate first, so the profile rules see ASCII where possible
+        let replacement = transliterate(c);
+        let mut buffer = [0; 4];
+        let chars = replacement.unwrap_or_else(|| c.encode_utf8(&mut buffer));
+        for c in chars.chars() {
+            let c = if profile == SanitizeProfile::Url {
+                c.to_ascii_lowercase()
+            } else {
+                c
+            };
+            if profile.allows(c) {
+                cleaned.push(c);
+            } else if !STRIPPED.contains(&c) {
+                cleaned.push(profile.separator());
+            }
+        }
+    }
+    collapse_separators(&cleaned, profile)
+}
+
+/// Split at the last dot, a leading dot (hidden file) belongs to the stem
+fn split_name(name: &str) -> (&str, &str) {
+    match name.rfind('.').filter(|&pos| pos > 0) {
+        Some(pos) => (&name[..pos], &name[pos + 1..]),
+        None => (name, ""),
+    }
+}
+
+/// Collapse runs of separators ("a__b", "a_-_b", "a..b") into one character
+fn collapse_separators(value: &str, profile: SanitizeProfile) -> String {
+    // Spaces separate words on Windows/FAT, so "Artist - Title" stays as it is there
+    let windows = profile.windows_rules();
+    let in_run = |c: char| {
+        if windows {
+            c == '_' || c == ' '
+        } else {
+            c == '_' || c == '-'
+        }
+    };
+    let pick = |run: &str| {
+        if windows {
+            if run.contains(' ') {
+                ' '
+            } else {
+                '_'
+            }
+        } else if run.contains('-') {
+            '-'
+        } else {
+            profile.separator()
+        }
+    };
+
+    let mut result = String::with_capacity(value.len());
+    let mut run = String::new();
+    for c in value.chars() {
+        if in_run(c) {
+            run.push(c);
+            continue;
+        }
+        if !run.is_empty() {
+            result.push(pick(&run));
+            run.clear();
+        }
+        if !(matches!(c, '.' | '-') && result.ends_with(c)) {
+            result.push(c);
+        }
+    }
+    if !run.is_empty() {
+        result.push(pick(&run));
+    }
+    result
+}
+
+/// Check for device names like CON or com1 (also "CON.tar" stems)
+fn is_windows_reserved(stem: &str) -> bool {
+    let base = stem.split('.').next().unwrap_or(stem).trim_end();
+    WINDOWS_RESERVED
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(base))
+}
+
+/// Shorten the stem so that stem plus extension fit into the length limit
+fn truncate_stem(stem: &mut String, ext_len: usize) {
+    while stem.len() + ext_len > MAX_NAME_LEN && stem.len() > 1 {
+        stem.pop();
+    }
+}
+
+/// Check if a new filename is valid on this system, returns the reason if not
+pub fn check_filename(name: &str) -> Option<&'static str> {
+    if name == "." || name == ".." {
+        return Some("reservierter Name");
+    }
+    if name.contains('/') || name.contains('\\') {
+        return Some("enthaelt / oder \\");
+    }
+    if name.chars().any(|c| c.is_control()) {
+        return Some("enthaelt Steuerzeichen");
+    }
+    if name.len() > MAX_NAME_LEN {
+        return Some("laenger als 255 Bytes");
+    }
+    if cfg!(windows) {
+        if name.contains(WINDOWS_FORBIDDEN) {
+            return Some("enthaelt unter Windows verbotene Zeichen");
+        }
+        if name.ends_with(['.', ' ']) {
+            return Some("endet mit Punkt oder Leerzeichen");
+        }
+        if is_windows_reserved(name) {
+            return Some("reservierter Geraetename");
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transliteration() {
+        assert_eq!(
+            sanitize_filename("Übergröße Maß café.txt", SanitizeProfile::Posix),
+            "Uebergroesse_Mass_cafe.txt"
+        );
+        assert_eq!(
+            sanitize_filename("Ärger.pdf", SanitizeProfile::Windows),
+            "Aerger.pdf"
+        );
+    }
+
+    #[test]
+    fn test_posix_profile() {
+        assert_eq!(
+            sanitize_filename("my file (1).txt", SanitizeProfile::Posix),
+            "my_file_1.txt"
+        );
+        assert_eq!(
+            sanitize_filename("Artist - Title.mp3", SanitizeProfile::Posix),
+            "Artist-Title.mp3"
+        );
+        assert_eq!(
+            sanitize_filename("--what?!.md", SanitizeProfile::Posix),
+            "what.md"
+        );
+        assert_eq!(
+            sanitize_filename(".bashrc", SanitizeProfile::Posix),
+            ".bashrc"
+        );
+    }
+
+    #[test]
+    fn test_windows_profile() {
+        assert_eq!(
+            sanitize_filename("Report: Q1? final.docx", SanitizeProfile::Windows),
+            "Report Q1 final.docx"
+        );
+        assert_eq!(
+            sanitize_filename("Artist - Title.mp3", SanitizeProfile::Windows),
+            "Artist - Title.mp3"
+        );
+        assert_eq!(
+            sanitize_filename("notes...", SanitizeProfile::Windows),
+            "notes"
+        );
+        assert_eq!(
+            sanitize_filename("CON.txt", SanitizeProfile::Windows),
+            "CON_.txt"
+        );
+        assert_eq!(sanitize_filename("lpt1", SanitizeProfile::Windows), "lpt1_");
+        assert_eq!(
+            sanitize_filename("a<b>c|d.txt", SanitizeProfile::Windows),
+            "a_b_c_d.txt"
+        );
+    }
+
+    #[test]
+    fn test_fat_profile() {
+        assert_eq!(
+            sanitize_filename("C++ [draft];v=2.txt", SanitizeProfile::Fat),
+            "C draft_v_2.txt"
+        );
+    }
+
+    #[test]
+    fn test_url_profile() {
+        assert_eq!(
+            sanitize_filename("Mein Urlaub & Strand (2024).JPG", SanitizeProfile::Url),
+            "mein-urlaub-strand-2024.jpg"
+        );
+        assert_eq!(
+            sanitize_filename("Größe__Test.html", SanitizeProfile::Url),
+            "groesse-test.html"
+        );
+    }
+
+    #[test]
+    fn test_empty_and_long_names() {
+        assert_eq!(
+            sanitize_filename("???.txt", SanitizeProfile::Posix),
+            "_.txt"
+        );
+        let long = format!("{}.txt", "a".repeat(300));
+        let sanitized = sanitize_filename(&long, SanitizeProfile::Posix);
+        assert_eq!(sanitized.len(), MAX_NAME_LEN);
+        assert!(sanitized.ends_with(".txt"));
+    }
+
+    #[test]
+    fn test_check_filename() {
+        assert_eq!(check_filename("ok.txt"), None);
+        assert!(check_filename("..").is_some());
+        assert!(check_filename("a/b").is_some());
+        assert!(check_filename("tab\there").is_some());
+        assert!(check_filename(&"a".repeat(256)).is_some());
+    }
+}
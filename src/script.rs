@@ -0,0 +1,260 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope, AST};
+
+use crate::config::Config;
+use crate::template::{format_local_time, validate_date_format};
+
+/// Upper bound for the work of one script run, so an endless loop cannot hang the preview
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// What a script gets to know about a file
+pub struct ScriptContext<'a> {
+    /// Current name (output of the previous step)
+    pub name: &'a str,
+    pub stem: &'a str,
+    /// Extension without the dot, empty if there is none
+    pub ext: &'a str,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub counter: i64,
+}
+
+/// A compiled rename script
+pub struct RenameScript {
+    engine: Engine,
+    ast: AST,
+}
+
+impl RenameScript {
+    /// Load a script by name from the scripts directory ("iso-date" ->
+    /// scripts/iso-date.rhai) or from a path
+    pub fn load(name: &str) -> Result<Self> {
+        let path = script_path(name)?;
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Skript nicht lesbar: {} ({})", path.display(), e))?;
+        Self::compile(&source)
+    }
+
+    pub fn compile(source: &str) -> Result<Self> {
+        let engine = create_engine();
+        let ast = engine
+            .compile(source)
+            .map_err(|e| anyhow!("Fehler im Skript: {}", e))?;
+        Ok(Self { engine, ast })
+    }
+
+    /// Run the script for a file, the value of the last expression is the new name
+    pub fn run(&self, ctx: &ScriptContext) -> Result<String> {
+        let mut scope = Scope::new();
+        scope.push("name", ctx.name.to_string());
+        scope.push("stem", ctx.stem.to_string());
+        scope.push("ext", ctx.ext.to_string());
+        scope.push("size", ctx.size as i64);
+        scope.push(
+            "mtime",
+            ctx.modified
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(Dynamic::UNIT, |d| Dynamic::from(d.as_secs() as i64)),
+        );
+        scope.push("counter", ctx.counter);
+
+        let result: Dynamic = self
+            .engine
+            .eval_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|e| anyhow!("Skriptfehler: {}", e))?;
+
+        if result.is_unit() {
+            return Err(anyhow!("Skript liefert keinen Namen"));
+        }
+        let name = result.to_string();
+        if name.is_empty() {
+            return Err(anyhow!("Skript liefert einen leeren Namen"));
+        }
+        Ok(name)
+    }
+}
+
+/// Resolve a script name to its file
+fn script_path(name: &str) -> Result<PathBuf> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(anyhow!("Kein Skript angegeben"));
+    }
+    let path = Path::new(name);
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+
+    let dir = Config::scripts_dir().ok_or_else(|| anyhow!("Kein Konfigurationsverzeichnis"))?;
+    let file = if name.ends_with(".rhai") {
+        dir.join(name)
+    } else {
+        dir.join(format!("{}.rhai", name))
+    };
+    if file.is_file() {
+        Ok(file)
+    } else {
+        Err(anyhow!("Skript nicht gefunden: {}", file.display()))
+    }
+}
+
+/// Names of the scripts in the scripts directory
+pub fn list_scripts() -> Vec<String> {
+    let Some(dir) = Config::scripts_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "rhai" {
+                return None;
+            }
+            Some(path.file_stem()?.to_string_lossy().to_string())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+fn compile_regex(pattern: &str) -> Result<Regex, Box<EvalAltResult>> {
+    Regex::new(pattern).map_err(|e| format!("Ungueltiger Regex: {}", e).into())
+}
+
+/// Engine with the helper functions for rename scripts
+fn create_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    // regex_match("IMG_1", "\\d+") -> true
+    engine.register_fn(
+        "regex_match",
+        |text: &str, pattern: &str| -> Result<bool, Box<EvalAltResult>> {
+            Ok(compile_regex(pattern)?.is_match(text))
+        },
+    );
+    // regex_replace("IMG_1", "IMG_(\\d+)", "photo_$1") -> "photo_1"
+    engine.register_fn(
+        "regex_replace",
+        |text: &str, pattern: &str, replacement: &str| -> Result<String, Box<EvalAltResult>> {
+            Ok(compile_regex(pattern)?
+                .replace_all(text, replacement)
+                .to_string())
+        },
+    );
+    // regex_captures("a 12", "(\\w) (\\d+)") -> ["a 12", "a", "12"], [] without match
+    engine.register_fn(
+        "regex_captures",
+        |text: &str, pattern: &str| -> Result<Array, Box<EvalAltResult>> {
+            let captures = compile_regex(pattern)?
+                .captures(text)
+                .map(|caps| {
+                    caps.iter()
+                        .map(|m| Dynamic::from(m.map_or("", |m| m.as_str()).to_string()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            Ok(captures)
+        },
+    );
+    // format_date(mtime, "%Y-%m-%d") -> "2024-05-01" (local time)
+    engine.register_fn(
+        "format_date",
+        |seconds: i64, format: &str| -> Result<String, Box<EvalAltResult>> {
+            validate_date_format(format).map_err(|e| e.to_string())?;
+            let time = UNIX_EPOCH + Duration::from_secs(seconds.max(0) as u64);
+            Ok(format_local_time(time, format))
+        },
+    );
+
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str, name: &str) -> Result<String> {
+        let (stem, ext) = name.rsplit_once('.').unwrap_or((name, ""));
+        let ctx = ScriptContext {
+            name,
+            stem,
+            ext,
+            size: 2048,
+            modified: Some(UNIX_EPOCH + Duration::from_secs(86400 * 400)),
+            counter: 7,
+        };
+        RenameScript::compile(source)?.run(&ctx)
+    }
+
+    #[test]
+    fn test_script_variables() {
+        assert_eq!(
+            run(r#"`${counter}_${stem.to_upper()}.${ext}`"#, "foto.jpg").unwrap(),
+            "7_FOTO.jpg"
+        );
+        assert_eq!(
+            run(
+                r#"if size > 1024 { "gross_" + name } else { name }"#,
+                "a.txt"
+            )
+            .unwrap(),
+            "gross_a.txt"
+        );
+        assert_eq!(
+            run(r#"format_date(mtime, "%Y") + "_" + name"#, "a.txt").unwrap(),
+            "1971_a.txt"
+        );
+    }
+
+    #[test]
+    fn test_script_regex_helpers() {
+        let iso_date = r#"
+            let c = regex_captures(stem, "(\\d{2})\\.(\\d{2})\\.(\\d{4})");
+            if c.is_empty() {
+                name
+            } else {
+                let rest = stem;
+                rest.replace(c[0], "");
+                rest.trim();
+                `${c[3]}-${c[2]}-${c[1]} ${rest}.${ext}`
+            }
+        "#;
+        assert_eq!(
+            run(iso_date, "Rechnung 24.12.2023.pdf").unwrap(),
+            "2023-12-24 Rechnung.pdf"
+        );
+        assert_eq!(run(iso_date, "Notizen.txt").unwrap(), "Notizen.txt");
+        assert_eq!(
+            run(
+                r#"regex_replace(name, "^IMG_(\\d+)", "photo_$1")"#,
+                "IMG_12.jpg"
+            )
+            .unwrap(),
+            "photo_12.jpg"
+        );
+        assert!(run(r#"regex_match(name, "(")"#, "a.txt").is_err());
+    }
+
+    #[test]
+    fn test_script_errors() {
+        assert!(RenameScript::compile("let x = ;").is_err());
+        assert!(run("let x = 1;", "a.txt")
+            .unwrap_err()
+            .to_string()
+            .contains("keinen Namen"));
+        assert!(run("loop { }", "a.txt").is_err());
+        assert!(run(r#"throw "kaputt""#, "a.txt")
+            .unwrap_err()
+            .to_string()
+            .contains("kaputt"));
+    }
+}
@@ -0,0 +1,229 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Number of bytes read from the start of a file for detection
+const SNIFF_LEN: usize = 512;
+
+/// A file type recognized by its magic bytes
+#[derive(Debug, PartialEq, Eq)]
+pub struct FileType {
+    /// Human readable name shown in the preview
+    pub name: &'static str,
+    /// Extension proposed for files of this type
+    pub extension: &'static str,
+    /// Other extensions that are fine for this type (e.g. docx for ZIP)
+    pub aliases: &'static [&'static str],
+}
+
+impl FileType {
+    /// Check if an extension (without dot) fits this type.
+    /// For multi-part extensions like "tar.gz" the last part is checked too.
+    pub fn matches(&self, extension: &str) -> bool {
+        let extension = extension.to_lowercase();
+        let last = extension.rsplit('.').next().unwrap_or_default();
+        [extension.as_str(), last]
+            .iter()
+            .any(|ext| !ext.is_empty() && (*ext == self.extension || self.aliases.contains(ext)))
+    }
+}
+
+const fn file_type(
+    name: &'static str,
+    extension: &'static str,
+    aliases: &'static [&'static str],
+) -> FileType {
+    FileType {
+        name,
+        extension,
+        aliases,
+    }
+}
+
+const PNG: FileType = file_type("PNG", "png", &[]);
+const JPEG: FileType = file_type("JPEG", "jpg", &["jpeg", "jpe", "jfif"]);
+const GIF: FileType = file_type("GIF", "gif", &[]);
+const WEBP: FileType = file_type("WebP", "webp", &[]);
+const BMP: FileType = file_type("BMP", "bmp", &["dib"]);
+const ICO: FileType = file_type("ICO", "ico", &["cur"]);
+const TIFF: FileType = file_type(
+    "TIFF",
+    "tiff",
+    &["tif", "dng", "cr2", "nef", "arw", "orf", "rw2", "pef"],
+);
+const PSD: FileType = file_type("PSD", "psd", &[]);
+const HEIC: FileType = file_type("HEIC", "heic", &["heif", "hif"]);
+const AVIF: FileType = file_type("AVIF", "avif", &[]);
+const PDF: FileType = file_type("PDF", "pdf", &["ai"]);
+const ZIP: FileType = file_type(
+    "ZIP",
+    "zip",
+    &[
+        "docx", "xlsx", "pptx", "odt", "ods", "odp", "odg", "epub", "jar", "war", "apk", "aab",
+        "xpi", "cbz", "kmz", "ipa", "whl", "nupkg", "3mf", "vsix",
+    ],
+);
+const GZIP: FileType = file_type("GZIP", "gz", &["tgz", "svgz"]);
+const BZIP2: FileType = file_type("BZIP2", "bz2", &["tbz2", "tbz"]);
+const XZ: FileType = file_type("XZ", "xz", &["txz"]);
+const ZSTD: FileType = file_type("Zstandard", "zst", &["tzst"]);
+const SEVEN_ZIP: FileType = file_type("7-Zip", "7z", &[]);
+const RAR: FileType = file_type("RAR", "rar", &["cbr"]);
+const TAR: FileType = file_type("TAR", "tar", &[]);
+const MP4: FileType = file_type("MP4", "mp4", &["m4v", "m4a", "m4b", "m4p", "3gp", "3g2"]);
+const M4A: FileType = file_type("M4A", "m4a", &["m4b", "m4p", "mp4"]);
+const MOV: FileType = file_type("QuickTime", "mov", &["qt"]);
+const MKV: FileType = file_type("Matroska", "mkv", &["mka", "mks", "mk3d", "webm"]);
+const AVI: FileType = file_type("AVI", "avi", &[]);
+const WAV: FileType = file_type("WAV", "wav", &["wave"]);
+const MP3: FileType = file_type("MP3", "mp3", &[]);
+const FLAC: FileType = file_type("FLAC", "flac", &[]);
+const OGG: FileType = file_type("Ogg", "ogg", &["oga", "ogv", "ogx", "opus", "spx"]);
+const SQLITE: FileType = file_type("SQLite", "sqlite", &["db", "sqlite3", "db3"]);
+const WASM: FileType = file_type("WebAssembly", "wasm", &[]);
+
+/// Detect the type of a file from its first bytes
+pub fn detect_file_type(path: &Path) -> Option<&'static FileType> {
+    let mut buffer = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)
+        .ok()?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut buffer)
+        .ok()?;
+    detect_bytes(&buffer)
+}
+
+/// Detect a file type from the start of its content
+pub fn detect_bytes(data: &[u8]) -> Option<&'static FileType> {
+    let starts = |magic: &[u8]| data.starts_with(magic);
+    let at = |offset: usize, magic: &[u8]| data.get(offset..).is_some_and(|d| d.starts_with(magic));
+
+    let file_type = if starts(b"\x89PNG\r\n\x1a\n") {
+        &PNG
+    } else if starts(&[0xFF, 0xD8, 0xFF]) {
+        &JPEG
+    } else if starts(b"GIF87a") || starts(b"GIF89a") {
+        &GIF
+    } else if starts(b"RIFF") && at(8, b"WEBP") {
+        &WEBP
+    } else if starts(b"RIFF") && at(8, b"WAVE") {
+        &WAV
+    } else if starts(b"RIFF") && at(8, b"AVI ") {
+        &AVI
+    } else if starts(b"II*\0") || starts(b"MM\0*") {
+        &TIFF
+    } else if starts(b"8BPS") {
+        &PSD
+    } else if starts(b"%PDF-") {
+        &PDF
+    } else if starts(b"PK\x03\x04") || starts(b"PK\x05\x06") {
+        &ZIP
+    } else if starts(&[0x1F, 0x8B]) {
+        &GZIP
+    } else if starts(b"BZh") {
+        &BZIP2
+    } else if starts(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+        &XZ
+    } else if starts(&[0x28, 0xB5, 0x2F, 0xFD]) {
+        &ZSTD
+    } else if starts(&[b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C]) {
+        &SEVEN_ZIP
+    } else if starts(b"Rar!\x1a\x07") {
+        &RAR
+    } else if at(4, b"ftyp") {
+        ftyp_type(data.get(8..12)?)
+    } else if starts(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        &MKV
+    } else if starts(b"fLaC") {
+        &FLAC
+    } else if starts(b"OggS") {
+        &OGG
+    } else if starts(b"ID3") || is_mpeg_audio_frame(data) {
+        &MP3
+    } else if starts(b"SQLite format 3\0") {
+        &SQLITE
+    } else if starts(b"\0asm") {
+        &WASM
+    } else if at(257, b"ustar") {
+        &TAR
+    } else if starts(b"BM") && data.len() >= 14 && at(6, &[0, 0, 0, 0]) {
+        &BMP
+    } else if starts(&[0, 0, 1, 0]) && data.get(4..6).is_some_and(|count| count != [0, 0]) {
+        &ICO
+    } else {
+        return None;
+    };
+
+    Some(file_type)
+}
+
+/// Pick the type for an ISO base media file from its major brand
+fn ftyp_type(brand: &[u8]) -> &'static FileType {
+    match brand {
+        b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"hevx" | b"mif1" | b"msf1" => &HEIC,
+        b"avif" | b"avis" => &AVIF,
+        b"qt  " => &MOV,
+        b"M4A " | b"M4B " | b"M4P " => &M4A,
+        _ => &MP4,
+    }
+}
+
+/// MPEG audio without ID3 tag starts with an 11 bit frame sync
+fn is_mpeg_audio_frame(data: &[u8]) -> bool {
+    match data {
+        [0xFF, second, ..] => {
+            // Sync bits set, layer not reserved
+            second & 0xE0 == 0xE0 && second & 0x06 != 0
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_common_types() {
+        assert_eq!(detect_bytes(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some(&PNG));
+        assert_eq!(
+            detect_bytes(&[0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10]),
+            Some(&JPEG)
+        );
+        assert_eq!(detect_bytes(b"GIF89a\x01\0"), Some(&GIF));
+        assert_eq!(detect_bytes(b"%PDF-1.7\n"), Some(&PDF));
+        assert_eq!(detect_bytes(b"PK\x03\x04\x14\0"), Some(&ZIP));
+        assert_eq!(detect_bytes(b"RIFF\x24\0\0\0WEBPVP8 "), Some(&WEBP));
+        assert_eq!(detect_bytes(b"ID3\x04\0\0"), Some(&MP3));
+        assert_eq!(detect_bytes(&[0xFF, 0xFB, 0x90, 0x64]), Some(&MP3));
+        assert_eq!(detect_bytes(b"hello world"), None);
+        assert_eq!(detect_bytes(b""), None);
+    }
+
+    #[test]
+    fn test_detect_ftyp_brands() {
+        assert_eq!(detect_bytes(b"\0\0\0\x18ftypisom\0\0\x02\0"), Some(&MP4));
+        assert_eq!(detect_bytes(b"\0\0\0\x18ftypheic\0\0\0\0"), Some(&HEIC));
+        assert_eq!(detect_bytes(b"\0\0\0\x14ftypqt  \0\0\0\0"), Some(&MOV));
+        assert_eq!(detect_bytes(b"\0\0\0\x1cftypM4A \0\0\0\0"), Some(&M4A));
+    }
+
+    #[test]
+    fn test_detect_tar() {
+        let mut data = vec![0u8; 512];
+        data[..8].copy_from_slice(b"file.txt");
+        data[257..263].copy_from_slice(b"ustar\0");
+        assert_eq!(detect_bytes(&data), Some(&TAR));
+    }
+
+    #[test]
+    fn test_matches_extension() {
+        assert!(JPEG.matches("jpg"));
+        assert!(JPEG.matches("JPEG"));
+        assert!(!JPEG.matches("png"));
+        assert!(!JPEG.matches(""));
+        assert!(ZIP.matches("docx"));
+        assert!(GZIP.matches("tar.gz"));
+        assert!(MP4.matches("m4v"));
+    }
+}
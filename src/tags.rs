@@ -0,0 +1,605 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::exif::{find_box, read_box_header};
+
+/// Maximum size of a tag block that is read into memory
+const TAG_READ_LIMIT: u64 = 16 * 1024 * 1024;
+
+/// Maximum number of Ogg pages scanned for the comment header
+const OGG_MAX_PAGES: usize = 64;
+
+/// Tags of an audio file that can be used in new names
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AudioTags {
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub title: Option<String>,
+    pub track: Option<u32>,
+    pub year: Option<String>,
+}
+
+impl AudioTags {
+    fn is_empty(&self) -> bool {
+        self.artist.is_none()
+            && self.album.is_none()
+            && self.title.is_none()
+            && self.track.is_none()
+            && self.year.is_none()
+    }
+
+    /// Set a field by its (lowercase) tag name, keeping values that are already set
+    fn set(&mut self, key: &str, value: &str) {
+        let value = value.trim_matches(char::from(0)).trim();
+        if value.is_empty() {
+            return;
+        }
+        match key {
+            "artist" => fill(&mut self.artist, Some(value.to_string())),
+            "album" => fill(&mut self.album, Some(value.to_string())),
+            "title" => fill(&mut self.title, Some(value.to_string())),
+            "track" | "tracknumber" => fill(&mut self.track, parse_track(value)),
+            "year" | "date" => fill(&mut self.year, parse_year(value)),
+            _ => {}
+        }
+    }
+
+    /// Fill missing fields from another set of tags
+    fn merge(&mut self, other: AudioTags) {
+        fill(&mut self.artist, other.artist);
+        fill(&mut self.album, other.album);
+        fill(&mut self.title, other.title);
+        fill(&mut self.track, other.track);
+        fill(&mut self.year, other.year);
+    }
+}
+
+fn fill<T>(field: &mut Option<T>, value: Option<T>) {
+    if field.is_none() {
+        *field = value;
+    }
+}
+
+/// Parse "3" or "3/12" into the track number
+fn parse_track(value: &str) -> Option<u32> {
+    value.split('/').next()?.trim().parse().ok()
+}
+
+/// Take the year from "2019", "2019-05-01" or similar
+fn parse_year(value: &str) -> Option<String> {
+    let year = value.get(..4)?;
+    year.chars()
+        .all(|c| c.is_ascii_digit())
+        .then(|| year.to_string())
+}
+
+/// Read tags from an MP3 (ID3v2/ID3v1), FLAC, Ogg Vorbis/Opus or MP4/M4A file
+pub fn read_tags(path: &Path) -> Option<AudioTags> {
+    let mut file = File::open(path).ok()?;
+    tags_from(&mut file)
+}
+
+/// Read tags from the content of an audio file
+fn tags_from<R: Read + Seek>(file: &mut R) -> Option<AudioTags> {
+    let mut header = Vec::new();
+    file.by_ref().take(12).read_to_end(&mut header).ok()?;
+
+    let mut tags = if header.starts_with(b"ID3") {
+        read_id3v2(file)
+    } else if header.starts_with(b"fLaC") {
+        read_flac(file, 4)
+    } else if header.starts_with(b"OggS") {
+        read_ogg(file)
+    } else if header.get(4..8) == Some(b"ftyp") {
+        read_mp4(file)
+    } else {
+        None
+    }
+    .unwrap_or_default();
+
+    // Old MP3 files may only have an ID3v1 tag at the end
+    if let Some(v1) = read_id3v1(file) {
+        tags.merge(v1);
+    }
+
+    (!tags.is_empty()).then_some(tags)
+}
+
+/// Decode a 28 bit "syncsafe" integer
+fn syncsafe(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0, |acc, &b| (acc << 7) | u32::from(b & 0x7F))
+}
+
+fn be_uint(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |acc, &b| (acc << 8) | u32::from(b))
+}
+
+/// Undo ID3 unsynchronisation (0xFF 0x00 -> 0xFF)
+fn remove_unsync(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(data.len());
+    let mut previous = 0u8;
+    for &b in data {
+        if !(previous == 0xFF && b == 0x00) {
+            result.push(b);
+        }
+        previous = b;
+    }
+    result
+}
+
+/// Parse an ID3v2.2/2.3/2.4 tag at the start of the file.
+/// FLAC files with a leading ID3 tag continue with the FLAC metadata.
+fn read_id3v2<R: Read + Seek>(file: &mut R) -> Option<AudioTags> {
+    file.seek(SeekFrom::Start(0)).ok()?;
+    let mut header = [0u8; 10];
+    file.read_exact(&mut header).ok()?;
+    let version = header[3];
+    let flags = header[5];
+    let size = u64::from(syncsafe(&header[6..10]));
+    if size > TAG_READ_LIMIT || !(2..=4).contains(&version) {
+        return None;
+    }
+
+    let mut data = vec![0u8; size as usize];
+    file.read_exact(&mut data).ok()?;
+    if flags & 0x80 != 0 && version < 4 {
+        data = remove_unsync(&data);
+    }
+
+    let mut pos = 0;
+    if flags & 0x40 != 0 {
+        pos = match version {
+            3 => 4 + be_uint(data.get(..4)?) as usize,
+            4 => syncsafe(data.get(..4)?) as usize,
+            _ => 0,
+        };
+    }
+
+    let (id_len, header_len) = if version == 2 { (3, 6) } else { (4, 10) };
+    let mut tags = AudioTags::default();
+
+    while pos + header_len <= data.len() {
+        let frame = &data[pos..pos + header_len];
+        // Padding
+        if frame[0] == 0 {
+            break;
+        }
+        let id = String::from_utf8_lossy(&frame[..id_len]).to_string();
+        let size = match version {
+            2 => be_uint(&frame[3..6]),
+            3 => be_uint(&frame[4..8]),
+            _ => syncsafe(&frame[4..8]),
+        } as usize;
+        let frame_flags = if version > 2 {
+            be_uint(&frame[8..10]) as u16
+        } else {
+            0
+        };
+        pos += header_len;
+        let Some(content) = data.get(pos..pos + size) else {
+            break;
+        };
+        pos += size;
+
+        let key = match id.as_str() {
+            "TPE1" | "TP1" => "artist",
+            "TALB" | "TAL" => "album",
+            "TIT2" | "TT2" => "title",
+            "TRCK" | "TRK" => "track",
+            "TYER" | "TYE" | "TDRC" => "year",
+            _ => continue,
+        };
+
+        let content = match version {
+            // Compressed or encrypted frames are skipped
+            3 if frame_flags & 0x00C0 != 0 => continue,
+            4 if frame_flags & 0x000C != 0 => continue,
+            4 => {
+                let content = if frame_flags & 0x0001 != 0 {
+                    content.get(4..).unwrap_or_default()
+                } else {
+                    content
+                };
+                if frame_flags & 0x0002 != 0 {
+                    remove_unsync(content)
+                } else {
+                    content.to_vec()
+                }
+            }
+            _ => content.to_vec(),
+        };
+
+        if let Some(text) = decode_id3_text(&content) {
+            tags.set(key, &text);
+        }
+    }
+
+    if tags.is_empty() {
+        let mut magic = [0u8; 4];
+        file.seek(SeekFrom::Start(10 + size)).ok()?;
+        if file.read_exact(&mut magic).is_ok() && &magic == b"fLaC" {
+            return read_flac(file, 10 + size + 4);
+        }
+    }
+
+    Some(tags)
+}
+
+/// Decode the text of an ID3 text frame (first value only)
+fn decode_id3_text(content: &[u8]) -> Option<String> {
+    let (&encoding, text) = content.split_first()?;
+    let text = match encoding {
+        0 => text.iter().map(|&b| b as char).collect(),
+        1 | 2 => {
+            let (big_endian, text) = match text {
+                [0xFE, 0xFF, rest @ ..] => (true, rest),
+                [0xFF, 0xFE, rest @ ..] => (false, rest),
+                _ => (encoding == 2, text),
+            };
+            let units: Vec<u16> = text
+                .chunks_exact(2)
+                .map(|c| {
+                    if big_endian {
+                        u16::from_be_bytes([c[0], c[1]])
+                    } else {
+                        u16::from_le_bytes([c[0], c[1]])
+                    }
+                })
+                .take_while(|&u| u != 0)
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        3 => String::from_utf8_lossy(text).to_string(),
+        _ => return None,
+    };
+    Some(text.split('\0').next().unwrap_or_default().to_string())
+}
+
+/// Parse an ID3v1 tag in the last 128 bytes of the file
+fn read_id3v1<R: Read + Seek>(file: &mut R) -> Option<AudioTags> {
+    file.seek(SeekFrom::End(-128)).ok()?;
+    let mut data = [0u8; 128];
+    file.read_exact(&mut data).ok()?;
+    if !data.starts_with(b"TAG") {
+        return None;
+    }
+
+    let text = |range: std::ops::Range<usize>| -> String {
+        data[range]
+            .iter()
+            .take_while(|&&b| b != 0)
+            .map(|&b| b as char)
+            .collect()
+    };
+
+    let mut tags = AudioTags::default();
+    tags.set("title", &text(3..33));
+    tags.set("artist", &text(33..63));
+    tags.set("album", &text(63..93));
+    tags.set("year", &text(93..97));
+    // ID3v1.1 stores the track in the last byte of the comment
+    if data[125] == 0 && data[126] != 0 {
+        tags.track = Some(u32::from(data[126]));
+    }
+    Some(tags)
+}
+
+/// Find the VORBIS_COMMENT block in the FLAC metadata starting at `start`
+fn read_flac<R: Read + Seek>(file: &mut R, mut start: u64) -> Option<AudioTags> {
+    const VORBIS_COMMENT: u8 = 4;
+
+    loop {
+        file.seek(SeekFrom::Start(start)).ok()?;
+        let mut header = [0u8; 4];
+        file.read_exact(&mut header).ok()?;
+        let last = header[0] & 0x80 != 0;
+        let len = u64::from(be_uint(&header[1..4]));
+
+        if header[0] & 0x7F == VORBIS_COMMENT {
+            if len > TAG_READ_LIMIT {
+                return None;
+            }
+            let mut data = vec![0u8; len as usize];
+            file.read_exact(&mut data).ok()?;
+            return parse_vorbis_comments(&data);
+        }
+        if last {
+            return None;
+        }
+        start += 4 + len;
+    }
+}
+
+fn le_u32(data: &[u8], pos: usize) -> Option<u32> {
+    let b = data.get(pos..pos.checked_add(4)?)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Parse a Vorbis comment block (vendor string followed by KEY=value entries)
+fn parse_vorbis_comments(data: &[u8]) -> Option<AudioTags> {
+    let vendor_len = le_u32(data, 0)? as usize;
+    let mut pos = 4 + vendor_len;
+    let count = le_u32(data, pos)?;
+    pos += 4;
+
+    let mut tags = AudioTags::default();
+    for _ in 0..count {
+        let len = le_u32(data, pos)? as usize;
+        pos += 4;
+        let entry = data.get(pos..pos.checked_add(len)?)?;
+        pos += len;
+
+        let entry = String::from_utf8_lossy(entry);
+        if let Some((key, value)) = entry.split_once('=') {
+            tags.set(&key.to_lowercase(), value);
+        }
+    }
+    Some(tags)
+}
+
+/// Read the comment header (second packet) of an Ogg Vorbis or Opus stream
+fn read_ogg<R: Read + Seek>(file: &mut R) -> Option<AudioTags> {
+    file.seek(SeekFrom::Start(0)).ok()?;
+
+    let mut packets: Vec<Vec<u8>> = Vec::new();
+    let mut current = Vec::new();
+    let mut serial = None;
+
+    for _ in 0..OGG_MAX_PAGES {
+        let mut header = [0u8; 27];
+        file.read_exact(&mut header).ok()?;
+        if !header.starts_with(b"OggS") {
+            return None;
+        }
+        let page_serial = le_u32(&header, 14)?;
+        let mut lacing = vec![0u8; header[26] as usize];
+        file.read_exact(&mut lacing).ok()?;
+        let mut body = vec![0u8; lacing.iter().map(|&l| l as usize).sum()];
+        file.read_exact(&mut body).ok()?;
+
+        // Only look at the first logical stream
+        if *serial.get_or_insert(page_serial) != page_serial {
+            continue;
+        }
+
+        let mut offset = 0;
+        for &len in &lacing {
+            current.extend_from_slice(&body[offset..offset + len as usize]);
+            offset += len as usize;
+            if len < 255 {
+                packets.push(std::mem::take(&mut current));
+            }
+        }
+        if current.len() as u64 > TAG_READ_LIMIT {
+            return None;
+        }
+
+        if let Some(comments) = packets.get(1) {
+            let data = comments
+                .strip_prefix(b"\x03vorbis")
+                .or_else(|| comments.strip_prefix(b"OpusTags"))?;
+            return parse_vorbis_comments(data);
+        }
+    }
+    None
+}
+
+/// Read the iTunes style metadata (moov/udta/meta/ilst) of an MP4 file
+fn read_mp4<R: Read + Seek>(file: &mut R) -> Option<AudioTags> {
+    let file_len = file.seek(SeekFrom::End(0)).ok()?;
+    let moov = find_box(file, 0, file_len, b"moov")?;
+    let udta = find_box(file, moov.content_start, moov.end, b"udta")?;
+    let meta = find_box(file, udta.content_start, udta.end, b"meta")?;
+
+    // iTunes writes meta as a full box, QuickTime without version and flags
+    let mut children = meta.content_start;
+    if read_box_header(file, children, meta.end).is_none_or(|first| &first.kind != b"hdlr") {
+        children += 4;
+    }
+    let ilst = find_box(file, children, meta.end, b"ilst")?;
+
+    let mut tags = AudioTags::default();
+    let mut pos = ilst.content_start;
+    while let Some(item) = read_box_header(file, pos, ilst.end) {
+        if item.end <= pos {
+            break;
+        }
+        pos = item.end;
+
+        let key = match &item.kind {
+            b"\xa9ART" => "artist",
+            b"\xa9alb" => "album",
+            b"\xa9nam" => "title",
+            b"\xa9day" => "year",
+            b"trkn" => "track",
+            _ => continue,
+        };
+        let Some(data) = find_box(file, item.content_start, item.end, b"data") else {
+            continue;
+        };
+        let len = data.end.saturating_sub(data.content_start);
+        if !(8..=64 * 1024).contains(&len) {
+            continue;
+        }
+        let mut content = vec![0u8; len as usize];
+        file.seek(SeekFrom::Start(data.content_start)).ok()?;
+        file.read_exact(&mut content).ok()?;
+        // Skip type and locale
+        let value = &content[8..];
+
+        if key == "track" {
+            if let Some(track) = value.get(2..4) {
+                fill(&mut tags.track, Some(be_uint(track)).filter(|&t| t > 0));
+            }
+        } else {
+            tags.set(key, &String::from_utf8_lossy(value));
+        }
+    }
+    Some(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(data: &[u8]) -> Option<AudioTags> {
+        tags_from(&mut std::io::Cursor::new(data))
+    }
+
+    fn id3_frame(id: &str, text: &str) -> Vec<u8> {
+        let mut content = vec![3u8];
+        content.extend_from_slice(text.as_bytes());
+        let mut frame = id.as_bytes().to_vec();
+        frame.extend_from_slice(&(content.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(&content);
+        frame
+    }
+
+    fn vorbis_comments(entries: &[&str]) -> Vec<u8> {
+        let mut data = 6u32.to_le_bytes().to_vec();
+        data.extend_from_slice(b"vendor");
+        data.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        for entry in entries {
+            data.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+            data.extend_from_slice(entry.as_bytes());
+        }
+        data
+    }
+
+    fn mp4_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut data = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(kind);
+        data.extend_from_slice(content);
+        data
+    }
+
+    fn mp4_item(kind: &[u8; 4], value: &[u8]) -> Vec<u8> {
+        let mut data = vec![0, 0, 0, 1, 0, 0, 0, 0];
+        data.extend_from_slice(value);
+        mp4_box(kind, &mp4_box(b"data", &data))
+    }
+
+    #[test]
+    fn test_read_id3v2() {
+        let mut frames = id3_frame("TPE1", "Daft Punk");
+        frames.extend(id3_frame("TIT2", "One More Time"));
+        frames.extend(id3_frame("TRCK", "3/14"));
+        frames.extend(id3_frame("TDRC", "2001-03-12"));
+        frames.extend_from_slice(&[0; 16]);
+
+        let mut file = b"ID3\x04\x00\x00".to_vec();
+        let size = frames.len() as u32;
+        file.extend_from_slice(&[
+            (size >> 21 & 0x7F) as u8,
+            (size >> 14 & 0x7F) as u8,
+            (size >> 7 & 0x7F) as u8,
+            (size & 0x7F) as u8,
+        ]);
+        file.extend_from_slice(&frames);
+        file.extend_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+
+        let tags = read(&file).unwrap();
+        assert_eq!(tags.artist.as_deref(), Some("Daft Punk"));
+        assert_eq!(tags.title.as_deref(), Some("One More Time"));
+        assert_eq!(tags.track, Some(3));
+        assert_eq!(tags.year.as_deref(), Some("2001"));
+        assert_eq!(tags.album, None);
+    }
+
+    #[test]
+    fn test_read_id3v1() {
+        let mut file = vec![0xFF, 0xFB, 0x90, 0x00];
+        let mut tag = [0u8; 128];
+        tag[..3].copy_from_slice(b"TAG");
+        tag[3..8].copy_from_slice(b"Title");
+        tag[33..39].copy_from_slice(b"Artist");
+        tag[93..97].copy_from_slice(b"1999");
+        tag[126] = 7;
+        file.extend_from_slice(&tag);
+
+        let tags = read(&file).unwrap();
+        assert_eq!(tags.title.as_deref(), Some("Title"));
+        assert_eq!(tags.artist.as_deref(), Some("Artist"));
+        assert_eq!(tags.year.as_deref(), Some("1999"));
+        assert_eq!(tags.track, Some(7));
+    }
+
+    #[test]
+    fn test_read_flac() {
+        let comments =
+            vorbis_comments(&["ARTIST=Nina Simone", "album=Pastel Blues", "TRACKNUMBER=05"]);
+        let mut file = b"fLaC".to_vec();
+        // STREAMINFO (not last)
+        file.extend_from_slice(&[0x00, 0x00, 0x00, 0x22]);
+        file.extend_from_slice(&[0; 34]);
+        // VORBIS_COMMENT (last)
+        file.push(0x84);
+        file.extend_from_slice(&(comments.len() as u32).to_be_bytes()[1..]);
+        file.extend_from_slice(&comments);
+
+        let tags = read(&file).unwrap();
+        assert_eq!(tags.artist.as_deref(), Some("Nina Simone"));
+        assert_eq!(tags.album.as_deref(), Some("Pastel Blues"));
+        assert_eq!(tags.track, Some(5));
+    }
+
+    #[test]
+    fn test_read_ogg() {
+        fn page(serial: u32, packet: &[u8]) -> Vec<u8> {
+            let mut page = b"OggS\x00\x00".to_vec();
+            page.extend_from_slice(&[0; 8]);
+            page.extend_from_slice(&serial.to_le_bytes());
+            page.extend_from_slice(&[0; 8]);
+            let mut lacing = vec![255u8; packet.len() / 255];
+            lacing.push((packet.len() % 255) as u8);
+            page.push(lacing.len() as u8);
+            page.extend_from_slice(&lacing);
+            page.extend_from_slice(packet);
+            page
+        }
+
+        let mut comments = b"\x03vorbis".to_vec();
+        comments.extend(vorbis_comments(&["TITLE=Clair de Lune", "DATE=1905"]));
+        comments.extend_from_slice(&[b'x'; 300]);
+
+        let mut file = page(1, b"\x01vorbis-identification");
+        file.extend(page(1, &comments));
+
+        let tags = read(&file).unwrap();
+        assert_eq!(tags.title.as_deref(), Some("Clair de Lune"));
+        assert_eq!(tags.year.as_deref(), Some("1905"));
+    }
+
+    #[test]
+    fn test_read_mp4() {
+        let mut ilst = mp4_item(b"\xa9ART", b"Miles Davis");
+        ilst.extend(mp4_item(b"\xa9nam", b"So What"));
+        ilst.extend(mp4_item(b"trkn", &[0, 0, 0, 1, 0, 5, 0, 0]));
+        let ilst = mp4_box(b"ilst", &ilst);
+
+        let mut meta = vec![0, 0, 0, 0];
+        meta.extend(mp4_box(b"hdlr", &[0; 25]));
+        meta.extend(ilst);
+        let moov = mp4_box(b"moov", &mp4_box(b"udta", &mp4_box(b"meta", &meta)));
+
+        let mut file = mp4_box(b"ftyp", b"M4A \x00\x00\x00\x00");
+        file.extend(mp4_box(b"mdat", &[0; 16]));
+        file.extend(moov);
+
+        let tags = read(&file).unwrap();
+        assert_eq!(tags.artist.as_deref(), Some("Miles Davis"));
+        assert_eq!(tags.title.as_deref(), Some("So What"));
+        assert_eq!(tags.track, Some(1));
+    }
+
+    #[test]
+    fn test_no_tags() {
+        assert_eq!(read(&[0xFF, 0xFB]), None);
+        assert_eq!(parse_track("04/12"), Some(4));
+        assert_eq!(parse_year("19xx"), None);
+    }
+}
@@ -0,0 +1,456 @@
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+
+use crate::tags::AudioTags;
+
+/// Default format for the {date} token and date insertion
+pub const DEFAULT_DATE_FORMAT: &str = "%Y%m%d";
+
+/// Values a template can refer to for a single file
+#[derive(Debug, Clone)]
+pub struct TemplateContext<'a> {
+    /// Current filename (output of the previous pipeline step)
+    pub name: &'a str,
+    /// Counter value for {n}
+    pub counter: i64,
+    /// Name of the parent directory
+    pub parent: &'a str,
+    /// File size in bytes
+    pub size: u64,
+    /// Modification time
+    pub modified: Option<SystemTime>,
+    /// Audio tags (only read if the template uses tag tokens)
+    pub tags: Option<&'a AudioTags>,
+}
+
+/// Filter applied to the value of a token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Lower,
+    Upper,
+    Title,
+    Slug,
+    Trim,
+}
+
+impl Filter {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim() {
+            "lower" => Ok(Filter::Lower),
+            "upper" => Ok(Filter::Upper),
+            "title" => Ok(Filter::Title),
+            "slug" => Ok(Filter::Slug),
+            "trim" => Ok(Filter::Trim),
+            other => Err(anyhow!("Unbekannter Filter: {}", other)),
+        }
+    }
+
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            Filter::Lower => value.to_lowercase(),
+            Filter::Upper => value.to_uppercase(),
+            Filter::Title => title_words(value),
+            Filter::Slug => slugify(value),
+            Filter::Trim => value.trim().to_string(),
+        }
+    }
+}
+
+/// A token such as {n:03} or {stem|lower}
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Name,
+    Stem,
+    Ext,
+    Counter { width: usize },
+    Parent,
+    Size,
+    Date { format: String },
+    Artist,
+    Album,
+    Title,
+    Track { width: usize },
+    Year,
+}
+
+impl Token {
+    /// Tag name for tokens that come from audio tags
+    fn tag_name(&self) -> Option<&'static str> {
+        match self {
+            Token::Artist => Some("artist"),
+            Token::Album => Some("album"),
+            Token::Title => Some("title"),
+            Token::Track { .. } => Some("track"),
+            Token::Year => Some("year"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Token { token: Token, filters: Vec<Filter> },
+}
+
+/// A parsed rename template
+#[derive(Debug, Clone)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Parse a template string like `{date:%Y-%m-%d}_{stem|lower}_{n:03}.{ext}`.
+    /// Literal braces are written as `{{` and `}}`.
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = input.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut content = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        content.push(c);
+                    }
+                    if !closed {
+                        return Err(anyhow!("Ungueltiges Template: '{{' ohne '}}'"));
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(parse_token(&content)?);
+                }
+                '}' => return Err(anyhow!("Ungueltiges Template: '}}' ohne '{{'")),
+                _ => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Render the template for a single file
+    pub fn render(&self, ctx: &TemplateContext) -> String {
+        let mut result = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => result.push_str(text),
+                Segment::Token { token, filters } => {
+                    let mut value = token_value(token, ctx);
+                    for filter in filters {
+                        value = filter.apply(&value);
+                    }
+                    result.push_str(&value);
+                }
+            }
+        }
+        result
+    }
+
+    /// Check if the template uses audio tag tokens
+    pub fn uses_tags(&self) -> bool {
+        self.tag_tokens().next().is_some()
+    }
+
+    /// Names of the tag tokens that have no value for this file
+    pub fn missing_tags(&self, ctx: &TemplateContext) -> Vec<&'static str> {
+        // In template order, each tag once
+        let mut missing: Vec<&'static str> = Vec::new();
+        for name in self
+            .tag_tokens()
+            .filter(|token| tag_value(token, ctx.tags).is_none())
+            .filter_map(Token::tag_name)
+        {
+            if !missing.contains(&name) {
+                missing.push(name);
+            }
+        }
+        missing
+    }
+
+    fn tag_tokens(&self) -> impl Iterator<Item = &Token> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Token { token, .. } if token.tag_name().is_some() => Some(token),
+            _ => None,
+        })
+    }
+}
+
+/// Parse the content between braces into a token with filters
+fn parse_token(content: &str) -> Result<Segment> {
+    let mut parts = content.split('|');
+    let head = parts.next().unwrap_or("").trim();
+    let filters = parts.map(Filter::parse).collect::<Result<Vec<_>>>()?;
+
+    let (name, arg) = match head.split_once(':') {
+        Some((name, arg)) => (name.trim(), Some(arg)),
+        None => (head, None),
+    };
+
+    let token = match name {
+        "name" => Token::Name,
+        "stem" => Token::Stem,
+        "ext" => Token::Ext,
+        "parent" => Token::Parent,
+        "size" => Token::Size,
+        "n" | "num" | "counter" => Token::Counter {
+            width: parse_width(name, arg)?,
+        },
+        "artist" => Token::Artist,
+        "album" => Token::Album,
+        "title" => Token::Title,
+        "track" => Token::Track {
+            width: parse_width(name, arg)?,
+        },
+        "year" => Token::Year,
+        "date" | "mtime" => {
+            let format = arg.unwrap_or(DEFAULT_DATE_FORMAT).to_string();
+            validate_date_format(&format)?;
+            Token::Date { format }
+        }
+        "" => return Err(anyhow!("Ungueltiges Template: leerer Platzhalter {{}}")),
+        other => return Err(anyhow!("Unbekannter Platzhalter: {{{}}}", other)),
+    };
+
+    Ok(Segment::Token { token, filters })
+}
+
+/// Parse the optional zero-padding width of a number token
+fn parse_width(name: &str, arg: Option<&str>) -> Result<usize> {
+    match arg {
+        Some(w) => w
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("Ungueltige Breite fuer {{{}}}: {}", name, w)),
+        None => Ok(0),
+    }
+}
+
+/// Check that a strftime format string only contains known specifiers
+pub fn validate_date_format(format: &str) -> Result<()> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(anyhow!("Ungueltiges Datumsformat: {}", format));
+    }
+    Ok(())
+}
+
+/// Format a time in the local time zone with a (validated) strftime format
+pub fn format_local_time(time: SystemTime, format: &str) -> String {
+    DateTime::<Local>::from(time).format(format).to_string()
+}
+
+/// Format a date in the local time zone, or a zeroed placeholder of the same shape
+pub fn format_date(time: Option<SystemTime>, format: &str) -> String {
+    match time {
+        Some(time) => format_local_time(time, format),
+        None => format_local_time(SystemTime::UNIX_EPOCH, format)
+            .chars()
+            .map(|c| if c.is_ascii_digit() { '0' } else { c })
+            .collect(),
+    }
+}
+
+fn token_value(token: &Token, ctx: &TemplateContext) -> String {
+    let (stem, ext) = split_name(ctx.name);
+    match token {
+        Token::Name => ctx.name.to_string(),
+        Token::Stem => stem.to_string(),
+        Token::Ext => ext.to_string(),
+        Token::Counter { width } => format!("{:0width$}", ctx.counter, width = *width),
+        Token::Parent => ctx.parent.to_string(),
+        Token::Size => ctx.size.to_string(),
+        Token::Date { format } => format_date(ctx.modified, format),
+        _ => tag_value(token, ctx.tags).unwrap_or_default(),
+    }
+}
+
+/// Value of a tag token; path separators in tags are replaced
+fn tag_value(token: &Token, tags: Option<&AudioTags>) -> Option<String> {
+    let tags = tags?;
+    let value = match token {
+        Token::Artist => tags.artist.clone()?,
+        Token::Album => tags.album.clone()?,
+        Token::Title => tags.title.clone()?,
+        Token::Track { width } => format!("{:0>width$}", tags.track?, width = *width),
+        Token::Year => tags.year.clone()?,
+        _ => return None,
+    };
+    Some(value.replace(['/', '\\'], "-"))
+}
+
+/// Split a filename into stem and extension (without the dot)
+fn split_name(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(pos) if pos > 0 => (&name[..pos], &name[pos + 1..]),
+        _ => (name, ""),
+    }
+}
+
+/// Capitalize the first letter of every word
+fn title_words(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut capitalize_next = true;
+    for c in value.chars() {
+        if c.is_alphanumeric() {
+            if capitalize_next {
+                result.extend(c.to_uppercase());
+            } else {
+                result.extend(c.to_lowercase());
+            }
+            capitalize_next = false;
+        } else {
+            result.push(c);
+            capitalize_next = true;
+        }
+    }
+    result
+}
+
+/// Lowercase and join words with '-', dropping everything else
+fn slugify(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_alphanumeric() {
+            result.extend(c.to_lowercase());
+        } else if !result.is_empty() && !result.ends_with('-') {
+            result.push('-');
+        }
+    }
+    result.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn ctx(name: &str) -> TemplateContext<'_> {
+        TemplateContext {
+            name,
+            counter: 7,
+            parent: "Urlaub",
+            size: 2048,
+            modified: None,
+            tags: None,
+        }
+    }
+
+    #[test]
+    fn test_basic_tokens() {
+        let template = Template::parse("{parent}_{stem}_{n:03}.{ext}").unwrap();
+        assert_eq!(template.render(&ctx("photo.jpg")), "Urlaub_photo_007.jpg");
+
+        let template = Template::parse("{name} ({size})").unwrap();
+        assert_eq!(template.render(&ctx("a.txt")), "a.txt (2048)");
+    }
+
+    #[test]
+    fn test_filters() {
+        let template = Template::parse("{stem|lower}.{ext|upper}").unwrap();
+        assert_eq!(template.render(&ctx("My Photo.jpg")), "my photo.JPG");
+
+        let template = Template::parse("{stem|slug}.{ext}").unwrap();
+        assert_eq!(
+            template.render(&ctx("  My  Great Photo!.jpg")),
+            "my-great-photo.jpg"
+        );
+
+        let template = Template::parse("{stem|lower|title}").unwrap();
+        assert_eq!(template.render(&ctx("HELLO world.txt")), "Hello World");
+    }
+
+    #[test]
+    fn test_date_token() {
+        // 2024-12-01 noon UTC, expected in local time as the token uses it
+        let time = UNIX_EPOCH + Duration::from_secs(20058 * 86400 + 12 * 3600);
+        let local = DateTime::<Local>::from(time);
+        let mut context = ctx("photo.jpg");
+        context.modified = Some(time);
+
+        let template = Template::parse("{date:%Y-%m-%d}_{stem}.{ext}").unwrap();
+        assert_eq!(
+            template.render(&context),
+            format!("{}_photo.jpg", local.format("%Y-%m-%d"))
+        );
+
+        let template = Template::parse("{date}").unwrap();
+        assert_eq!(
+            template.render(&context),
+            local.format("%Y%m%d").to_string()
+        );
+
+        // No date: zeroed placeholder in the shape of the format
+        let template = Template::parse("{date:%Y-%m-%d_%H%M}").unwrap();
+        assert_eq!(template.render(&ctx("photo.jpg")), "0000-00-00_0000");
+    }
+
+    #[test]
+    fn test_tag_tokens() {
+        let tags = AudioTags {
+            artist: Some("AC/DC".to_string()),
+            title: Some("Thunderstruck".to_string()),
+            track: Some(1),
+            ..AudioTags::default()
+        };
+        let mut context = ctx("track01.mp3");
+        context.tags = Some(&tags);
+
+        let template = Template::parse("{track:02} - {artist} - {title}.{ext}").unwrap();
+        assert!(template.uses_tags());
+        assert!(template.missing_tags(&context).is_empty());
+        assert_eq!(template.render(&context), "01 - AC-DC - Thunderstruck.mp3");
+
+        let template = Template::parse("{artist} - {album} ({year})").unwrap();
+        assert_eq!(template.missing_tags(&context), vec!["album", "year"]);
+        assert_eq!(
+            template.missing_tags(&ctx("x.mp3")),
+            vec!["artist", "album", "year"]
+        );
+
+        // Each missing tag is named once
+        let template = Template::parse("{artist}/{album}/{artist} - {title}").unwrap();
+        assert_eq!(
+            template.missing_tags(&ctx("x.mp3")),
+            vec!["artist", "album", "title"]
+        );
+
+        assert!(!Template::parse("{stem}").unwrap().uses_tags());
+    }
+
+    #[test]
+    fn test_escaped_braces_and_no_extension() {
+        let template = Template::parse("{{{stem}}}{ext}").unwrap();
+        assert_eq!(template.render(&ctx("README")), "{README}");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{stem").is_err());
+        assert!(Template::parse("stem}").is_err());
+        assert!(Template::parse("{unknown}").is_err());
+        assert!(Template::parse("{stem|nope}").is_err());
+        assert!(Template::parse("{n:abc}").is_err());
+        assert!(Template::parse("{date:%Q}").is_err());
+    }
+}
@@ -6,7 +6,8 @@ use ratatui::{
     Frame,
 };
 
-use crate::app::{App, DatePosition, DialogState, FocusedPanel, RenameMode};
+use crate::app::{App, DialogState, FocusedPanel, PositionAction, RenameMode, SanitizeProfile};
+use crate::config::Config;
 
 // btop-inspired color scheme
 const BORDER_COLOR: Color = Color::Cyan;
@@ -27,6 +28,7 @@ const DIALOG_BG: Color = Color::Rgb(30, 34, 42);
 const SUCCESS_COLOR: Color = Color::LightGreen;
 const ERROR_COLOR: Color = Color::LightRed;
 const WARNING_COLOR: Color = Color::Yellow;
+const MISMATCH_COLOR: Color = Color::LightMagenta;
 const MODE_COLOR: Color = Color::Magenta;
 const SIZE_COLOR: Color = Color::DarkGray;
 
@@ -50,7 +52,7 @@ pub fn draw_ui(frame: &mut Frame, app: &App) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(8),    // Files panel
-            Constraint::Length(6), // Operation panel
+            Constraint::Length(8), // Operation panel
             Constraint::Min(5),    // Preview panel
             Constraint::Length(3), // Help bar
         ])
@@ -73,7 +75,8 @@ pub fn draw_ui(frame: &mut Frame, app: &App) {
 
 /// Draw the files panel
 fn draw_files_panel(frame: &mut Frame, app: &App, area: Rect) {
-    let is_focused = app.focused_panel == FocusedPanel::Files;
+    let is_filter_focused = app.focused_panel == FocusedPanel::FilterField;
+    let is_focused = app.focused_panel == FocusedPanel::Files || is_filter_focused;
     let border_color = if is_focused {
         BORDER_COLOR_FOCUSED
     } else {
@@ -81,7 +84,25 @@ fn draw_files_panel(frame: &mut Frame, app: &App, area: Rect) {
     };
 
     let sort_indicator = app.sort_order.short_indicator();
-    let title = format!(" Dateien ({}) {} ", app.directory.display(), sort_indicator);
+    let mut title = if app.filter.is_empty() {
+        format!(" Dateien ({}) {} ", app.directory.display(), sort_indicator)
+    } else {
+        format!(
+            " Dateien ({}) {} [{} von {}] ",
+            app.directory.display(),
+            sort_indicator,
+            app.files.len(),
+            app.all_files.len()
+        )
+    };
+    match app.config.max_depth {
+        0 => title.push_str("[rekursiv] "),
+        1 => {}
+        depth => title.push_str(&format!("[Tiefe {}] ", depth)),
+    }
+    if app.config.include_dirs {
+        title.push_str("[+Ordner] ");
+    }
     let block = Block::default()
         .title(title)
         .title_style(Style::default().fg(TITLE_COLOR).bold())
@@ -89,9 +110,19 @@ fn draw_files_panel(frame: &mut Frame, app: &App, area: Rect) {
         .border_style(Style::default().fg(border_color))
         .style(Style::default());
 
-    let inner_area = block.inner(area);
+    let mut inner_area = block.inner(area);
     frame.render_widget(block, area);
 
+    // Filter line above the list while editing or when a filter is set
+    if is_filter_focused || !app.filter_input.is_empty() {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(inner_area);
+        draw_filter_line(frame, app, is_filter_focused, chunks[0]);
+        inner_area = chunks[1];
+    }
+
     if app.files.is_empty() {
         let empty_msg =
             Paragraph::new("Keine Dateien gefunden").style(Style::default().fg(TEXT_DIM));
@@ -124,7 +155,7 @@ fn draw_files_panel(frame: &mut Frame, app: &App, area: Rect) {
             };
 
             let suffix = if file.is_dir { "/" } else { "" };
-            let display_name = format!("{}{}", file.name, suffix);
+            let display_name = format!("{}{}", file.relative_name(), suffix);
 
             // Truncate name if too long
             let truncated_name = if display_name.len() > available_width {
@@ -166,9 +197,7 @@ fn draw_files_panel(frame: &mut Frame, app: &App, area: Rect) {
     let total_items = items.len();
     let selected = app.selected_index;
 
-    let start = if total_items <= visible_height {
-        0
-    } else if selected < visible_height / 2 {
+    let start = if total_items <= visible_height || selected < visible_height / 2 {
         0
     } else if selected > total_items - visible_height / 2 {
         total_items.saturating_sub(visible_height)
@@ -182,6 +211,41 @@ fn draw_files_panel(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(list, inner_area);
 }
 
+/// Helper to draw the filter input with its error, if any
+fn draw_filter_line(frame: &mut Frame, app: &App, is_focused: bool, area: Rect) {
+    let label_style = if is_focused {
+        Style::default().fg(INPUT_COLOR).bold()
+    } else {
+        Style::default().fg(TEXT_DIM)
+    };
+
+    let mut spans = vec![
+        Span::styled(" Filter: ", label_style),
+        Span::styled(&app.filter_input, Style::default().fg(TEXT_COLOR)),
+    ];
+    if is_focused {
+        spans.push(Span::styled(
+            "_",
+            Style::default()
+                .fg(INPUT_COLOR)
+                .add_modifier(Modifier::SLOW_BLINK),
+        ));
+    }
+    if let Some(err) = &app.filter_error {
+        spans.push(Span::styled(
+            format!("  {}", err),
+            Style::default().fg(ERROR_COLOR),
+        ));
+    } else if is_focused && app.filter_input.is_empty() {
+        spans.push(Span::styled(
+            "  z.B. IMG_* re:^\\d ext:jpg,png size:1M.. date:2024-01-01..2024-06-30 type:file",
+            Style::default().fg(TEXT_DIM),
+        ));
+    }
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
 /// Draw the operation panel
 fn draw_operation_panel(frame: &mut Frame, app: &App, area: Rect) {
     let is_search_focused = app.focused_panel == FocusedPanel::SearchField;
@@ -210,6 +274,7 @@ fn draw_operation_panel(frame: &mut Frame, app: &App, area: Rect) {
             Constraint::Length(1), // Mode label
             Constraint::Length(1), // Search/Pattern or info
             Constraint::Length(1), // Replace or action toggle or empty
+            Constraint::Length(1), // Pipeline steps
         ])
         .margin(1)
         .split(inner_area);
@@ -222,9 +287,19 @@ fn draw_operation_panel(frame: &mut Frame, app: &App, area: Rect) {
             Style::default().fg(MODE_COLOR).bold(),
         ),
         Span::styled("  (m: wechseln)", Style::default().fg(TEXT_DIM)),
+        Span::styled(
+            format!(
+                "  Schritt {}/{}",
+                app.active_step + 1,
+                app.steps.len().max(1)
+            ),
+            Style::default().fg(TEXT_DIM),
+        ),
     ]);
     frame.render_widget(Paragraph::new(mode_line), inner_chunks[0]);
 
+    draw_pipeline_line(frame, app, inner_chunks[3]);
+
     // Mode-specific content
     match app.rename_mode {
         RenameMode::SearchReplace => {
@@ -238,6 +313,17 @@ fn draw_operation_panel(frame: &mut Frame, app: &App, area: Rect) {
                 "Ersetze:",
             );
         }
+        RenameMode::Media => {
+            draw_search_replace_fields(
+                frame,
+                app,
+                is_search_focused,
+                is_replace_focused,
+                &inner_chunks,
+                "Serie:",
+                "Film:",
+            );
+        }
         RenameMode::Regex => {
             draw_search_replace_fields(
                 frame,
@@ -248,6 +334,23 @@ fn draw_operation_panel(frame: &mut Frame, app: &App, area: Rect) {
                 "Regex:",
                 "Ersetze:",
             );
+            let flags_line = Line::from(vec![
+                Span::styled("Flags: ", Style::default().fg(TEXT_DIM)),
+                Span::styled(
+                    format!("[{}]", app.regex_flags.display()),
+                    Style::default().fg(INPUT_COLOR).bold(),
+                ),
+                Span::styled(" (i/l)  Treffer: ", Style::default().fg(TEXT_DIM)),
+                Span::styled(
+                    format!("[{}]", app.regex_flags.occurrence_name()),
+                    Style::default().fg(INPUT_COLOR).bold(),
+                ),
+                Span::styled(" (t)", Style::default().fg(TEXT_DIM)),
+            ]);
+            frame.render_widget(
+                Paragraph::new(flags_line).alignment(ratatui::layout::Alignment::Right),
+                inner_chunks[1],
+            );
             // Show regex error if any
             if let Some(err) = &app.regex_error {
                 let error_line = Line::from(Span::styled(
@@ -259,14 +362,71 @@ fn draw_operation_panel(frame: &mut Frame, app: &App, area: Rect) {
             }
         }
         RenameMode::Numbering => {
+            let cursor = |focused: bool| {
+                if focused {
+                    Span::styled(
+                        "_",
+                        Style::default()
+                            .fg(INPUT_COLOR)
+                            .add_modifier(Modifier::SLOW_BLINK),
+                    )
+                } else {
+                    Span::raw("")
+                }
+            };
+            let label_style = |focused: bool| {
+                if focused {
+                    Style::default().fg(INPUT_COLOR).bold()
+                } else {
+                    Style::default().fg(TEXT_DIM)
+                }
+            };
+
+            let pattern_line = Line::from(vec![
+                Span::styled("Muster:  ", label_style(is_search_focused)),
+                Span::styled(&app.search_input, Style::default().fg(TEXT_COLOR)),
+                cursor(is_search_focused),
+                Span::styled("  Format:", Style::default().fg(TEXT_DIM)),
+                Span::styled(
+                    format!(" [{}]", app.number_format.display_name()),
+                    Style::default().fg(INPUT_COLOR).bold(),
+                ),
+                Span::styled(" (t)  Zaehler:", Style::default().fg(TEXT_DIM)),
+                Span::styled(
+                    format!(" [{}]", app.number_group.display_name()),
+                    Style::default().fg(INPUT_COLOR).bold(),
+                ),
+                Span::styled(" (g)", Style::default().fg(TEXT_DIM)),
+            ]);
+            frame.render_widget(Paragraph::new(pattern_line), inner_chunks[1]);
+
+            let second_line = if app.renumber {
+                Line::from(vec![
+                    Span::styled("Versatz: ", label_style(is_replace_focused)),
+                    Span::styled(&app.replace_input, Style::default().fg(TEXT_COLOR)),
+                    cursor(is_replace_focused),
+                    Span::styled(
+                        "  [Umnummerieren] (r: aus)  Muster ### = Breite, leer = Breite behalten",
+                        Style::default().fg(TEXT_DIM),
+                    ),
+                ])
+            } else {
+                Line::from(Span::styled(
+                    "Nutze # fuer Ziffern: photo_### -> photo_001  (r: vorhandene Nummer umnummerieren)",
+                    Style::default().fg(TEXT_DIM).italic(),
+                ))
+            };
+            frame.render_widget(Paragraph::new(second_line), inner_chunks[2]);
+        }
+        RenameMode::PadNumbers => {
             let label_style = if is_search_focused {
                 Style::default().fg(INPUT_COLOR).bold()
             } else {
                 Style::default().fg(TEXT_DIM)
             };
 
-            let pattern_line = Line::from(vec![
-                Span::styled("Muster:  ", label_style),
+            let width_line = Line::from(vec![
+                Span::styled("Breite:  ", label_style),
                 Span::styled(&app.search_input, Style::default().fg(TEXT_COLOR)),
                 if is_search_focused {
                     Span::styled(
@@ -278,13 +438,103 @@ fn draw_operation_panel(frame: &mut Frame, app: &App, area: Rect) {
                 } else {
                     Span::raw("")
                 },
+                Span::styled(
+                    "  leer = automatisch (groesste Nummer)",
+                    Style::default().fg(TEXT_DIM),
+                ),
             ]);
-            frame.render_widget(Paragraph::new(pattern_line), inner_chunks[1]);
+            frame.render_widget(Paragraph::new(width_line), inner_chunks[1]);
 
-            let hint_line = Line::from(Span::styled(
-                "Nutze # fuer Ziffern: photo_### -> photo_001",
-                Style::default().fg(TEXT_DIM).italic(),
-            ));
+            let target_line = Line::from(vec![
+                Span::styled("Nummern: ", Style::default().fg(TEXT_DIM)),
+                Span::styled(
+                    format!("[{}]", app.pad_target.display_name()),
+                    Style::default().fg(INPUT_COLOR).bold(),
+                ),
+                Span::styled(
+                    "  (t: wechseln)  page7 -> page007",
+                    Style::default().fg(TEXT_DIM),
+                ),
+            ]);
+            frame.render_widget(Paragraph::new(target_line), inner_chunks[2]);
+        }
+        RenameMode::Template | RenameMode::AudioTags | RenameMode::Organize => {
+            let label_style = if is_search_focused {
+                Style::default().fg(INPUT_COLOR).bold()
+            } else {
+                Style::default().fg(TEXT_DIM)
+            };
+            let (label, hint) = match app.rename_mode {
+                RenameMode::Template => (
+                    "Template: ",
+                    "{stem} {ext} {name} {n:03} {parent} {size} {date:%Y-%m-%d}  |lower |upper |title |slug",
+                ),
+                RenameMode::Organize => (
+                    "Ziel:     ",
+                    "/ trennt Ordner, die angelegt werden: {date:%Y}/{ext}/{name}  + alle Template-Platzhalter",
+                ),
+                _ => (
+                    "Muster:   ",
+                    "{artist} {album} {title} {track:02} {year}  + alle Template-Platzhalter",
+                ),
+            };
+
+            let template_line = Line::from(vec![
+                Span::styled(label, label_style),
+                Span::styled(&app.search_input, Style::default().fg(TEXT_COLOR)),
+                if is_search_focused {
+                    Span::styled(
+                        "_",
+                        Style::default()
+                            .fg(INPUT_COLOR)
+                            .add_modifier(Modifier::SLOW_BLINK),
+                    )
+                } else {
+                    Span::raw("")
+                },
+            ]);
+            frame.render_widget(Paragraph::new(template_line), inner_chunks[1]);
+
+            let hint_line = Line::from(Span::styled(hint, Style::default().fg(TEXT_DIM).italic()));
+            frame.render_widget(Paragraph::new(hint_line), inner_chunks[2]);
+        }
+        RenameMode::Script => {
+            let label_style = if is_search_focused {
+                Style::default().fg(INPUT_COLOR).bold()
+            } else {
+                Style::default().fg(TEXT_DIM)
+            };
+
+            let script_line = Line::from(vec![
+                Span::styled("Skript:  ", label_style),
+                Span::styled(&app.search_input, Style::default().fg(TEXT_COLOR)),
+                if is_search_focused {
+                    Span::styled(
+                        "_",
+                        Style::default()
+                            .fg(INPUT_COLOR)
+                            .add_modifier(Modifier::SLOW_BLINK),
+                    )
+                } else {
+                    Span::raw("")
+                },
+                Span::styled(
+                    "  name stem ext size mtime counter -> neuer Name",
+                    Style::default().fg(TEXT_DIM),
+                ),
+            ]);
+            frame.render_widget(Paragraph::new(script_line), inner_chunks[1]);
+
+            let scripts = crate::script::list_scripts();
+            let hint = if scripts.is_empty() {
+                match Config::scripts_dir() {
+                    Some(dir) => format!("Keine Skripte in {}", dir.display()),
+                    None => "Kein Konfigurationsverzeichnis".to_string(),
+                }
+            } else {
+                format!("Verfuegbar: {}", scripts.join(", "))
+            };
+            let hint_line = Line::from(Span::styled(hint, Style::default().fg(TEXT_DIM).italic()));
             frame.render_widget(Paragraph::new(hint_line), inner_chunks[2]);
         }
         RenameMode::Prefix | RenameMode::Suffix => {
@@ -325,36 +575,268 @@ fn draw_operation_panel(frame: &mut Frame, app: &App, area: Rect) {
             ]);
             frame.render_widget(Paragraph::new(action_line), inner_chunks[2]);
         }
+        RenameMode::Extension => {
+            let label_style = if is_search_focused {
+                Style::default().fg(INPUT_COLOR).bold()
+            } else {
+                Style::default().fg(TEXT_DIM)
+            };
+
+            let input_line = Line::from(vec![
+                Span::styled("Endung:  ", label_style),
+                Span::styled(&app.search_input, Style::default().fg(TEXT_COLOR)),
+                if is_search_focused {
+                    Span::styled(
+                        "_",
+                        Style::default()
+                            .fg(INPUT_COLOR)
+                            .add_modifier(Modifier::SLOW_BLINK),
+                    )
+                } else {
+                    Span::raw("")
+                },
+            ]);
+            frame.render_widget(Paragraph::new(input_line), inner_chunks[1]);
+
+            let hint = if app.extension_action.uses_value() {
+                "  (t: wechseln)  z.B. png, tar.gz"
+            } else {
+                "  (t: wechseln)  Aliase: extension_aliases in der Config"
+            };
+            let action_line = Line::from(vec![
+                Span::styled("Aktion:  ", Style::default().fg(TEXT_DIM)),
+                Span::styled(
+                    format!("[{}]", app.extension_action.display_name()),
+                    Style::default().fg(INPUT_COLOR).bold(),
+                ),
+                Span::styled(hint, Style::default().fg(TEXT_DIM)),
+            ]);
+            frame.render_widget(Paragraph::new(action_line), inner_chunks[2]);
+        }
+        RenameMode::Sanitize => {
+            let profile_line = Line::from(vec![
+                Span::styled("Profil:  ", Style::default().fg(TEXT_DIM)),
+                Span::styled(
+                    format!("[{}]", app.sanitize_profile.display_name()),
+                    Style::default().fg(INPUT_COLOR).bold(),
+                ),
+                Span::styled("  (t: wechseln)", Style::default().fg(TEXT_DIM)),
+            ]);
+            frame.render_widget(Paragraph::new(profile_line), inner_chunks[1]);
+
+            let hint = match app.sanitize_profile {
+                SanitizeProfile::Posix => "Nur A-Z a-z 0-9 . _ -, Umlaute werden umschrieben",
+                SanitizeProfile::Windows => {
+                    "Ohne <>:\"|?*, reservierte Namen (CON, ...), Punkte am Ende"
+                }
+                SanitizeProfile::Fat => "Wie Windows, zusaetzlich ohne + , ; = [ ]",
+                SanitizeProfile::Url => "Kleinbuchstaben, Woerter mit - getrennt",
+            };
+            let hint_line = Line::from(Span::styled(hint, Style::default().fg(TEXT_DIM).italic()));
+            frame.render_widget(Paragraph::new(hint_line), inner_chunks[2]);
+        }
+        RenameMode::Position => {
+            let cursor = |focused: bool| {
+                if focused {
+                    Span::styled(
+                        "_",
+                        Style::default()
+                            .fg(INPUT_COLOR)
+                            .add_modifier(Modifier::SLOW_BLINK),
+                    )
+                } else {
+                    Span::raw("")
+                }
+            };
+            let label_style = |focused: bool| {
+                if focused {
+                    Style::default().fg(INPUT_COLOR).bold()
+                } else {
+                    Style::default().fg(TEXT_DIM)
+                }
+            };
+            let (first_label, second_label, hint) = match app.position_action {
+                PositionAction::Insert => (
+                    "Text:    ",
+                    "Position:",
+                    "  0 = Anfang, 3 = nach 3 Zeichen, -0 = Ende, -2 = 2 vor Ende",
+                ),
+                PositionAction::Delete | PositionAction::Keep => (
+                    "Bereich: ",
+                    "",
+                    "z.B. 0..4, -3.. oder ..5 (Zeichen, ohne Endung)",
+                ),
+                PositionAction::CutAfter => {
+                    ("Trenner: ", "Vorkommen:", "  1 = erstes, -1 = letztes")
+                }
+            };
+
+            let first_line = Line::from(vec![
+                Span::styled(first_label, label_style(is_search_focused)),
+                Span::styled(&app.search_input, Style::default().fg(TEXT_COLOR)),
+                cursor(is_search_focused),
+                Span::styled("  Aktion:", Style::default().fg(TEXT_DIM)),
+                Span::styled(
+                    format!(" [{}]", app.position_action.display_name()),
+                    Style::default().fg(INPUT_COLOR).bold(),
+                ),
+                Span::styled("  (t: wechseln)", Style::default().fg(TEXT_DIM)),
+            ]);
+            frame.render_widget(Paragraph::new(first_line), inner_chunks[1]);
+
+            let second_line = if app.position_action.uses_second_input() {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{} ", second_label),
+                        label_style(is_replace_focused),
+                    ),
+                    Span::styled(&app.replace_input, Style::default().fg(TEXT_COLOR)),
+                    cursor(is_replace_focused),
+                    Span::styled(hint, Style::default().fg(TEXT_DIM)),
+                ])
+            } else {
+                Line::from(Span::styled(hint, Style::default().fg(TEXT_DIM).italic()))
+            };
+            frame.render_widget(Paragraph::new(second_line), inner_chunks[2]);
+        }
         RenameMode::DateInsert => {
-            let example = match app.date_position {
-                DatePosition::Prefix => "photo.jpg -> 20241130_photo.jpg",
-                DatePosition::Suffix => "photo.jpg -> photo_20241130.jpg",
-                DatePosition::Replace => "photo.jpg -> 20241130.jpg",
+            let cursor = |focused: bool| {
+                if focused {
+                    Span::styled(
+                        "_",
+                        Style::default()
+                            .fg(INPUT_COLOR)
+                            .add_modifier(Modifier::SLOW_BLINK),
+                    )
+                } else {
+                    Span::raw("")
+                }
+            };
+            let label_style = |focused: bool| {
+                if focused {
+                    Style::default().fg(INPUT_COLOR).bold()
+                } else {
+                    Style::default().fg(TEXT_DIM)
+                }
             };
 
-            let info_line = Line::from(vec![
-                Span::styled("Format:  ", Style::default().fg(TEXT_DIM)),
-                Span::styled("YYYYMMDD", Style::default().fg(INPUT_COLOR).bold()),
-                Span::styled("  (Aenderungsdatum)", Style::default().fg(TEXT_DIM)),
+            let format_line = Line::from(vec![
+                Span::styled("Format:  ", label_style(is_search_focused)),
+                Span::styled(&app.search_input, Style::default().fg(TEXT_COLOR)),
+                cursor(is_search_focused),
+                Span::styled("  Quelle:", Style::default().fg(TEXT_DIM)),
+                Span::styled(
+                    format!(" [{}]", app.date_source.display_name()),
+                    Style::default().fg(INPUT_COLOR).bold(),
+                ),
+                Span::styled("  (d: wechseln)", Style::default().fg(TEXT_DIM)),
             ]);
-            frame.render_widget(Paragraph::new(info_line), inner_chunks[1]);
+            frame.render_widget(Paragraph::new(format_line), inner_chunks[1]);
 
-            let position_line = Line::from(vec![
-                Span::styled("Position:", Style::default().fg(TEXT_DIM)),
+            let separator_line = Line::from(vec![
+                Span::styled("Trenner: ", label_style(is_replace_focused)),
+                Span::styled(&app.replace_input, Style::default().fg(TEXT_COLOR)),
+                cursor(is_replace_focused),
+                Span::styled("  Position:", Style::default().fg(TEXT_DIM)),
                 Span::styled(
                     format!(" [{}]", app.date_position.display_name()),
                     Style::default().fg(INPUT_COLOR).bold(),
                 ),
-                Span::styled("  (t: wechseln)  ", Style::default().fg(TEXT_DIM)),
-                Span::styled(example, Style::default().fg(TEXT_DIM).italic()),
+                Span::styled(
+                    "  (t: wechseln)  z.B. %Y-%m-%d_%H%M",
+                    Style::default().fg(TEXT_DIM),
+                ),
+            ]);
+            frame.render_widget(Paragraph::new(separator_line), inner_chunks[2]);
+        }
+        RenameMode::Hash => {
+            let cursor = |focused: bool| {
+                if focused {
+                    Span::styled(
+                        "_",
+                        Style::default()
+                            .fg(INPUT_COLOR)
+                            .add_modifier(Modifier::SLOW_BLINK),
+                    )
+                } else {
+                    Span::raw("")
+                }
+            };
+            let label_style = |focused: bool| {
+                if focused {
+                    Style::default().fg(INPUT_COLOR).bold()
+                } else {
+                    Style::default().fg(TEXT_DIM)
+                }
+            };
+
+            let length_line = Line::from(vec![
+                Span::styled("Laenge:  ", label_style(is_search_focused)),
+                Span::styled(&app.search_input, Style::default().fg(TEXT_COLOR)),
+                cursor(is_search_focused),
+                Span::styled("  Algorithmus:", Style::default().fg(TEXT_DIM)),
+                Span::styled(
+                    format!(" [{}]", app.hash_algorithm.display_name()),
+                    Style::default().fg(INPUT_COLOR).bold(),
+                ),
+                Span::styled(
+                    "  (h: wechseln)  leer = volle Laenge",
+                    Style::default().fg(TEXT_DIM),
+                ),
             ]);
-            frame.render_widget(Paragraph::new(position_line), inner_chunks[2]);
+            frame.render_widget(Paragraph::new(length_line), inner_chunks[1]);
+
+            let mut separator_line = vec![
+                Span::styled("Trenner: ", label_style(is_replace_focused)),
+                Span::styled(&app.replace_input, Style::default().fg(TEXT_COLOR)),
+                cursor(is_replace_focused),
+                Span::styled("  Position:", Style::default().fg(TEXT_DIM)),
+                Span::styled(
+                    format!(" [{}]", app.hash_position.display_name()),
+                    Style::default().fg(INPUT_COLOR).bold(),
+                ),
+                Span::styled("  (t: wechseln)", Style::default().fg(TEXT_DIM)),
+            ];
+            if let Some(job) = &app.hash_job {
+                separator_line.push(Span::styled(
+                    format!(
+                        "  Berechne: {}/{} Dateien ({}%)",
+                        job.done_files,
+                        job.total_files,
+                        job.percent()
+                    ),
+                    Style::default().fg(INPUT_COLOR),
+                ));
+            }
+            frame.render_widget(Paragraph::new(Line::from(separator_line)), inner_chunks[2]);
         }
-        RenameMode::Uppercase | RenameMode::Lowercase | RenameMode::TitleCase => {
+        RenameMode::FixExtension
+        | RenameMode::Uppercase
+        | RenameMode::Lowercase
+        | RenameMode::TitleCase
+        | RenameMode::SnakeCase
+        | RenameMode::KebabCase
+        | RenameMode::CamelCase
+        | RenameMode::PascalCase
+        | RenameMode::DotCase
+        | RenameMode::SentenceCase => {
+            let title_info = format!(
+                "Jedes Wort Beginnt Gross, ausser Fuellwoertern (Sprache: {}, title_case in der Config)",
+                app.config.title_case.language.display_name()
+            );
             let info_text = match app.rename_mode {
+                RenameMode::FixExtension => {
+                    "Endungen werden anhand des Dateiinhalts (PNG, JPEG, PDF, ZIP, ...) korrigiert"
+                }
                 RenameMode::Uppercase => "Alle Dateinamen werden in GROSSBUCHSTABEN umgewandelt",
                 RenameMode::Lowercase => "Alle Dateinamen werden in kleinbuchstaben umgewandelt",
-                RenameMode::TitleCase => "Jedes Wort Beginnt Mit Grossbuchstaben",
+                RenameMode::TitleCase => &title_info,
+                RenameMode::SnakeCase => "myHTTPServer2.rs -> my_http_server_2.rs",
+                RenameMode::KebabCase => "myHTTPServer2.rs -> my-http-server-2.rs",
+                RenameMode::CamelCase => "my_http_server_2.rs -> myHttpServer2.rs",
+                RenameMode::PascalCase => "my_http_server_2.rs -> MyHttpServer2.rs",
+                RenameMode::DotCase => "myHTTPServer2.rs -> my.http.server.2.rs",
+                RenameMode::SentenceCase => "my_http_server_2.rs -> My http server 2.rs",
                 _ => "",
             };
 
@@ -373,6 +855,56 @@ fn draw_operation_panel(frame: &mut Frame, app: &App, area: Rect) {
     }
 }
 
+/// Helper to draw the list of pipeline steps with the active one highlighted
+fn draw_pipeline_line(frame: &mut Frame, app: &App, area: Rect) {
+    // A rule set replaces the pipeline
+    if let Some(rule_set) = app.active_rule_set() {
+        let mut spans = vec![
+            Span::styled("Regelsatz: ", Style::default().fg(TEXT_DIM)),
+            Span::styled(
+                format!("[{}]", rule_set.name),
+                Style::default().fg(MODE_COLOR).bold(),
+            ),
+        ];
+        for (i, rule) in rule_set.rules.iter().enumerate() {
+            spans.push(Span::styled(
+                format!(" {}. {}", i + 1, rule.label()),
+                Style::default().fg(TEXT_COLOR),
+            ));
+        }
+        if !rule_set.fallback.is_empty() {
+            spans.push(Span::styled(" sonst", Style::default().fg(TEXT_COLOR)));
+        }
+        spans.push(Span::styled(
+            "  (R: wechseln, Pipeline inaktiv)",
+            Style::default().fg(TEXT_DIM),
+        ));
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+        return;
+    }
+
+    let mut spans = vec![Span::styled("Pipeline:", Style::default().fg(TEXT_DIM))];
+
+    for (i, step) in app.steps.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(" >", Style::default().fg(ARROW_COLOR)));
+        }
+        let label = format!(" {}. {}", i + 1, step.mode.display_name());
+        if i == app.active_step {
+            spans.push(Span::styled(label, Style::default().fg(MODE_COLOR).bold()));
+        } else {
+            spans.push(Span::styled(label, Style::default().fg(TEXT_COLOR)));
+        }
+    }
+
+    spans.push(Span::styled(
+        "  (+/-: Schritt, [/]: waehlen, </>: verschieben)",
+        Style::default().fg(TEXT_DIM),
+    ));
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
 /// Helper to draw search/replace input fields
 fn draw_search_replace_fields(
     frame: &mut Frame,
@@ -439,16 +971,17 @@ fn draw_preview_panel(frame: &mut Frame, app: &App, area: Rect) {
     let inner_area = block.inner(area);
     frame.render_widget(block, area);
 
-    // Show regex error if present
+    // Show regex/template error if present
     if let Some(err) = &app.regex_error {
-        let error_line = Paragraph::new(format!("Regex-Fehler: {}", err))
-            .style(Style::default().fg(ERROR_COLOR));
+        let error_line =
+            Paragraph::new(format!("Fehler: {}", err)).style(Style::default().fg(ERROR_COLOR));
         frame.render_widget(error_line, inner_area);
         return;
     }
 
     // For search/replace and regex mode, check if search is empty
-    if app.rename_mode.uses_search_replace() && app.search_input.is_empty() {
+    let uses_rules = app.rule_set.is_some();
+    if !uses_rules && app.rename_mode.uses_search_replace() && app.search_input.is_empty() {
         let hint = match app.rename_mode {
             RenameMode::Regex => "Gib ein Regex-Muster ein (z.B. IMG_(\\d+) -> photo_$1)",
             _ => "Gib einen Suchbegriff ein, um die Vorschau zu sehen",
@@ -459,13 +992,29 @@ fn draw_preview_panel(frame: &mut Frame, app: &App, area: Rect) {
     }
 
     // For numbering/prefix/suffix, check if pattern is empty
-    if matches!(
-        app.rename_mode,
-        RenameMode::Numbering | RenameMode::Prefix | RenameMode::Suffix
-    ) && app.search_input.is_empty()
+    let renumbering = app.rename_mode == RenameMode::Numbering && app.renumber;
+    if !uses_rules
+        && !renumbering
+        && matches!(
+            app.rename_mode,
+            RenameMode::Numbering
+                | RenameMode::Prefix
+                | RenameMode::Suffix
+                | RenameMode::Template
+                | RenameMode::AudioTags
+                | RenameMode::Organize
+        )
+        && app.search_input.is_empty()
     {
         let hint = match app.rename_mode {
             RenameMode::Numbering => "Gib ein Muster ein (z.B. photo_###)",
+            RenameMode::Template => {
+                "Gib ein Template ein (z.B. {date:%Y-%m-%d}_{stem|lower}_{n:03}.{ext})"
+            }
+            RenameMode::AudioTags => {
+                "Gib ein Muster ein (z.B. {track:02} - {artist} - {title}.{ext})"
+            }
+            RenameMode::Organize => "Gib ein Ziel ein (z.B. {date:%Y}/{date:%m}/{name})",
             RenameMode::Prefix => "Gib einen Prefix ein",
             RenameMode::Suffix => "Gib einen Suffix ein",
             _ => "",
@@ -475,7 +1024,12 @@ fn draw_preview_panel(frame: &mut Frame, app: &App, area: Rect) {
         return;
     }
 
-    let changes: Vec<&_> = app.previews.iter().filter(|p| p.will_change).collect();
+    // Changes and files that were flagged (e.g. missing tags, wrong extension)
+    let changes: Vec<&_> = app
+        .previews
+        .iter()
+        .filter(|p| p.will_change || p.error.is_some() || p.type_mismatch.is_some())
+        .collect();
 
     if changes.is_empty() {
         let hint = Paragraph::new("Keine Aenderungen").style(Style::default().fg(TEXT_DIM));
@@ -486,7 +1040,14 @@ fn draw_preview_panel(frame: &mut Frame, app: &App, area: Rect) {
     let items: Vec<ListItem> = changes
         .iter()
         .map(|preview| {
-            let line = Line::from(vec![
+            if let Some(error) = &preview.error {
+                return ListItem::new(Line::from(vec![
+                    Span::styled(&preview.original_name, Style::default().fg(TEXT_COLOR)),
+                    Span::styled("  !  ", Style::default().fg(WARNING_COLOR).bold()),
+                    Span::styled(error, Style::default().fg(WARNING_COLOR)),
+                ]));
+            }
+            let mut spans = vec![
                 Span::styled(
                     &preview.original_name,
                     Style::default()
@@ -498,8 +1059,22 @@ fn draw_preview_panel(frame: &mut Frame, app: &App, area: Rect) {
                     &preview.new_name,
                     Style::default().fg(NEW_NAME_COLOR).bold(),
                 ),
-            ]);
-            ListItem::new(line)
+            ];
+            // Detected content type vs current extension
+            if let Some(mismatch) = &preview.type_mismatch {
+                spans.push(Span::styled(
+                    format!("  [{}]", mismatch.describe()),
+                    Style::default().fg(MISMATCH_COLOR).bold(),
+                ));
+            }
+            // Rule of the rule set that renamed the file
+            if let Some(rule) = &preview.rule {
+                spans.push(Span::styled(
+                    format!("  [{}]", rule),
+                    Style::default().fg(MODE_COLOR),
+                ));
+            }
+            ListItem::new(Line::from(spans))
         })
         .collect();
 
@@ -524,16 +1099,36 @@ fn draw_help_bar(frame: &mut Frame, app: &App, area: Rect) {
                 ("j/k", "Nav"),
                 ("Space", "Ausw"),
                 ("a", "Alle"),
+                ("f", "Filter"),
+                ("D", "Ordner"),
                 ("m", "Modus"),
+                ("+/-", "Schritt"),
                 ("s", "Sort"),
             ];
+            if !app.config.rule_sets.is_empty() {
+                base.push(("R", "Regeln"));
+            }
             // Add 't' hint for modes with toggles
             if app.rename_mode.has_toggle() {
                 base.push(("t", "Toggle"));
             }
+            if app.rename_mode == RenameMode::DateInsert {
+                base.push(("d", "Quelle"));
+            }
+            if app.rename_mode == RenameMode::Hash {
+                base.push(("h", "Algorithmus"));
+            }
+            if app.rename_mode == RenameMode::Regex {
+                base.push(("i/l", "Flags"));
+            }
+            if app.rename_mode == RenameMode::Numbering {
+                base.push(("g", "Zaehler"));
+                base.push(("r", "Umnummerieren"));
+            }
             base.extend([
                 ("Tab", "Feld"),
                 ("Enter", "Run"),
+                ("e", "Editor"),
                 ("u", "Undo"),
                 ("?", "Hilfe"),
                 ("q", "Ende"),
@@ -552,6 +1147,7 @@ fn draw_help_bar(frame: &mut Frame, app: &App, area: Rect) {
             base.push(("F1", "Hilfe"));
             base
         }
+        FocusedPanel::FilterField => vec![("Enter", "Fertig"), ("Esc", "Zurueck"), ("F1", "Hilfe")],
     };
 
     let spans: Vec<Span> = help_text
@@ -650,15 +1246,44 @@ fn draw_help_dialog(frame: &mut Frame) {
         ("k / Pfeil hoch", "Vorherige Datei"),
         ("Space", "Datei auswaehlen/abwaehlen"),
         ("a", "Alle Dateien auswaehlen/abwaehlen"),
+        ("f", "Filter bearbeiten (z.B. *.jpg size:1M.. type:file)"),
+        ("D", "Ordner mit umbenennen an/aus"),
         ("", ""),
         ("", "--- Modi & Sortierung ---"),
         ("m", "Modus wechseln"),
         ("s", "Sortierung wechseln"),
-        ("t", "Aktion/Position wechseln"),
+        ("t", "Aktion/Position/Profil/Treffer/Zahlenformat wechseln"),
+        (
+            "d",
+            "Datumsquelle wechseln (mtime/ctime/Erstellung/EXIF/jetzt)",
+        ),
+        ("i / l", "Regex: Gross/klein ignorieren / mehrzeilig"),
+        ("g", "Zaehler pro Endung/Ordner/Tag (Nummerierung)"),
+        ("r", "Vorhandene Nummer umnummerieren (Nummerierung)"),
+        ("h", "Algorithmus wechseln (SHA-256/BLAKE3/CRC32)"),
+        ("", ""),
+        ("", "--- Pipeline ---"),
+        ("+ / -", "Schritt hinzufuegen/entfernen"),
+        ("[ / ]", "Vorherigen/naechsten Schritt bearbeiten"),
+        ("< / >", "Schritt nach vorne/hinten verschieben"),
+        ("R", "Regelsatz aus der Konfiguration statt Pipeline"),
         ("", ""),
         ("", "--- Modi ---"),
-        ("", "Suchen/Ersetzen, Regex, Nummerierung"),
-        ("", "Prefix, Suffix, Datum, GROSS, klein, Titel"),
+        (
+            "",
+            "Suchen/Ersetzen, Regex, Nummerierung, Nummern auffuellen",
+        ),
+        (
+            "",
+            "Prefix, Suffix, Position, Datum, Template, Audio-Tags, Skript, Ordnen",
+        ),
+        (
+            "",
+            "Serien/Filme, Endung, Typ-Erkennung, Pruefsumme, Bereinigen",
+        ),
+        ("", "GROSS, klein, Titel"),
+        ("", "snake_case, kebab-case, camelCase, PascalCase"),
+        ("", "dot.case, Satzschreibweise"),
         ("", ""),
         ("", "--- Navigation ---"),
         ("Tab", "Naechstes Panel"),
@@ -667,6 +1292,7 @@ fn draw_help_dialog(frame: &mut Frame) {
         ("", ""),
         ("", "--- Aktionen ---"),
         ("Enter", "Umbenennung ausfuehren"),
+        ("e", "Namen in $EDITOR bearbeiten"),
         ("u", "Letzte Umbenennung rueckgaengig"),
         ("?", "Hilfe anzeigen"),
         ("q", "Programm beenden"),